@@ -0,0 +1,74 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use zkdiff_core::{edit_distance, myers_diff, myers_diff_trace};
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+/// Share of the lines of file A changed in file B, in percent.
+const DENSITIES: [usize; 3] = [1, 10, 50];
+/// Largest trace `myers_diff_trace` is benchmarked with; the bigger cases would
+/// only measure the allocator.
+const MAX_TRACE_BYTES: usize = 512 << 20;
+
+/// File A of `len` distinct lines, and file B with about `density` percent of them
+/// replaced, deleted or followed by a new line. The same arguments always give the
+/// same files.
+fn files(len: usize, density: usize) -> (Vec<String>, Vec<String>) {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+
+    let a: Vec<String> = (0..len).map(|i| format!("line {} of file A", i)).collect();
+    let mut b = Vec::new();
+    for (i, line) in a.iter().enumerate() {
+        if next() % 100 >= density {
+            b.push(line.clone());
+            continue;
+        }
+        match next() % 3 {
+            0 => b.push(format!("line {} replaced in file B", i)),
+            1 => {}
+            _ => b.extend([line.clone(), format!("line inserted after {}", i)]),
+        }
+    }
+    (a, b)
+}
+
+/// Bytes `myers_diff_trace` holds at its peak: a copy of the furthest-reaching
+/// paths, one `i32` for each of the 2(N+M)+1 diagonals, for every round up to the
+/// edit distance.
+fn trace_peak_bytes(distance: usize, len_a: usize, len_b: usize) -> usize {
+    (distance + 1) * (2 * (len_a + len_b) + 1) * std::mem::size_of::<i32>()
+}
+
+fn bench_myers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("myers_diff");
+    group.sample_size(10);
+    for len in SIZES {
+        for density in DENSITIES {
+            let (a, b) = files(len, density);
+            let a: Vec<&str> = a.iter().map(String::as_str).collect();
+            let b: Vec<&str> = b.iter().map(String::as_str).collect();
+            let case = format!("{}_lines/{}pct", len, density);
+            let distance = edit_distance(&myers_diff(&a, &b));
+            let trace_bytes = trace_peak_bytes(distance, a.len(), b.len());
+            println!("{}: edit distance {}, trace peak {} KiB", case, distance, trace_bytes >> 10);
+
+            group.bench_with_input(BenchmarkId::new("linear", &case), &(&a, &b), |bench, (a, b)| {
+                bench.iter(|| myers_diff(black_box(a), black_box(b)))
+            });
+            if trace_bytes <= MAX_TRACE_BYTES {
+                group.bench_with_input(
+                    BenchmarkId::new("trace", &case),
+                    &(&a, &b),
+                    |bench, (a, b)| bench.iter(|| myers_diff_trace(black_box(a), black_box(b))),
+                );
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_myers);
+criterion_main!(benches);
@@ -0,0 +1,730 @@
+use crate::{
+    anchors_consistent, annotate_line_endings, attach_merkle_paths, comparison_keys,
+    create_diff_lines, create_proof_hash, dedup_line_contents, diff_hunks, edit_distance,
+    hash_content, hash_content_with, hide_redacted_line_numbers, histogram_diff, hunk_headers,
+    ignore_matched_lines, ignored_lines_consistent, keyword_hashes, keyword_redaction_ranges,
+    line_endings, mark_missing_newlines, mark_moves, merge_chunks, missing_final_newline,
+    myers_diff, patience_diff, pattern_matches_consistent, pattern_redaction_ranges, records,
+    redact_all_ranges, redacted_text, replayed_file_b, retain_operations, script_diff, similarity,
+    sync_anchors_consistent, token_line_edits, truncate_long_lines, Algorithm, DiffInput,
+    DiffOperation, DiffOutput, DiffStats, Edit, EditOp, FileDiff, FileInput, FileStatus,
+    Granularity, HashAlgorithm, LineRange,
+};
+use crate::hash::Hasher;
+use crate::myers::edits_from_matches;
+
+/// Runs the whole diff pipeline on an input whose hashes have already been
+/// checked, producing the output the guest commits, `proof_hash` included.
+///
+/// # Panics
+///
+/// Panics if a pattern redaction, the hunk anchors or the ignored lines were
+/// evaluated inconsistently for identical lines, if a line range reaches outside
+/// its file, if a precomputed edit script doesn't fit its line hashes, if an
+/// append-only input changes a line of file A or has line ranges, if the redacted
+/// text doesn't have the expected hash, if operations other than insertions and
+/// deletions are suppressed, or if all lines are redacted at char granularity.
+pub fn compute_diff(input: &DiffInput) -> DiffOutput {
+    let mut output = full_diff(input);
+    if input.options.stats_only {
+        return stats_only(output);
+    }
+    if input.options.dedup_lines {
+        dedup_lines(&mut output);
+    }
+    output
+}
+
+fn full_diff(input: &DiffInput) -> DiffOutput {
+    if !input.files.is_empty() {
+        return compute_tree_diff(input);
+    }
+    if let Some(script) = &input.script {
+        return script_diff(input, script);
+    }
+
+    let separator = input.options.record_separator;
+    if let Some(separator) = separator {
+        assert!(separator.is_ascii(), "Record separator must be an ASCII byte");
+        // line endings and the rebuilt file B only know newlines
+        assert!(
+            !input.options.normalize_eol && !input.options.include_unchanged,
+            "Record separators can't be combined with normalize_eol or include_unchanged"
+        );
+    }
+    let lines_a = records(&input.file_a_content, separator);
+    let lines_b = records(&input.file_b_content, separator);
+
+    let mut redaction_ranges = input.redaction_ranges.clone();
+    for redaction in &input.pattern_redactions {
+        assert!(
+            pattern_matches_consistent(redaction, &lines_a, &lines_b),
+            "Inconsistent matches for redaction pattern"
+        );
+        redaction_ranges.extend(pattern_redaction_ranges(redaction));
+    }
+    redaction_ranges.extend(keyword_redaction_ranges(&input.options, &lines_a, &lines_b));
+    if input.options.redact_all {
+        // character breakdowns keep the unchanged text of a line redacted whole
+        assert!(
+            input.options.granularity != Granularity::Char,
+            "Redacting all lines can't be combined with char granularity"
+        );
+        redaction_ranges.extend(redact_all_ranges(lines_a.len(), lines_b.len()));
+    }
+    if let Some(anchors) = &input.hunk_anchors {
+        assert!(
+            anchors_consistent(anchors, &lines_a),
+            "Inconsistent matches for hunk pattern"
+        );
+    }
+    if let Some(anchors) = &input.sync_anchors {
+        assert!(
+            sync_anchors_consistent(anchors, &lines_a, &lines_b),
+            "Inconsistent matches for anchor pattern"
+        );
+    }
+
+    let mut keys_a = comparison_keys(&input.file_a_content, &input.options);
+    let mut keys_b = comparison_keys(&input.file_b_content, &input.options);
+    if let Some(ignored) = &input.ignored_lines {
+        assert!(
+            ignored_lines_consistent(ignored, &lines_a, &lines_b),
+            "Inconsistent matches for ignore pattern"
+        );
+        ignore_matched_lines(&mut keys_a, &ignored.lines_a, separator);
+        ignore_matched_lines(&mut keys_b, &ignored.lines_b, separator);
+    }
+    let keys_a: Vec<&str> = keys_a.iter().map(String::as_str).collect();
+    let keys_b: Vec<&str> = keys_b.iter().map(String::as_str).collect();
+    let no_newline_a = missing_final_newline(&input.file_a_content, separator);
+    let no_newline_b = missing_final_newline(&input.file_b_content, separator);
+
+    // only the ranges are diffed; shifting the edits back to whole-file indices
+    // lets everything after this work in file coordinates
+    let (start_a, end_a) = range_bounds(input.options.range_a, keys_a.len());
+    let (start_b, end_b) = range_bounds(input.options.range_b, keys_b.len());
+    let (range_keys_a, range_keys_b) = (&keys_a[start_a..end_a], &keys_b[start_b..end_b]);
+    let edits: Vec<Edit> = if input.options.append_only {
+        assert!(
+            input.options.range_a.is_none() && input.options.range_b.is_none(),
+            "Line ranges can't be used in append-only mode"
+        );
+        assert!(
+            append_mismatch(&keys_a, &keys_b).is_none(),
+            "File B doesn't just append lines to file A"
+        );
+        append_edits(keys_a.len(), keys_b.len())
+    } else {
+        let algorithm = input.options.algorithm;
+        let diff = |a: &[&str], b: &[&str]| match input.options.tokenizer {
+            Some(tokenizer) => token_line_edits(algorithm, tokenizer, a, b),
+            None => line_edits(algorithm, a, b),
+        };
+        let segment_diff = |a: &[&str], b: &[&str]| {
+            if input.options.ignore_blank_lines {
+                blank_lines_ignored(a, b, diff)
+            } else {
+                diff(a, b)
+            }
+        };
+        let range_edits = match &input.sync_anchors {
+            Some(anchors) => {
+                // anchors are numbered in the whole file, the segments cut from the range
+                let in_range = |lines: &[usize], start: usize, end: usize| -> Vec<usize> {
+                    let mut indices: Vec<usize> = lines
+                        .iter()
+                        .filter(|&&n| n > start && n <= end)
+                        .map(|&n| n - 1 - start)
+                        .collect();
+                    indices.sort_unstable();
+                    indices.dedup();
+                    indices
+                };
+                let anchors_a = in_range(&anchors.lines_a, start_a, end_a);
+                let anchors_b = in_range(&anchors.lines_b, start_b, end_b);
+                anchored_edits(range_keys_a, range_keys_b, &anchors_a, &anchors_b, segment_diff)
+            }
+            None => segment_diff(range_keys_a, range_keys_b),
+        };
+        range_edits
+            .into_iter()
+            .map(|edit| Edit {
+                old_index: edit.old_index + start_a,
+                new_index: edit.new_index + start_b,
+                ..edit
+            })
+            .collect()
+    };
+
+    let mut diff_lines = create_diff_lines(
+        &edits,
+        &lines_a,
+        &lines_b,
+        &redaction_ranges,
+        &input.options,
+    );
+    if let Some(expected) = input.options.expected_redaction_hash {
+        assert!(
+            hash_content(&redacted_text(&diff_lines, &lines_a, &lines_b)) == expected,
+            "Redacted text doesn't have the expected hash"
+        );
+    }
+    if input.options.detect_moves {
+        mark_moves(&mut diff_lines, &keys_a, &keys_b);
+    }
+    // a full-file proof needs the terminators to rebuild file B byte for byte
+    if input.options.normalize_eol || input.options.include_unchanged {
+        annotate_line_endings(
+            &mut diff_lines,
+            &line_endings(&input.file_a_content),
+            &line_endings(&input.file_b_content),
+        );
+    }
+    mark_missing_newlines(
+        &mut diff_lines,
+        lines_a.len(),
+        lines_b.len(),
+        no_newline_a,
+        no_newline_b,
+    );
+    if !input.options.suppress.is_empty() {
+        let suppress = &input.options.suppress;
+        assert!(
+            suppress.iter().all(|op| matches!(op, DiffOperation::Insert | DiffOperation::Delete)),
+            "Only insertions and deletions can be suppressed"
+        );
+        retain_operations(&mut diff_lines, |op| !suppress.contains(op));
+    }
+    if let Some(max_len) = input.options.max_line_len {
+        truncate_long_lines(&mut diff_lines, max_len);
+    }
+    if input.options.include_unchanged {
+        let rebuilt = replayed_file_b(&diff_lines)
+            .is_some_and(|b| hash_content_with(input.options.hash, &b) == input.file_b_hash);
+        assert!(rebuilt, "The committed lines don't rebuild file B");
+    }
+    let (merkle_root_a, merkle_root_b) = if input.options.merkle {
+        let (root_a, root_b) = attach_merkle_paths(&mut diff_lines, &lines_a, &lines_b);
+        (Some(root_a), Some(root_b))
+    } else {
+        (None, None)
+    };
+    let hunk_headers = match &input.hunk_anchors {
+        Some(anchors) => hunk_headers(&diff_lines, anchors, &lines_a, &redaction_ranges),
+        None => Vec::new(),
+    };
+    let mut hunks = if input.options.hunks { diff_hunks(&diff_lines) } else { Vec::new() };
+    // last, since everything before places lines by their numbers
+    if input.options.redact_line_numbers {
+        hide_redacted_line_numbers(&mut diff_lines);
+        for hunk in &mut hunks {
+            hide_redacted_line_numbers(&mut hunk.lines);
+        }
+    }
+    let edit_distance = edit_distance(&edits);
+    let similarity = similarity(edit_distance, end_a - start_a, end_b - start_b);
+
+    let mut output = DiffOutput {
+        file_a_hash: input.file_a_hash,
+        file_b_hash: input.file_b_hash,
+        lines_a: lines_a.len(),
+        lines_b: lines_b.len(),
+        diff_lines,
+        line_dict: Vec::new(),
+        edit_distance,
+        similarity,
+        algorithm: input.options.algorithm,
+        hash: input.options.hash,
+        no_newline_a,
+        no_newline_b,
+        record_separator: input.options.record_separator,
+        normalize_eol: input.options.normalize_eol,
+        ignore_whitespace: input.options.ignore_whitespace,
+        ignore_case: input.options.ignore_case,
+        ignore_blank_lines: input.options.ignore_blank_lines,
+        ignore_pattern: input.ignored_lines.as_ref().map(|ignored| ignored.pattern.clone()),
+        anchor_pattern: input.sync_anchors.as_ref().map(|anchors| anchors.pattern.clone()),
+        detect_moves: input.options.detect_moves,
+        include_unchanged: input.options.include_unchanged,
+        expand_tabs: input.options.expand_tabs,
+        max_line_len: input.options.max_line_len,
+        merge_gap: input.options.merge_gap,
+        prefix_lines: input.options.append_only.then_some(lines_a.len()),
+        binary: input.options.binary,
+        precomputed_script: false,
+        length_unit: input.options.length_unit,
+        salted_redaction: input.options.redaction_salt.is_some(),
+        range_a: input.options.range_a,
+        range_b: input.options.range_b,
+        merkle_root_a,
+        merkle_root_b,
+        redaction_patterns: input.pattern_redactions.iter().map(|r| r.pattern.clone()).collect(),
+        redaction_keyword_hashes: keyword_hashes(&input.options),
+        redact_keyword_values: input.options.redact_keyword_values,
+        redact_all: input.options.redact_all,
+        redact_paths: Vec::new(),
+        redact_line_numbers: input.options.redact_line_numbers,
+        tokenizer: input.options.tokenizer,
+        suppressed: input.options.suppress.clone(),
+        redaction_profile: input.redaction_profile.clone(),
+        hunk_pattern: input.hunk_anchors.as_ref().map(|anchors| anchors.pattern.clone()),
+        hunk_headers,
+        hunks,
+        base_hash: input.merge_base.as_ref().map(|base| base.hash),
+        merge_chunks: merge_chunks(input),
+        structure_only: false,
+        files: Vec::new(),
+        stats: None,
+        proof_hash: [0; 32],
+    };
+    output.proof_hash = create_proof_hash(&output);
+    output
+}
+
+/// First line of file A, 1-based, that file B doesn't start with unchanged, or
+/// `None` when B is A with lines appended. Both sides are comparison keys, which
+/// keep the line terminator, so a last line of A without a newline counts as
+/// changed once B goes on past it.
+pub fn append_mismatch(keys_a: &[&str], keys_b: &[&str]) -> Option<usize> {
+    (0..keys_a.len()).find(|&i| keys_b.get(i) != Some(&keys_a[i])).map(|i| i + 1)
+}
+
+/// Edit script keeping the `len_a` lines of file A and inserting the rest of the
+/// `len_b` lines of file B after them.
+fn append_edits(len_a: usize, len_b: usize) -> Vec<Edit> {
+    let kept = (0..len_a).map(|i| Edit { operation: EditOp::Keep, old_index: i, new_index: i });
+    let appended = (len_a..len_b).map(|j| Edit {
+        operation: EditOp::Insert,
+        old_index: len_a,
+        new_index: j,
+    });
+    kept.chain(appended).collect()
+}
+
+/// Edit script from `a` to `b` by `algorithm`.
+pub(crate) fn line_edits(algorithm: Algorithm, a: &[&str], b: &[&str]) -> Vec<Edit> {
+    match algorithm {
+        Algorithm::Myers => myers_diff(a, b),
+        Algorithm::Patience => patience_diff(a, b),
+        Algorithm::Histogram => histogram_diff(a, b),
+    }
+}
+
+/// Edit script from `a` to `b` by `diff`, with blank lines left out of the
+/// comparison. Blank lines between the same two matched lines are paired up in
+/// order as unchanged, and those left over are dropped from the script rather than
+/// reported, so it no longer covers every line.
+fn blank_lines_ignored(
+    a: &[&str],
+    b: &[&str],
+    diff: impl Fn(&[&str], &[&str]) -> Vec<Edit>,
+) -> Vec<Edit> {
+    let is_blank = |key: &str| key.trim().is_empty();
+    let kept_a: Vec<usize> = (0..a.len()).filter(|&i| !is_blank(a[i])).collect();
+    let kept_b: Vec<usize> = (0..b.len()).filter(|&j| !is_blank(b[j])).collect();
+    let keys_a: Vec<&str> = kept_a.iter().map(|&i| a[i]).collect();
+    let keys_b: Vec<&str> = kept_b.iter().map(|&j| b[j]).collect();
+
+    let mut matches = Vec::new();
+    let (mut next_a, mut next_b) = (0, 0);
+    let kept_pairs = diff(&keys_a, &keys_b)
+        .into_iter()
+        .filter(|edit| edit.operation == EditOp::Keep)
+        .map(|edit| (kept_a[edit.old_index], kept_b[edit.new_index]));
+    for (x, y) in kept_pairs.chain([(a.len(), b.len())]) {
+        let blanks_a = (next_a..x).filter(|&i| is_blank(a[i]));
+        let blanks_b = (next_b..y).filter(|&j| is_blank(b[j]));
+        matches.extend(blanks_a.zip(blanks_b));
+        if x < a.len() {
+            matches.push((x, y));
+        }
+        (next_a, next_b) = (x + 1, y + 1);
+    }
+    edits_from_matches(&matches, a.len(), b.len())
+        .into_iter()
+        .filter(|edit| match edit.operation {
+            EditOp::Keep => true,
+            EditOp::Delete => !is_blank(a[edit.old_index]),
+            EditOp::Insert => !is_blank(b[edit.new_index]),
+        })
+        .collect()
+}
+
+/// Edit script from `a` to `b` with equal anchor lines aligned first. The anchors,
+/// sorted indices into `a` and `b`, are paired up by a Myers diff of their keys,
+/// and `diff` runs on each segment between two pairs, its edits shifted back to
+/// indices into the whole of `a` and `b`.
+fn anchored_edits(
+    a: &[&str],
+    b: &[&str],
+    anchors_a: &[usize],
+    anchors_b: &[usize],
+    diff: impl Fn(&[&str], &[&str]) -> Vec<Edit>,
+) -> Vec<Edit> {
+    let keys_a: Vec<&str> = anchors_a.iter().map(|&i| a[i]).collect();
+    let keys_b: Vec<&str> = anchors_b.iter().map(|&j| b[j]).collect();
+    let pairs = myers_diff(&keys_a, &keys_b)
+        .into_iter()
+        .filter(|edit| edit.operation == EditOp::Keep)
+        .map(|edit| (anchors_a[edit.old_index], anchors_b[edit.new_index]));
+
+    let mut edits = Vec::new();
+    let (mut next_a, mut next_b) = (0, 0);
+    for (x, y) in pairs.chain([(a.len(), b.len())]) {
+        edits.extend(diff(&a[next_a..x], &b[next_b..y]).into_iter().map(|edit| Edit {
+            old_index: edit.old_index + next_a,
+            new_index: edit.new_index + next_b,
+            ..edit
+        }));
+        if x < a.len() {
+            edits.push(Edit { operation: EditOp::Keep, old_index: x, new_index: y });
+        }
+        (next_a, next_b) = (x + 1, y + 1);
+    }
+    edits
+}
+
+/// Half-open index bounds of the lines to diff out of `len`.
+fn range_bounds(range: Option<LineRange>, len: usize) -> (usize, usize) {
+    match range {
+        Some(range) => {
+            assert!(
+                range.start >= 1 && range.start <= range.end && range.end <= len,
+                "Line range outside file"
+            );
+            (range.start - 1, range.end)
+        }
+        None => (0, len),
+    }
+}
+
+/// Directory mode: every file is diffed on its own and the top-level output only
+/// aggregates them, with the tree hashes standing in for the file hashes.
+fn compute_tree_diff(input: &DiffInput) -> DiffOutput {
+    let files: Vec<FileDiff> = input
+        .files
+        .iter()
+        .map(|file| {
+            let redact_paths = &input.options.redact_paths;
+            let redacted = redact_paths.iter().any(|glob| path_matches(glob, &file.path));
+            let output = if input.options.structure_only {
+                hashes_only(&file.input)
+            } else if redacted {
+                let mut input = file.input.clone();
+                input.options.redact_all = true;
+                full_diff(&input)
+            } else {
+                full_diff(&file.input)
+            };
+            FileDiff { path: file.path.clone(), status: file_status(file), output }
+        })
+        .collect();
+
+    let edit_distance = files.iter().map(|f| f.output.edit_distance).sum();
+    let lines_a = files.iter().map(|f| f.output.lines_a).sum();
+    let lines_b = files.iter().map(|f| f.output.lines_b).sum();
+    let similarity = if input.options.structure_only {
+        0.0
+    } else {
+        similarity(edit_distance, lines_a, lines_b)
+    };
+
+    let mut output = DiffOutput {
+        file_a_hash: input.file_a_hash,
+        file_b_hash: input.file_b_hash,
+        lines_a,
+        lines_b,
+        diff_lines: Vec::new(),
+        line_dict: Vec::new(),
+        edit_distance,
+        similarity,
+        algorithm: input.options.algorithm,
+        hash: input.options.hash,
+        no_newline_a: false,
+        no_newline_b: false,
+        record_separator: input.options.record_separator,
+        normalize_eol: input.options.normalize_eol,
+        ignore_whitespace: input.options.ignore_whitespace,
+        ignore_case: input.options.ignore_case,
+        ignore_blank_lines: input.options.ignore_blank_lines,
+        ignore_pattern: input
+            .files
+            .first()
+            .and_then(|f| f.input.ignored_lines.as_ref())
+            .map(|ignored| ignored.pattern.clone()),
+        anchor_pattern: input
+            .files
+            .first()
+            .and_then(|f| f.input.sync_anchors.as_ref())
+            .map(|anchors| anchors.pattern.clone()),
+        detect_moves: input.options.detect_moves,
+        include_unchanged: input.options.include_unchanged,
+        expand_tabs: input.options.expand_tabs,
+        max_line_len: input.options.max_line_len,
+        merge_gap: input.options.merge_gap,
+        prefix_lines: input
+            .options
+            .append_only
+            .then(|| files.iter().filter_map(|f| f.output.prefix_lines).sum()),
+        binary: input.options.binary,
+        precomputed_script: false,
+        length_unit: input.options.length_unit,
+        salted_redaction: input.options.redaction_salt.is_some(),
+        range_a: input.options.range_a,
+        range_b: input.options.range_b,
+        merkle_root_a: None,
+        merkle_root_b: None,
+        redaction_patterns: input
+            .files
+            .first()
+            .map(|f| f.input.pattern_redactions.iter().map(|r| r.pattern.clone()).collect())
+            .unwrap_or_default(),
+        redaction_keyword_hashes: keyword_hashes(&input.options),
+        redact_keyword_values: input.options.redact_keyword_values,
+        redact_all: input.options.redact_all,
+        redact_paths: input.options.redact_paths.clone(),
+        redact_line_numbers: input.options.redact_line_numbers,
+        tokenizer: input.options.tokenizer,
+        suppressed: input.options.suppress.clone(),
+        redaction_profile: input.redaction_profile.clone(),
+        hunk_pattern: input
+            .files
+            .first()
+            .and_then(|f| f.input.hunk_anchors.as_ref())
+            .map(|anchors| anchors.pattern.clone()),
+        hunk_headers: Vec::new(),
+        hunks: Vec::new(),
+        base_hash: None,
+        merge_chunks: Vec::new(),
+        structure_only: input.options.structure_only,
+        files,
+        stats: None,
+        proof_hash: [0; 32],
+    };
+    output.proof_hash = create_proof_hash(&output);
+    output
+}
+
+/// Output of a file in structure-only mode: its hashes and the options they were
+/// computed with, and nothing about its lines.
+fn hashes_only(input: &DiffInput) -> DiffOutput {
+    let mut output = DiffOutput {
+        file_a_hash: input.file_a_hash,
+        file_b_hash: input.file_b_hash,
+        lines_a: 0,
+        lines_b: 0,
+        diff_lines: Vec::new(),
+        line_dict: Vec::new(),
+        edit_distance: 0,
+        similarity: 0.0,
+        algorithm: input.options.algorithm,
+        hash: input.options.hash,
+        no_newline_a: false,
+        no_newline_b: false,
+        record_separator: None,
+        normalize_eol: false,
+        ignore_whitespace: false,
+        ignore_case: false,
+        ignore_blank_lines: false,
+        ignore_pattern: None,
+        anchor_pattern: None,
+        detect_moves: false,
+        include_unchanged: false,
+        expand_tabs: None,
+        max_line_len: None,
+        merge_gap: None,
+        prefix_lines: None,
+        binary: input.options.binary,
+        precomputed_script: false,
+        length_unit: input.options.length_unit,
+        salted_redaction: false,
+        range_a: None,
+        range_b: None,
+        merkle_root_a: None,
+        merkle_root_b: None,
+        redaction_patterns: Vec::new(),
+        redaction_keyword_hashes: Vec::new(),
+        redact_keyword_values: false,
+        redact_all: false,
+        redact_paths: Vec::new(),
+        redact_line_numbers: false,
+        tokenizer: None,
+        suppressed: Vec::new(),
+        redaction_profile: None,
+        hunk_pattern: None,
+        hunk_headers: Vec::new(),
+        hunks: Vec::new(),
+        base_hash: None,
+        merge_chunks: Vec::new(),
+        structure_only: true,
+        files: Vec::new(),
+        stats: None,
+        proof_hash: [0; 32],
+    };
+    output.proof_hash = create_proof_hash(&output);
+    output
+}
+
+/// Moves the line contents of `output` into its `line_dict`, and those of each file
+/// into the file's own, recomputing the proof hashes that committed them.
+fn dedup_lines(output: &mut DiffOutput) {
+    for file in &mut output.files {
+        dedup_lines(&mut file.output);
+    }
+    let hunk_lines = output.hunks.iter_mut().flat_map(|hunk| hunk.lines.iter_mut());
+    output.line_dict = dedup_line_contents(output.diff_lines.iter_mut().chain(hunk_lines));
+    output.proof_hash = create_proof_hash(output);
+}
+
+/// Drops everything that could reveal content or positions, keeping the hashes,
+/// the aggregate counts and the options they depend on.
+fn stats_only(output: DiffOutput) -> DiffOutput {
+    let mut reduced = DiffOutput {
+        file_a_hash: output.file_a_hash,
+        file_b_hash: output.file_b_hash,
+        lines_a: output.lines_a,
+        lines_b: output.lines_b,
+        diff_lines: Vec::new(),
+        line_dict: Vec::new(),
+        edit_distance: output.edit_distance,
+        similarity: output.similarity,
+        algorithm: output.algorithm,
+        hash: output.hash,
+        no_newline_a: false,
+        no_newline_b: false,
+        record_separator: output.record_separator,
+        normalize_eol: output.normalize_eol,
+        ignore_whitespace: output.ignore_whitespace,
+        ignore_case: output.ignore_case,
+        ignore_blank_lines: output.ignore_blank_lines,
+        ignore_pattern: output.ignore_pattern.clone(),
+        anchor_pattern: output.anchor_pattern.clone(),
+        detect_moves: output.detect_moves,
+        include_unchanged: output.include_unchanged,
+        expand_tabs: output.expand_tabs,
+        max_line_len: output.max_line_len,
+        merge_gap: output.merge_gap,
+        prefix_lines: output.prefix_lines,
+        binary: output.binary,
+        precomputed_script: output.precomputed_script,
+        length_unit: output.length_unit,
+        salted_redaction: output.salted_redaction,
+        range_a: output.range_a,
+        range_b: output.range_b,
+        merkle_root_a: output.merkle_root_a,
+        merkle_root_b: output.merkle_root_b,
+        redaction_patterns: Vec::new(),
+        redaction_keyword_hashes: Vec::new(),
+        redact_keyword_values: output.redact_keyword_values,
+        redact_all: output.redact_all,
+        redact_paths: output.redact_paths.clone(),
+        redact_line_numbers: output.redact_line_numbers,
+        tokenizer: output.tokenizer,
+        suppressed: output.suppressed.clone(),
+        redaction_profile: output.redaction_profile.clone(),
+        hunk_pattern: None,
+        hunk_headers: Vec::new(),
+        hunks: Vec::new(),
+        base_hash: output.base_hash,
+        merge_chunks: Vec::new(),
+        structure_only: output.structure_only,
+        files: Vec::new(),
+        stats: Some(diff_stats(&output)),
+        proof_hash: [0; 32],
+    };
+    reduced.proof_hash = create_proof_hash(&reduced);
+    reduced
+}
+
+/// Counts of the changed lines in `output` and all of its files, or the committed
+/// counts of a stats-only output.
+pub fn diff_stats(output: &DiffOutput) -> DiffStats {
+    if let Some(stats) = output.stats {
+        return stats;
+    }
+
+    let mut stats = DiffStats::default();
+    let lines = output
+        .diff_lines
+        .iter()
+        .chain(output.files.iter().flat_map(|file| file.output.diff_lines.iter()));
+    for line in lines {
+        match line.operation {
+            DiffOperation::Insert => stats.inserted += 1,
+            DiffOperation::Delete => stats.deleted += 1,
+            DiffOperation::Replace => stats.replaced += 1,
+            DiffOperation::Move if line.line_number_a.is_some() => stats.moved += 1,
+            DiffOperation::Move
+            | DiffOperation::Context
+            | DiffOperation::NoNewline
+            | DiffOperation::Any => continue,
+        }
+        if line.redacted_length.is_some()
+            || line.new_redacted_length.is_some()
+            || line.redacted_spans.is_some()
+            || line.new_redacted_spans.is_some()
+        {
+            stats.redacted += 1;
+        }
+    }
+    stats
+}
+
+fn file_status(file: &FileInput) -> FileStatus {
+    match (file.in_a, file.in_b) {
+        (false, _) => FileStatus::Added,
+        (true, false) => FileStatus::Removed,
+        _ if file.input.file_a_hash == file.input.file_b_hash => FileStatus::Unchanged,
+        _ => FileStatus::Modified,
+    }
+}
+
+/// Whether the relative `path`, or a directory it is in, matches `glob` the way a
+/// git pathspec does: `*` and `?` match within one path component, `**` matches
+/// across them, and everything else matches itself.
+pub fn path_matches(glob: &str, path: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    let ends = path.iter().enumerate().filter(|(_, &c)| c == '/').map(|(i, _)| i);
+    ends.chain([path.len()]).any(|end| glob_matches(&glob, &path[..end]))
+}
+
+fn glob_matches(glob: &[char], path: &[char]) -> bool {
+    match glob {
+        [] => path.is_empty(),
+        // `**/` matches no directory at all too
+        ['*', '*', '/', rest @ ..] => {
+            let starts = path.iter().enumerate().filter(|(_, &c)| c == '/').map(|(i, _)| i + 1);
+            [0].into_iter().chain(starts).any(|start| glob_matches(rest, &path[start..]))
+        }
+        ['*', '*', rest @ ..] => (0..=path.len()).any(|start| glob_matches(rest, &path[start..])),
+        ['*', rest @ ..] => {
+            let component = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=component).any(|start| glob_matches(rest, &path[start..]))
+        }
+        ['?', rest @ ..] => {
+            path.first().is_some_and(|&c| c != '/') && glob_matches(rest, &path[1..])
+        }
+        [c, rest @ ..] => path.first() == Some(c) && glob_matches(rest, &path[1..]),
+    }
+}
+
+/// Hash of one side of a directory by `algorithm`: the length-prefixed path and
+/// content hash of every file present on that side, in the order given.
+pub fn tree_hash(files: &[FileInput], side_b: bool, algorithm: HashAlgorithm) -> [u8; 32] {
+    let mut hasher = Hasher::new(algorithm);
+    for file in files {
+        let (present, hash) = if side_b {
+            (file.in_b, file.input.file_b_hash)
+        } else {
+            (file.in_a, file.input.file_a_hash)
+        };
+        if present {
+            hasher.update((file.path.len() as u64).to_le_bytes());
+            hasher.update(file.path.as_bytes());
+            hasher.update(hash);
+        }
+    }
+    hasher.finalize()
+}
@@ -0,0 +1,448 @@
+use sha2::{Digest, Sha256};
+
+use crate::{
+    Algorithm, DiffLine, DiffOperation, DiffOutput, FileStatus, HashAlgorithm, LengthUnit,
+    LineEnding, MergeStatus, RedactedSpan, Tokenizer, WordEdit,
+};
+
+/// SHA-256 of `content`, the default file hash.
+pub fn hash_content(content: &str) -> [u8; 32] {
+    hash_bytes(content.as_bytes())
+}
+
+pub fn hash_bytes(bytes: &[u8]) -> [u8; 32] {
+    hash_bytes_with(HashAlgorithm::Sha256, bytes)
+}
+
+/// File hash of `content` under the chosen `algorithm`.
+pub fn hash_content_with(algorithm: HashAlgorithm, content: &str) -> [u8; 32] {
+    hash_bytes_with(algorithm, content.as_bytes())
+}
+
+pub fn hash_bytes_with(algorithm: HashAlgorithm, bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+/// Incremental hasher for either `HashAlgorithm`.
+pub(crate) enum Hasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub(crate) fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: impl AsRef<[u8]>) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data.as_ref());
+            }
+        }
+    }
+
+    pub(crate) fn finalize(self) -> [u8; 32] {
+        match self {
+            Hasher::Sha256(hasher) => hasher.finalize().into(),
+            Hasher::Blake3(hasher) => hasher.finalize().into(),
+        }
+    }
+}
+
+/// Hashes every committed field of `output` except `proof_hash` itself, with the
+/// hash function the output records. Sizes and positions are hashed as `u64`, so
+/// the 32-bit guest and a 64-bit host recomputing the hash agree on it.
+pub fn create_proof_hash(output: &DiffOutput) -> [u8; 32] {
+    let mut hasher = Hasher::new(output.hash);
+    hasher.update(output.file_a_hash);
+    hasher.update(output.file_b_hash);
+    hasher.update(b"lines:");
+    hasher.update((output.lines_a as u64).to_le_bytes());
+    hasher.update((output.lines_b as u64).to_le_bytes());
+    
+    for line in &output.diff_lines {
+        hash_line(&mut hasher, line, output.redact_line_numbers);
+    }
+    if !output.line_dict.is_empty() {
+        hasher.update(b"line_dict:");
+        hasher.update((output.line_dict.len() as u64).to_le_bytes());
+        for content in &output.line_dict {
+            hasher.update((content.len() as u64).to_le_bytes());
+            hasher.update(content.as_bytes());
+        }
+    }
+    
+    hasher.update(b"edit_distance:");
+    hasher.update((output.edit_distance as u64).to_le_bytes());
+    hasher.update(b"similarity:");
+    hasher.update(output.similarity.to_bits().to_le_bytes());
+    hasher.update(b"algorithm:");
+    hasher.update([match output.algorithm {
+        Algorithm::Myers => 1,
+        Algorithm::Patience => 2,
+        Algorithm::Histogram => 3,
+    }]);
+    hasher.update(b"no_newline:");
+    hasher.update([output.no_newline_a as u8, output.no_newline_b as u8]);
+    if let Some(separator) = output.record_separator {
+        hasher.update(b"record_separator:");
+        hasher.update([separator]);
+    }
+    hasher.update(b"normalize_eol:");
+    hasher.update([output.normalize_eol as u8]);
+    hasher.update(b"ignore_whitespace:");
+    hasher.update([output.ignore_whitespace as u8]);
+    hasher.update(b"ignore_case:");
+    hasher.update([output.ignore_case as u8]);
+    if output.ignore_blank_lines {
+        hasher.update(b"ignore_blank_lines:");
+    }
+    if let Some(pattern) = &output.ignore_pattern {
+        hasher.update(b"ignore_pattern:");
+        hasher.update((pattern.len() as u64).to_le_bytes());
+        hasher.update(pattern.as_bytes());
+    }
+    if let Some(pattern) = &output.anchor_pattern {
+        hasher.update(b"anchor_pattern:");
+        hasher.update((pattern.len() as u64).to_le_bytes());
+        hasher.update(pattern.as_bytes());
+    }
+    hasher.update(b"detect_moves:");
+    hasher.update([output.detect_moves as u8]);
+    if output.include_unchanged {
+        hasher.update(b"include_unchanged:");
+    }
+    if let Some(width) = output.expand_tabs {
+        hasher.update(b"expand_tabs:");
+        hasher.update((width as u64).to_le_bytes());
+    }
+    if let Some(max_len) = output.max_line_len {
+        hasher.update(b"max_line_len:");
+        hasher.update((max_len as u64).to_le_bytes());
+    }
+    if let Some(gap) = output.merge_gap {
+        hasher.update(b"merge_gap:");
+        hasher.update((gap as u64).to_le_bytes());
+    }
+    if output.redact_line_numbers {
+        hasher.update(b"redact_line_numbers:");
+    }
+    if !output.suppressed.is_empty() {
+        hasher.update(b"suppressed:");
+        hasher.update((output.suppressed.len() as u64).to_le_bytes());
+        for operation in &output.suppressed {
+            hasher.update([operation_tag(operation)]);
+        }
+    }
+    if let Some(tokenizer) = output.tokenizer {
+        hasher.update(b"tokenizer:");
+        hasher.update([match tokenizer {
+            Tokenizer::Generic => 1,
+            Tokenizer::Rust => 2,
+        }]);
+    }
+    if let Some(prefix) = output.prefix_lines {
+        hasher.update(b"prefix_lines:");
+        hasher.update((prefix as u64).to_le_bytes());
+    }
+    hasher.update(b"binary:");
+    hasher.update([output.binary as u8]);
+    hasher.update(b"precomputed_script:");
+    hasher.update([output.precomputed_script as u8]);
+    hasher.update(b"length_unit:");
+    hasher.update([match output.length_unit {
+        LengthUnit::Bytes => 1,
+        LengthUnit::Chars => 2,
+        LengthUnit::Graphemes => 3,
+    }]);
+    hasher.update(b"salted_redaction:");
+    hasher.update([output.salted_redaction as u8]);
+    let roots = [
+        (b"merkle_root_a:", output.merkle_root_a),
+        (b"merkle_root_b:", output.merkle_root_b),
+    ];
+    for (tag, root) in roots {
+        if let Some(root) = root {
+            hasher.update(tag);
+            hasher.update(root);
+        }
+    }
+    for (tag, range) in [(b"range_a:", output.range_a), (b"range_b:", output.range_b)] {
+        hasher.update(tag);
+        match range {
+            Some(range) => {
+                hasher.update([1]);
+                hasher.update((range.start as u64).to_le_bytes());
+                hasher.update((range.end as u64).to_le_bytes());
+            }
+            None => hasher.update([0]),
+        }
+    }
+    hasher.update(b"redaction_patterns:");
+    hasher.update((output.redaction_patterns.len() as u64).to_le_bytes());
+    for pattern in &output.redaction_patterns {
+        hasher.update((pattern.len() as u64).to_le_bytes());
+        hasher.update(pattern.as_bytes());
+    }
+    if !output.redaction_keyword_hashes.is_empty() {
+        hasher.update(b"redaction_keywords:");
+        hasher.update((output.redaction_keyword_hashes.len() as u64).to_le_bytes());
+        for hash in &output.redaction_keyword_hashes {
+            hasher.update(hash);
+        }
+        hasher.update([output.redact_keyword_values as u8]);
+    }
+    if output.redact_all {
+        hasher.update(b"redact_all:");
+    }
+    if !output.redact_paths.is_empty() {
+        hasher.update(b"redact_paths:");
+        hasher.update((output.redact_paths.len() as u64).to_le_bytes());
+        for glob in &output.redact_paths {
+            hasher.update((glob.len() as u64).to_le_bytes());
+            hasher.update(glob.as_bytes());
+        }
+    }
+    if output.structure_only {
+        hasher.update(b"structure_only:");
+    }
+    if let Some(profile) = &output.redaction_profile {
+        hasher.update(b"redaction_profile:");
+        hasher.update((profile.len() as u64).to_le_bytes());
+        hasher.update(profile.as_bytes());
+    }
+    if let Some(pattern) = &output.hunk_pattern {
+        hasher.update(b"hunk_pattern:");
+        hasher.update((pattern.len() as u64).to_le_bytes());
+        hasher.update(pattern.as_bytes());
+        hasher.update((output.hunk_headers.len() as u64).to_le_bytes());
+        for header in &output.hunk_headers {
+            hasher.update((header.old_start as u64).to_le_bytes());
+            hasher.update((header.new_start as u64).to_le_bytes());
+            match &header.hunk_context {
+                Some(context) => {
+                    hasher.update([1]);
+                    hasher.update((context.len() as u64).to_le_bytes());
+                    hasher.update(context.as_bytes());
+                }
+                None => hasher.update([0]),
+            }
+        }
+    }
+    if !output.hunks.is_empty() {
+        hasher.update(b"hunks:");
+        hasher.update((output.hunks.len() as u64).to_le_bytes());
+        for hunk in &output.hunks {
+            hasher.update((hunk.old_start as u64).to_le_bytes());
+            hasher.update((hunk.old_count as u64).to_le_bytes());
+            hasher.update((hunk.new_start as u64).to_le_bytes());
+            hasher.update((hunk.new_count as u64).to_le_bytes());
+            hasher.update((hunk.lines.len() as u64).to_le_bytes());
+            for line in &hunk.lines {
+                hash_line(&mut hasher, line, output.redact_line_numbers);
+            }
+        }
+    }
+    if let Some(base_hash) = output.base_hash {
+        hasher.update(b"base_hash:");
+        hasher.update(base_hash);
+        hasher.update(b"merge_chunks:");
+        hasher.update((output.merge_chunks.len() as u64).to_le_bytes());
+        for chunk in &output.merge_chunks {
+            hasher.update([match chunk.status {
+                MergeStatus::Unchanged => 1,
+                MergeStatus::Ours => 2,
+                MergeStatus::Theirs => 3,
+                MergeStatus::Both => 4,
+                MergeStatus::Conflict => 5,
+            }]);
+            let spans = [
+                (chunk.base_start, chunk.base_count, &chunk.base_lines),
+                (chunk.ours_start, chunk.ours_count, &chunk.ours_lines),
+                (chunk.theirs_start, chunk.theirs_count, &chunk.theirs_lines),
+            ];
+            for (start, count, lines) in spans {
+                hasher.update((start as u64).to_le_bytes());
+                hasher.update((count as u64).to_le_bytes());
+                hasher.update((lines.len() as u64).to_le_bytes());
+                for line in lines {
+                    hasher.update((line.len() as u64).to_le_bytes());
+                    hasher.update(line.as_bytes());
+                }
+            }
+        }
+    }
+    
+    // each file's own proof hash already commits to its diff, so chaining them
+    // binds the whole changeset to this one hash
+    hasher.update(b"files:");
+    hasher.update((output.files.len() as u64).to_le_bytes());
+    for file in &output.files {
+        hasher.update((file.path.len() as u64).to_le_bytes());
+        hasher.update(file.path.as_bytes());
+        hasher.update([match file.status {
+            FileStatus::Added => 1,
+            FileStatus::Removed => 2,
+            FileStatus::Modified => 3,
+            FileStatus::Unchanged => 4,
+        }]);
+        hasher.update(file.output.proof_hash);
+    }
+    
+    if let Some(stats) = &output.stats {
+        hasher.update(b"stats:");
+        hasher.update((stats.inserted as u64).to_le_bytes());
+        hasher.update((stats.deleted as u64).to_le_bytes());
+        hasher.update((stats.replaced as u64).to_le_bytes());
+        hasher.update((stats.moved as u64).to_le_bytes());
+        hasher.update((stats.redacted as u64).to_le_bytes());
+    }
+    
+    hasher.finalize()
+}
+
+fn hash_line(hasher: &mut Hasher, line: &DiffLine, redact_line_numbers: bool) {
+    hasher.update([operation_tag(&line.operation)]);
+    
+    // numbers are hashed untagged, so once some lines may lack them which ones
+    // are present has to be committed too
+    if redact_line_numbers {
+        let present = [line.line_number_a, line.line_number_b].map(|n| n.is_some() as u8);
+        hasher.update(present);
+    }
+    if let Some(line_a) = line.line_number_a {
+        hasher.update((line_a as u64).to_le_bytes());
+    }
+    if let Some(line_b) = line.line_number_b {
+        hasher.update((line_b as u64).to_le_bytes());
+    }
+    
+    match (&line.content, line.redacted_length) {
+        (Some(content), _) => {
+            hasher.update(b"content:");
+            hasher.update(content.as_bytes());
+        }
+        (None, Some(length)) => {
+            hasher.update(b"redacted:");
+            hasher.update((length as u64).to_le_bytes());
+        }
+        _ => {}
+    }
+    if let Some(index) = line.content_index {
+        hasher.update(b"content_index:");
+        hasher.update((index as u64).to_le_bytes());
+    }
+    
+    match (&line.new_content, line.new_redacted_length) {
+        (Some(content), _) => {
+            hasher.update(b"new_content:");
+            hasher.update(content.as_bytes());
+        }
+        (None, Some(length)) => {
+            hasher.update(b"new_redacted:");
+            hasher.update((length as u64).to_le_bytes());
+        }
+        _ => {}
+    }
+    if let Some(index) = line.new_content_index {
+        hasher.update(b"new_content_index:");
+        hasher.update((index as u64).to_le_bytes());
+    }
+    
+    if let Some(eol) = line.eol {
+        hasher.update(b"eol:");
+        hasher.update([eol_tag(eol)]);
+    }
+    if let Some(eol) = line.new_eol {
+        hasher.update(b"new_eol:");
+        hasher.update([eol_tag(eol)]);
+    }
+    if let Some(spans) = &line.redacted_spans {
+        hasher.update(b"redacted_spans:");
+        hash_spans(hasher, spans);
+    }
+    if let Some(spans) = &line.new_redacted_spans {
+        hasher.update(b"new_redacted_spans:");
+        hash_spans(hasher, spans);
+    }
+    if line.truncated {
+        hasher.update(b"truncated:");
+        for length in [line.full_length, line.new_full_length] {
+            hasher.update([length.is_some() as u8]);
+            hasher.update((length.unwrap_or(0) as u64).to_le_bytes());
+        }
+    }
+    if let Some(word_edits) = &line.word_edits {
+        hasher.update(b"word_edits:");
+        hash_span_edits(hasher, word_edits);
+    }
+    if let Some(char_edits) = &line.char_edits {
+        hasher.update(b"char_edits:");
+        hash_span_edits(hasher, char_edits);
+    }
+    if let Some(moved_line) = line.moved_line {
+        hasher.update(b"moved_line:");
+        hasher.update((moved_line as u64).to_le_bytes());
+    }
+    let paths = [
+        (b"merkle_path_a:", &line.merkle_path_a),
+        (b"merkle_path_b:", &line.merkle_path_b),
+    ];
+    for (tag, path) in paths {
+        if let Some(path) = path {
+            hasher.update(tag);
+            hasher.update((path.len() as u64).to_le_bytes());
+            for step in path {
+                hasher.update(step.sibling);
+                hasher.update([step.sibling_on_left as u8]);
+            }
+        }
+    }
+}
+
+fn hash_spans(hasher: &mut Hasher, spans: &[RedactedSpan]) {
+    hasher.update((spans.len() as u64).to_le_bytes());
+    for span in spans {
+        hasher.update((span.start as u64).to_le_bytes());
+        hasher.update((span.end as u64).to_le_bytes());
+        hasher.update((span.redacted_length as u64).to_le_bytes());
+    }
+}
+
+fn hash_span_edits(hasher: &mut Hasher, edits: &[WordEdit]) {
+    hasher.update((edits.len() as u64).to_le_bytes());
+    for edit in edits {
+        hasher.update([operation_tag(&edit.operation)]);
+        hasher.update((edit.old_start as u64).to_le_bytes());
+        hasher.update((edit.old_end as u64).to_le_bytes());
+        hasher.update((edit.new_start as u64).to_le_bytes());
+        hasher.update((edit.new_end as u64).to_le_bytes());
+    }
+}
+
+fn operation_tag(operation: &DiffOperation) -> u8 {
+    match operation {
+        DiffOperation::Insert => 1,
+        DiffOperation::Delete => 2,
+        DiffOperation::Replace => 3,
+        DiffOperation::NoNewline => 4,
+        DiffOperation::Context => 5,
+        DiffOperation::Move => 6,
+        DiffOperation::Any => 7,
+    }
+}
+
+fn eol_tag(eol: LineEnding) -> u8 {
+    match eol {
+        LineEnding::Lf => 1,
+        LineEnding::Crlf => 2,
+        LineEnding::Missing => 3,
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::BTreeMap;
+
+use crate::myers::edits_from_matches;
+use crate::{myers_diff, Edit, EditOp};
+
+/// Lines occurring more often than this in a region of file A are never used to
+/// start a match; if every common line does, the region is diffed with Myers.
+const MAX_CHAIN_LENGTH: usize = 64;
+
+// weights of git's indent heuristic
+const MAX_INDENT: i32 = 200;
+const MAX_BLANKS: i32 = 20;
+const INDENT_HEURISTIC_MAX_SLIDING: usize = 100;
+const START_OF_FILE_PENALTY: i32 = 1;
+const END_OF_FILE_PENALTY: i32 = 21;
+const TOTAL_BLANK_WEIGHT: i32 = -30;
+const POST_BLANK_WEIGHT: i32 = 6;
+const RELATIVE_INDENT_PENALTY: i32 = -4;
+const RELATIVE_INDENT_WITH_BLANK_PENALTY: i32 = 10;
+const RELATIVE_OUTDENT_PENALTY: i32 = 24;
+const RELATIVE_OUTDENT_WITH_BLANK_PENALTY: i32 = 17;
+const RELATIVE_DEDENT_PENALTY: i32 = 23;
+const RELATIVE_DEDENT_WITH_BLANK_PENALTY: i32 = 17;
+const INDENT_WEIGHT: i32 = 60;
+
+/// Edit script from `a` to `b` following git's histogram diff, including the way
+/// git slides each changed run into place afterwards (with its default indent
+/// heuristic), so the hunks match `git diff --histogram`. Lines are expected to
+/// keep their terminators, as `comparison_keys` produces them.
+pub fn histogram_diff(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let mut changed_a = vec![false; a.len()];
+    let mut changed_b = vec![false; b.len()];
+
+    // unlike Myers, git doesn't strip the common ends first: they can change which
+    // run is picked as the longest
+    let mut diff = HistogramDiff {
+        a,
+        b,
+        changed_a: &mut changed_a,
+        changed_b: &mut changed_b,
+    };
+    diff.diff_range(0, a.len(), 0, b.len());
+
+    compact(a, &mut changed_a, &mut changed_b);
+    compact(b, &mut changed_b, &mut changed_a);
+
+    // unchanged lines pair up in order on both sides
+    let kept_a = (0..a.len()).filter(|&x| !changed_a[x]);
+    let kept_b = (0..b.len()).filter(|&y| !changed_b[y]);
+    let matches: Vec<(usize, usize)> = kept_a.zip(kept_b).collect();
+    edits_from_matches(&matches, a.len(), b.len())
+}
+
+struct HistogramDiff<'a, 'b> {
+    a: &'b [&'a str],
+    b: &'b [&'a str],
+    changed_a: &'b mut [bool],
+    changed_b: &'b mut [bool],
+}
+
+/// Occurrences of one line within the current region of file A.
+struct Record {
+    first: usize,
+    count: usize,
+}
+
+/// Longest common region found so far, with inclusive ends.
+#[derive(Clone, Copy)]
+struct Region {
+    start_a: usize,
+    end_a: usize,
+    start_b: usize,
+    end_b: usize,
+}
+
+enum Lcs {
+    Found(Option<Region>),
+    /// Every common line is too frequent to anchor on.
+    TooCommon,
+}
+
+impl HistogramDiff<'_, '_> {
+    fn diff_range(
+        &mut self,
+        mut start_a: usize,
+        end_a: usize,
+        mut start_b: usize,
+        end_b: usize,
+    ) {
+        loop {
+            if start_a == end_a || start_b == end_b {
+                self.mark_changed(start_a, end_a, start_b, end_b);
+                return;
+            }
+            match self.find_lcs(start_a, end_a, start_b, end_b) {
+                Lcs::TooCommon => {
+                    let edits = myers_diff(&self.a[start_a..end_a], &self.b[start_b..end_b]);
+                    for edit in edits {
+                        match edit.operation {
+                            EditOp::Delete => self.changed_a[start_a + edit.old_index] = true,
+                            EditOp::Insert => self.changed_b[start_b + edit.new_index] = true,
+                            EditOp::Keep => {}
+                        }
+                    }
+                    return;
+                }
+                Lcs::Found(None) => {
+                    self.mark_changed(start_a, end_a, start_b, end_b);
+                    return;
+                }
+                Lcs::Found(Some(lcs)) => {
+                    self.diff_range(start_a, lcs.start_a, start_b, lcs.start_b);
+                    start_a = lcs.end_a + 1;
+                    start_b = lcs.end_b + 1;
+                }
+            }
+        }
+    }
+
+    fn mark_changed(&mut self, start_a: usize, end_a: usize, start_b: usize, end_b: usize) {
+        self.changed_a[start_a..end_a].fill(true);
+        self.changed_b[start_b..end_b].fill(true);
+    }
+
+    /// Finds the longest common run whose rarest line occurs least often in file A,
+    /// scanning file B once and extending every occurrence of each of its lines.
+    fn find_lcs(&self, start_a: usize, end_a: usize, start_b: usize, end_b: usize) -> Lcs {
+        let (a, b) = (self.a, self.b);
+
+        // scanning backwards leaves each record on its first occurrence, with
+        // `next` chaining the later ones
+        let mut records: BTreeMap<&str, Record> = BTreeMap::new();
+        let mut next: Vec<Option<usize>> = vec![None; end_a - start_a];
+        for x in (start_a..end_a).rev() {
+            let record = records.entry(a[x]).or_insert(Record { first: x, count: 0 });
+            if record.count > 0 {
+                next[x - start_a] = Some(record.first);
+                record.first = x;
+            }
+            record.count += 1;
+        }
+        let count = |x: usize| records[a[x]].count;
+
+        let mut lcs: Option<Region> = None;
+        let mut max_count = MAX_CHAIN_LENGTH + 1;
+        let mut has_common = false;
+
+        let mut y = start_b;
+        while y < end_b {
+            let mut next_y = y + 1;
+            let Some(record) = records.get(b[y]) else {
+                y = next_y;
+                continue;
+            };
+            has_common = true;
+            if record.count > max_count {
+                y = next_y;
+                continue;
+            }
+
+            let mut x = record.first;
+            loop {
+                let mut region = Region {
+                    start_a: x,
+                    end_a: x,
+                    start_b: y,
+                    end_b: y,
+                };
+                let mut rarest = record.count;
+                while region.start_a > start_a
+                    && region.start_b > start_b
+                    && a[region.start_a - 1] == b[region.start_b - 1]
+                {
+                    region.start_a -= 1;
+                    region.start_b -= 1;
+                    if rarest > 1 {
+                        rarest = rarest.min(count(region.start_a));
+                    }
+                }
+                while region.end_a + 1 < end_a
+                    && region.end_b + 1 < end_b
+                    && a[region.end_a + 1] == b[region.end_b + 1]
+                {
+                    region.end_a += 1;
+                    region.end_b += 1;
+                    if rarest > 1 {
+                        rarest = rarest.min(count(region.end_a));
+                    }
+                }
+
+                if next_y <= region.end_b {
+                    next_y = region.end_b + 1;
+                }
+                let best_len = lcs.map_or(0, |lcs| lcs.end_a - lcs.start_a);
+                if best_len < region.end_a - region.start_a || rarest < max_count {
+                    lcs = Some(region);
+                    max_count = rarest;
+                }
+
+                // continue with the next occurrence past the run just found
+                let mut following = next[x - start_a];
+                while let Some(n) = following.filter(|&n| n <= region.end_a) {
+                    following = next[n - start_a];
+                }
+                match following {
+                    Some(n) => x = n,
+                    None => break,
+                }
+            }
+            y = next_y;
+        }
+
+        if has_common && max_count > MAX_CHAIN_LENGTH {
+            Lcs::TooCommon
+        } else {
+            Lcs::Found(lcs)
+        }
+    }
+}
+
+/// A run of changed lines `start..end` in one file; empty between two unchanged
+/// lines that are paired with a change in the other file.
+struct Group {
+    start: usize,
+    end: usize,
+}
+
+impl Group {
+    fn first(changed: &[bool]) -> Group {
+        let end = changed.iter().take_while(|&&c| c).count();
+        Group { start: 0, end }
+    }
+
+    fn next(&mut self, changed: &[bool]) -> bool {
+        if self.end == changed.len() {
+            return false;
+        }
+        self.start = self.end + 1;
+        self.end = self.start;
+        while self.end < changed.len() && changed[self.end] {
+            self.end += 1;
+        }
+        true
+    }
+
+    fn previous(&mut self, changed: &[bool]) -> bool {
+        if self.start == 0 {
+            return false;
+        }
+        self.end = self.start - 1;
+        self.start = self.end;
+        while self.start > 0 && changed[self.start - 1] {
+            self.start -= 1;
+        }
+        true
+    }
+
+    /// Moves the run down a line when its first line equals the line after it,
+    /// merging with a run it reaches.
+    fn slide_down(&mut self, lines: &[&str], changed: &mut [bool]) -> bool {
+        if self.end < lines.len() && lines[self.start] == lines[self.end] {
+            changed[self.start] = false;
+            changed[self.end] = true;
+            self.start += 1;
+            self.end += 1;
+            while self.end < lines.len() && changed[self.end] {
+                self.end += 1;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn slide_up(&mut self, lines: &[&str], changed: &mut [bool]) -> bool {
+        if self.start > 0 && lines[self.start - 1] == lines[self.end - 1] {
+            self.start -= 1;
+            self.end -= 1;
+            changed[self.start] = true;
+            changed[self.end] = false;
+            while self.start > 0 && changed[self.start - 1] {
+                self.start -= 1;
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// git's `xdl_change_compact`: slides every changed run of `lines` as far as it
+/// can go, merging runs that touch, then settles it where it lines up with a change
+/// in the other file or, failing that, where the indent heuristic scores best.
+/// `other` is kept in step so unchanged lines stay paired.
+fn compact(lines: &[&str], changed: &mut [bool], other: &mut [bool]) {
+    let mut group = Group::first(changed);
+    let mut other_group = Group::first(other);
+
+    loop {
+        if group.end > group.start {
+            let mut earliest_end;
+            let mut matches_other;
+            let mut size;
+            loop {
+                size = group.end - group.start;
+                matches_other = false;
+
+                while group.slide_up(lines, changed) {
+                    assert!(other_group.previous(other), "group sync broken sliding up");
+                }
+                earliest_end = group.end;
+                if other_group.end > other_group.start {
+                    matches_other = true;
+                }
+
+                while group.slide_down(lines, changed) {
+                    assert!(other_group.next(other), "group sync broken sliding down");
+                    if other_group.end > other_group.start {
+                        matches_other = true;
+                    }
+                }
+
+                if size == group.end - group.start {
+                    break;
+                }
+            }
+
+            // the run now sits as low as it can go, so only upward shifts are left
+            if group.end == earliest_end {
+                // it can't move
+            } else if matches_other {
+                // back up to the last position that lines up with a change there
+                while other_group.end == other_group.start {
+                    assert!(group.slide_up(lines, changed), "match disappeared");
+                    assert!(other_group.previous(other), "group sync broken sliding to match");
+                }
+            } else {
+                let mut shift = earliest_end
+                    .max((group.end - size).saturating_sub(1))
+                    .max(group.end.saturating_sub(INDENT_HEURISTIC_MAX_SLIDING));
+                let mut best: Option<(usize, SplitScore)> = None;
+                while shift <= group.end {
+                    let mut score = SplitScore::default();
+                    score.add(&measure_split(lines, shift));
+                    score.add(&measure_split(lines, shift - size));
+                    let better = match &best {
+                        Some((_, best)) => score.cmp(best) <= 0,
+                        None => true,
+                    };
+                    if better {
+                        best = Some((shift, score));
+                    }
+                    shift += 1;
+                }
+
+                let best_shift = best.map_or(group.end, |(shift, _)| shift);
+                while group.end > best_shift {
+                    assert!(group.slide_up(lines, changed), "best shift unreached");
+                    assert!(other_group.previous(other), "group sync broken sliding");
+                }
+            }
+        }
+
+        if !group.next(changed) {
+            break;
+        }
+        assert!(other_group.next(other), "group sync broken moving to next group");
+    }
+}
+
+/// Indentation of `line` in columns, with tabs to the next multiple of 8, or -1 if
+/// it is blank.
+fn indent(line: &str) -> i32 {
+    let mut indent = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => indent += 1,
+            '\t' => indent += 8 - indent % 8,
+            '\n' | '\r' | '\x0b' | '\x0c' => {}
+            _ => return indent,
+        }
+        if indent >= MAX_INDENT {
+            return MAX_INDENT;
+        }
+    }
+    -1
+}
+
+/// Surroundings of a split placed just before line `split`.
+struct SplitMeasurement {
+    end_of_file: bool,
+    indent: i32,
+    pre_blank: i32,
+    pre_indent: i32,
+    post_blank: i32,
+    post_indent: i32,
+}
+
+fn measure_split(lines: &[&str], split: usize) -> SplitMeasurement {
+    let (end_of_file, split_indent) = match lines.get(split) {
+        Some(line) => (false, indent(line)),
+        None => (true, -1),
+    };
+
+    let mut pre_blank = 0;
+    let mut pre_indent = -1;
+    for line in lines[..split.min(lines.len())].iter().rev() {
+        pre_indent = indent(line);
+        if pre_indent != -1 {
+            break;
+        }
+        pre_blank += 1;
+        if pre_blank == MAX_BLANKS {
+            pre_indent = 0;
+            break;
+        }
+    }
+
+    let mut post_blank = 0;
+    let mut post_indent = -1;
+    for line in lines.iter().skip(split + 1) {
+        post_indent = indent(line);
+        if post_indent != -1 {
+            break;
+        }
+        post_blank += 1;
+        if post_blank == MAX_BLANKS {
+            post_indent = 0;
+            break;
+        }
+    }
+
+    SplitMeasurement {
+        end_of_file,
+        indent: split_indent,
+        pre_blank,
+        pre_indent,
+        post_blank,
+        post_indent,
+    }
+}
+
+#[derive(Default)]
+struct SplitScore {
+    effective_indent: i32,
+    penalty: i32,
+}
+
+impl SplitScore {
+    fn add(&mut self, m: &SplitMeasurement) {
+        if m.pre_indent == -1 && m.pre_blank == 0 {
+            self.penalty += START_OF_FILE_PENALTY;
+        }
+        if m.end_of_file {
+            self.penalty += END_OF_FILE_PENALTY;
+        }
+
+        let post_blank = if m.indent == -1 { 1 + m.post_blank } else { 0 };
+        let total_blank = m.pre_blank + post_blank;
+        self.penalty += TOTAL_BLANK_WEIGHT * total_blank;
+        self.penalty += POST_BLANK_WEIGHT * post_blank;
+
+        let indent = if m.indent != -1 { m.indent } else { m.post_indent };
+        let any_blanks = total_blank != 0;
+        self.effective_indent += indent;
+
+        if indent == -1 || m.pre_indent == -1 || indent == m.pre_indent {
+            // no adjustment
+        } else if indent > m.pre_indent {
+            self.penalty += if any_blanks {
+                RELATIVE_INDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_INDENT_PENALTY
+            };
+        } else if m.post_indent != -1 && m.post_indent > indent {
+            self.penalty += if any_blanks {
+                RELATIVE_OUTDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_OUTDENT_PENALTY
+            };
+        } else {
+            self.penalty += if any_blanks {
+                RELATIVE_DEDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_DEDENT_PENALTY
+            };
+        }
+    }
+
+    /// Negative when `self` is the better split.
+    fn cmp(&self, other: &SplitScore) -> i32 {
+        let indents = (self.effective_indent > other.effective_indent) as i32
+            - (self.effective_indent < other.effective_indent) as i32;
+        INDENT_WEIGHT * indents + (self.penalty - other.penalty)
+    }
+}
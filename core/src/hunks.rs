@@ -0,0 +1,161 @@
+use crate::{
+    pattern_matches_consistent, DiffHunk, DiffLine, DiffOperation, HunkAnchors, HunkHeader,
+    PatternRedaction, RedactionRange,
+};
+
+/// Adjacent diff lines with the 1-based start and length of the span they cover
+/// in each file.
+pub struct Hunk<'a> {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    pub lines: Vec<&'a DiffLine>,
+}
+
+/// Groups diff lines into hunks of adjacent changes, tracking the offset between
+/// file A and file B so one-sided lines can be positioned in the other file. Lines
+/// committed without numbers join the hunk before them, or the first hunk when
+/// they come before any, which then starts early enough to hold them.
+pub fn group_hunks(diff_lines: &[DiffLine]) -> Vec<Hunk<'_>> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    // line_b - line_a for unchanged lines at the current position
+    let mut offset: isize = 0;
+    // numberless lines before the first hunk
+    let mut leading: Vec<&DiffLine> = Vec::new();
+
+    for line in diff_lines {
+        let numbered = line.line_number_a.is_some() || line.line_number_b.is_some();
+        if line.operation == DiffOperation::NoNewline || !numbered {
+            match hunks.last_mut() {
+                Some(hunk) => add_line(hunk, line),
+                None if numbered => {}
+                None => leading.push(line),
+            }
+            continue;
+        }
+        
+        let continues = hunks.last().is_some_and(|hunk| {
+            let next_a = hunk.old_start + hunk.old_count;
+            let next_b = hunk.new_start + hunk.new_count;
+            match (line.line_number_a, line.line_number_b) {
+                (Some(a), _) => a == next_a,
+                (None, Some(b)) => b == next_b,
+                (None, None) => false,
+            }
+        });
+
+        if !continues {
+            if let Some(hunk) = hunks.last() {
+                offset += hunk.new_count as isize - hunk.old_count as isize;
+            }
+            let (old_start, new_start) = match (line.line_number_a, line.line_number_b) {
+                (Some(a), _) => (a, (a as isize + offset) as usize),
+                (None, Some(b)) => ((b as isize - offset) as usize, b),
+                (None, None) => continue,
+            };
+            let mut hunk = Hunk {
+                old_start,
+                old_count: 0,
+                new_start,
+                new_count: 0,
+                lines: Vec::new(),
+            };
+            for line in leading.drain(..) {
+                add_line(&mut hunk, line);
+            }
+            hunk.old_start = hunk.old_start.saturating_sub(hunk.old_count).max(1);
+            hunk.new_start = hunk.new_start.saturating_sub(hunk.new_count).max(1);
+            hunks.push(hunk);
+        }
+
+        add_line(hunks.last_mut().unwrap(), line);
+    }
+    if !leading.is_empty() {
+        let mut hunk = Hunk {
+            old_start: 1,
+            old_count: 0,
+            new_start: 1,
+            new_count: 0,
+            lines: Vec::new(),
+        };
+        for line in leading {
+            add_line(&mut hunk, line);
+        }
+        hunks.push(hunk);
+    }
+
+    hunks
+}
+
+/// The hunks of `diff_lines` with copies of their lines, as committed.
+pub fn diff_hunks(diff_lines: &[DiffLine]) -> Vec<DiffHunk> {
+    group_hunks(diff_lines)
+        .into_iter()
+        .map(|hunk| DiffHunk {
+            old_start: hunk.old_start,
+            old_count: hunk.old_count,
+            new_start: hunk.new_start,
+            new_count: hunk.new_count,
+            lines: hunk.lines.into_iter().cloned().collect(),
+        })
+        .collect()
+}
+
+fn add_line<'a>(hunk: &mut Hunk<'a>, line: &'a DiffLine) {
+    match line.operation {
+        DiffOperation::Delete => hunk.old_count += 1,
+        DiffOperation::Insert => hunk.new_count += 1,
+        DiffOperation::Move if line.line_number_a.is_some() => hunk.old_count += 1,
+        DiffOperation::Move => hunk.new_count += 1,
+        DiffOperation::Replace | DiffOperation::Context => {
+            hunk.old_count += 1;
+            hunk.new_count += 1;
+        }
+        DiffOperation::NoNewline | DiffOperation::Any => {}
+    }
+    hunk.lines.push(line);
+}
+
+/// Same check as for redaction patterns: every line of file A equal to an anchor
+/// line has to be an anchor too.
+pub fn anchors_consistent(anchors: &HunkAnchors, lines_a: &[&str]) -> bool {
+    let as_redaction = PatternRedaction {
+        pattern: anchors.pattern.clone(),
+        lines_a: anchors.lines_a.clone(),
+        lines_b: Vec::new(),
+    };
+    pattern_matches_consistent(&as_redaction, lines_a, &[])
+}
+
+/// One header per hunk, naming the closest anchor line of file A before the hunk
+/// starts, like the function name git prints after `@@`. An anchor that would be
+/// redacted on file A's side is never shown; the hunk then gets no context rather
+/// than a more distant one.
+pub fn hunk_headers(
+    diff_lines: &[DiffLine],
+    anchors: &HunkAnchors,
+    lines_a: &[&str],
+    redaction_ranges: &[RedactionRange],
+) -> Vec<HunkHeader> {
+    let redacted = |n: usize| {
+        redaction_ranges.iter().any(|range| {
+            range.operation != DiffOperation::Insert
+                && n >= range.start_line
+                && n <= range.end_line
+        })
+    };
+    group_hunks(diff_lines)
+        .iter()
+        .map(|hunk| {
+            let anchor = anchors.lines_a.iter().copied().filter(|&n| n < hunk.old_start).max();
+            HunkHeader {
+                old_start: hunk.old_start,
+                new_start: hunk.new_start,
+                hunk_context: anchor
+                    .filter(|&n| !redacted(n))
+                    .map(|n| lines_a[n - 1].to_string()),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,628 @@
+mod diff;
+mod hash;
+mod histogram;
+mod hunks;
+mod lines;
+mod merge;
+mod merkle;
+mod myers;
+mod patience;
+mod script;
+mod tokens;
+
+use serde::{Deserialize, Serialize};
+
+pub use diff::{append_mismatch, compute_diff, diff_stats, path_matches, tree_hash};
+pub use hash::{create_proof_hash, hash_bytes, hash_bytes_with, hash_content, hash_content_with};
+pub use histogram::histogram_diff;
+pub use hunks::{anchors_consistent, diff_hunks, group_hunks, hunk_headers, Hunk};
+pub use lines::{
+    annotate_line_endings, comparison_keys, create_diff_lines, dedup_line_contents,
+    hide_redacted_line_numbers, ignore_matched_lines, ignored_lines_consistent, keyword_hashes,
+    keyword_redaction_ranges, line_endings, mark_missing_newlines, mark_moves,
+    missing_final_newline, pattern_matches_consistent, pattern_redaction_ranges, records,
+    redact_all_ranges, redacted_text, replayed_file_b, restore_line_contents, retain_operations,
+    sync_anchors_consistent, tab_expanded, truncate_long_lines,
+};
+pub use merge::merge_chunks;
+pub use merkle::{
+    attach_leaf_paths, attach_merkle_paths, merkle_leaf, merkle_path_root, merkle_root,
+    merkle_root_of_leaves,
+};
+pub use myers::{edit_distance, myers_diff, myers_diff_trace, similarity, Edit, EditOp};
+pub use patience::patience_diff;
+pub use script::script_diff;
+pub use tokens::{token_line_edits, tokenize};
+
+/// Most lines, counted over both sides and all files, the guest will diff. Larger
+/// inputs are rejected before any work is done.
+pub const MAX_TOTAL_LINES: usize = 200_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiffInput {
+    pub file_a_hash: [u8; 32],
+    pub file_b_hash: [u8; 32],
+    pub file_a_content: String,
+    pub file_b_content: String,
+    /// Binary mode: the raw file bytes, sent in place of the contents and decoded
+    /// by `decode_binary` once their hashes are checked.
+    pub file_a_bytes: Vec<u8>,
+    pub file_b_bytes: Vec<u8>,
+    pub redaction_ranges: Vec<RedactionRange>,
+    /// Name of the configured profile some of `redaction_ranges` came from.
+    pub redaction_profile: Option<String>,
+    pub pattern_redactions: Vec<PatternRedaction>,
+    pub hunk_anchors: Option<HunkAnchors>,
+    pub ignored_lines: Option<IgnoredLines>,
+    pub sync_anchors: Option<SyncAnchors>,
+    pub options: DiffOptions,
+    /// Directory mode: one entry per relative path, sorted. The top-level contents
+    /// are then empty and the top-level hashes are the tree hashes of both sides.
+    pub files: Vec<FileInput>,
+    /// Script mode: a precomputed edit script over line hashes, proven without the
+    /// contents. The contents are then empty and the hashes are the Merkle roots.
+    pub script: Option<ScriptInput>,
+    /// Merge mode: the common ancestor of file A (ours) and file B (theirs), merged
+    /// three ways on top of the usual diff between them.
+    pub merge_base: Option<MergeBase>,
+}
+
+impl DiffInput {
+    /// Lines on both sides, summed over every file in directory mode.
+    pub fn total_lines(&self) -> usize {
+        let separator = self.options.record_separator;
+        records(&self.file_a_content, separator).len()
+            + records(&self.file_b_content, separator).len()
+            + self.merge_base.as_ref().map_or(0, |base| records(&base.content, separator).len())
+            + self.files.iter().map(|f| f.input.total_lines()).sum::<usize>()
+            + self.script.as_ref().map_or(0, |s| s.leaves_a.len() + s.leaves_b.len())
+    }
+
+    /// In binary mode, replaces the raw bytes of both sides with their lossy UTF-8
+    /// decoding as contents, for this input and every file in directory mode.
+    /// Invalid sequences become U+FFFD.
+    pub fn decode_binary(&mut self) {
+        if self.options.binary {
+            self.file_a_content = String::from_utf8_lossy(&self.file_a_bytes).into_owned();
+            self.file_b_content = String::from_utf8_lossy(&self.file_b_bytes).into_owned();
+            self.file_a_bytes = Vec::new();
+            self.file_b_bytes = Vec::new();
+        }
+        for file in &mut self.files {
+            file.input.decode_binary();
+        }
+    }
+
+    /// With `expand_tabs` set, expands the tabs of both contents, the merge base and
+    /// every file in directory mode. Done once the hashes are checked, so they stay
+    /// those of the original files.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tab width is 0.
+    pub fn expand_tabs(&mut self) {
+        if let Some(width) = self.options.expand_tabs {
+            assert!(width > 0, "Tab width must be at least 1");
+            self.file_a_content = tab_expanded(&self.file_a_content, width);
+            self.file_b_content = tab_expanded(&self.file_b_content, width);
+            if let Some(base) = &mut self.merge_base {
+                base.content = tab_expanded(&base.content, width);
+            }
+        }
+        for file in &mut self.files {
+            file.input.expand_tabs();
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FileInput {
+    pub path: String,
+    pub in_a: bool,
+    pub in_b: bool,
+    /// A file missing from one side has empty content there.
+    pub input: DiffInput,
+}
+
+/// What a prover without the file contents supplies: the `merkle_leaf` hash of
+/// every line of both files and an edit script between them, one operation per
+/// line in order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ScriptInput {
+    pub leaves_a: Vec<[u8; 32]>,
+    pub leaves_b: Vec<[u8; 32]>,
+    pub operations: Vec<EditOp>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MergeBase {
+    pub hash: [u8; 32],
+    pub content: String,
+}
+
+/// Lines whose content matched `pattern`, as evaluated by the host. Line numbers
+/// are 1-based in their own file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PatternRedaction {
+    pub pattern: String,
+    pub lines_a: Vec<usize>,
+    pub lines_b: Vec<usize>,
+}
+
+/// Lines of file A matching the hunk header `pattern`, as evaluated by the host.
+/// Line numbers are 1-based.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HunkAnchors {
+    pub pattern: String,
+    pub lines_a: Vec<usize>,
+}
+
+/// Lines of either file matching the `--ignore-matching` pattern, as evaluated by
+/// the host. They compare equal to each other whatever they hold. Line numbers are
+/// 1-based in their own file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IgnoredLines {
+    pub pattern: String,
+    pub lines_a: Vec<usize>,
+    pub lines_b: Vec<usize>,
+}
+
+/// Lines of either file matching the `--anchor` pattern, as evaluated by the host.
+/// Equal anchor lines are aligned first and the files are diffed one segment
+/// between them at a time. Line numbers are 1-based in their own file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SyncAnchors {
+    pub pattern: String,
+    pub lines_a: Vec<usize>,
+    pub lines_b: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiffOptions {
+    pub algorithm: Algorithm,
+    pub granularity: Granularity,
+    /// Split the contents into records ending in this ASCII byte instead of into
+    /// lines. Records may hold newlines, and line numbers count records.
+    pub record_separator: Option<u8>,
+    pub normalize_eol: bool,
+    /// Compare lines with surrounding whitespace trimmed and inner runs collapsed.
+    pub ignore_whitespace: bool,
+    /// Compare lines ignoring ASCII case.
+    pub ignore_case: bool,
+    /// Leave blank and whitespace-only lines out of the comparison.
+    pub ignore_blank_lines: bool,
+    /// Unchanged lines to include before and after each change.
+    pub context: usize,
+    /// Also include every unchanged line between two changes when at most this
+    /// many of them would be left out, merging their hunks, like git's
+    /// `--inter-hunk-context`.
+    pub merge_gap: Option<usize>,
+    /// Commit every unchanged line as context, so the diff holds all of file B; the
+    /// guest checks that it rebuilds to `file_b_hash`.
+    pub include_unchanged: bool,
+    /// Report blocks deleted in one place and inserted unchanged elsewhere as moves.
+    pub detect_moves: bool,
+    /// What `redacted_length` counts.
+    pub length_unit: LengthUnit,
+    /// Secret salt: every `redacted_length` becomes a salted hash tag of the hidden
+    /// text instead. The salt itself is never committed.
+    pub redaction_salt: Option<Vec<u8>>,
+    /// Diff only these lines of each file. Both files are still hashed whole.
+    pub range_a: Option<LineRange>,
+    pub range_b: Option<LineRange>,
+    /// Commit a Merkle root over the lines of each file and an inclusion path for
+    /// every line shown.
+    pub merkle: bool,
+    /// Hash the files as raw bytes and diff their lossy UTF-8 decoding, so files
+    /// that aren't valid UTF-8 can be compared.
+    pub binary: bool,
+    /// Commit only the hashes and aggregate counts, no line content or numbers.
+    pub stats_only: bool,
+    /// Directory mode: hash every file but diff none of them, committing only the
+    /// path, status and hashes of each.
+    pub structure_only: bool,
+    /// Commit each distinct line content once, in `line_dict`, with the lines
+    /// pointing into it by index.
+    pub dedup_lines: bool,
+    /// Expand tabs to this many columns before diffing; the committed content is
+    /// the expanded text.
+    pub expand_tabs: Option<usize>,
+    /// Commit at most this many bytes of each line's content. Lines are still
+    /// compared in full.
+    pub max_line_len: Option<usize>,
+    /// Prove file B is file A with lines appended: the prefix is checked line by
+    /// line instead of diffed, and only the appended lines are committed.
+    pub append_only: bool,
+    /// Hash function of the file hashes, the merge base hash and `proof_hash`.
+    pub hash: HashAlgorithm,
+    /// Words hidden wherever a line contains them, matched by the guest ignoring
+    /// ASCII case. Only their hashes are committed.
+    pub redact_keywords: Vec<String>,
+    /// Also hide the `=value` following a keyword, up to the next whitespace.
+    pub redact_keyword_values: bool,
+    /// Hide the content of every line of both files.
+    pub redact_all: bool,
+    /// Directory mode: globs of relative paths, matched by the guest, whose files
+    /// are diffed with `redact_all`.
+    pub redact_paths: Vec<String>,
+    /// Commit lines with redacted content without their line numbers, so the proof
+    /// doesn't say where they are.
+    pub redact_line_numbers: bool,
+    /// Diff the token streams of the files rather than their lines; a line is
+    /// changed when any of its tokens is.
+    pub tokenizer: Option<Tokenizer>,
+    /// SHA-256 that `redacted_text` must have, so redacting the wrong lines fails
+    /// the proof instead of leaking the ones meant to be hidden. Never committed.
+    pub expected_redaction_hash: Option<[u8; 32]>,
+    /// Operations left out of `diff_lines`, which may be `Insert` and `Delete`, so
+    /// the proof covers only the rest of the diff.
+    pub suppress: Vec<DiffOperation>,
+    /// Also commit `diff_lines` grouped into hunks with their spans in each file.
+    pub hunks: bool,
+}
+
+/// Algorithm computing the line edit script. Replaced-line breakdowns always use
+/// Myers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Algorithm {
+    #[default]
+    Myers,
+    Patience,
+    Histogram,
+}
+
+/// Hash function of the file and tree hashes and of `proof_hash`. Merkle trees,
+/// and the file hashes that are their roots, and salted redaction tags always use
+/// SHA-256.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    /// Far cheaper to compute in the zkVM.
+    Blake3,
+}
+
+/// Unit of every `redacted_length`. Bytes reveal how much of a line is multi-byte
+/// text; graphemes count what a reader would see as one character.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum LengthUnit {
+    #[default]
+    Bytes,
+    Chars,
+    Graphemes,
+}
+
+/// How `tokenize` splits lines when diffing by tokens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Tokenizer {
+    /// Identifiers, numbers and single punctuation characters.
+    Generic,
+    /// Generic tokens, with Rust literals, lifetimes, comments and operators kept
+    /// whole.
+    Rust,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Granularity {
+    #[default]
+    Line,
+    Word,
+    Char,
+}
+
+/// 1-based inclusive span of lines, `start..=end`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RedactionRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Optional 1-based inclusive character columns; without them the whole line
+    /// is redacted.
+    pub start_col: Option<usize>,
+    pub end_col: Option<usize>,
+    pub operation: DiffOperation,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DiffOperation {
+    Insert,
+    Delete,
+    Replace,
+    /// Unchanged line shown around a change when context is requested.
+    Context,
+    /// Synthetic marker following the last line of a file that has no final newline,
+    /// like git's `\ No newline at end of file`.
+    NoNewline,
+    /// Line of a block deleted in one place and inserted unchanged in another. It
+    /// appears at both places: at the old one with `line_number_a`, at the new one
+    /// with `line_number_b`, and `moved_line` holds the line number on the other side.
+    Move,
+    /// Only in a `RedactionRange`, never in a diff: matches inserted lines by their
+    /// number in file B and deleted, replaced and context lines by their number in
+    /// file A.
+    Any,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiffOutput {
+    pub file_a_hash: [u8; 32],
+    pub file_b_hash: [u8; 32],
+    /// Lines of each file as diffed, summed over every file in directory mode.
+    pub lines_a: usize,
+    pub lines_b: usize,
+    pub diff_lines: Vec<DiffLine>,
+    /// Set in dedup mode: every distinct content of the lines in `diff_lines` and
+    /// `hunks`, once each and in order of first use. The lines carry an index into
+    /// it in place of their content.
+    pub line_dict: Vec<String>,
+    pub edit_distance: usize,
+    pub similarity: f32,
+    pub algorithm: Algorithm,
+    /// Hash function `proof_hash` and the file hashes were computed with.
+    pub hash: HashAlgorithm,
+    pub no_newline_a: bool,
+    pub no_newline_b: bool,
+    /// Byte the contents were split into records on instead of newlines. Line
+    /// numbers count records, and `no_newline_*` means the last record has no
+    /// separator after it.
+    pub record_separator: Option<u8>,
+    pub normalize_eol: bool,
+    pub ignore_whitespace: bool,
+    pub ignore_case: bool,
+    /// Blank lines were left out of the comparison, and those not paired up with
+    /// one on the other side out of `diff_lines`.
+    pub ignore_blank_lines: bool,
+    /// Pattern of the lines compared as equal to each other, whatever they hold.
+    pub ignore_pattern: Option<String>,
+    /// Pattern of the lines aligned with an equal one before the segments between
+    /// them were diffed.
+    pub anchor_pattern: Option<String>,
+    pub detect_moves: bool,
+    /// Every line of file B is in `diff_lines`, with its terminator in `eol` or
+    /// `new_eol`, and `replayed_file_b` rebuilds it to `file_b_hash`.
+    pub include_unchanged: bool,
+    /// Tab width the contents were expanded with before diffing.
+    pub expand_tabs: Option<usize>,
+    /// Longest content committed for a line, in bytes; longer lines are `truncated`.
+    pub max_line_len: Option<usize>,
+    /// Gaps of at most this many unchanged lines between changes were committed as
+    /// context, on top of the usual context lines.
+    pub merge_gap: Option<usize>,
+    /// Set in append-only mode: the leading lines file B keeps unchanged from file
+    /// A, which is all of them.
+    pub prefix_lines: Option<usize>,
+    pub length_unit: LengthUnit,
+    /// Every `redacted_length` is a salted tag rather than a length.
+    pub salted_redaction: bool,
+    /// Set when only part of a file was diffed. Line numbers are still those of the
+    /// whole file.
+    pub range_a: Option<LineRange>,
+    pub range_b: Option<LineRange>,
+    /// Roots of the line Merkle trees, set in Merkle mode.
+    pub merkle_root_a: Option<[u8; 32]>,
+    pub merkle_root_b: Option<[u8; 32]>,
+    /// Contents were decoded lossily from raw bytes.
+    pub binary: bool,
+    /// The edit script was supplied by the prover and only checked against line
+    /// hashes. Lines carry no content and the file hashes are Merkle roots.
+    pub precomputed_script: bool,
+    pub redaction_patterns: Vec<String>,
+    /// Sorted hashes of the lowercased redaction keywords, by the output's hash
+    /// function, and whether the values after them were hidden too.
+    pub redaction_keyword_hashes: Vec<[u8; 32]>,
+    pub redact_keyword_values: bool,
+    /// Every line was redacted whole, so the proof has no content at all.
+    pub redact_all: bool,
+    /// Directory mode: globs of the paths whose files have `redact_all` set in
+    /// `files`.
+    pub redact_paths: Vec<String>,
+    /// Lines with redacted content, and the missing newline markers after them,
+    /// carry no line numbers.
+    pub redact_line_numbers: bool,
+    /// Tokenizer the edit script was found through, when diffing by tokens.
+    pub tokenizer: Option<Tokenizer>,
+    /// Operations left out of `diff_lines`; the edit distance and similarity still
+    /// count them.
+    pub suppressed: Vec<DiffOperation>,
+    /// Redaction profile applied, so a verifier can tell which policy was used.
+    pub redaction_profile: Option<String>,
+    /// Pattern the hunk contexts were found with, and one header per hunk of
+    /// `diff_lines` when it is set.
+    pub hunk_pattern: Option<String>,
+    pub hunk_headers: Vec<HunkHeader>,
+    /// `diff_lines` grouped into hunks, set when they were asked for. The lines are
+    /// copies of those in `diff_lines`.
+    pub hunks: Vec<DiffHunk>,
+    /// Hash of the merge base and the three-way merge over it, set in merge mode.
+    pub base_hash: Option<[u8; 32]>,
+    pub merge_chunks: Vec<MergeChunk>,
+    /// The files were hashed but not diffed: every `diff_lines` is empty and the
+    /// line counts, edit distances and similarities are zero.
+    pub structure_only: bool,
+    pub files: Vec<FileDiff>,
+    /// Set in stats-only mode, where `diff_lines`, `files` and `redaction_patterns`
+    /// are left empty.
+    pub stats: Option<DiffStats>,
+    pub proof_hash: [u8; 32],
+}
+
+/// The hunk starting at `old_start` in file A and `new_start` in file B, with the
+/// nearest line before it that matched the hunk pattern.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HunkHeader {
+    pub old_start: usize,
+    pub new_start: usize,
+    pub hunk_context: Option<String>,
+}
+
+/// Adjacent diff lines with the 1-based start and length of the span they cover
+/// in each file, as `group_hunks` finds them.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// One region of a three-way merge, covering consecutive lines of the base, file A
+/// (ours) and file B (theirs). Starts are 1-based; an empty side starts at the line
+/// after it. The lines of all three sides are included unless the region is
+/// unchanged.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MergeChunk {
+    pub status: MergeStatus,
+    pub base_start: usize,
+    pub base_count: usize,
+    pub ours_start: usize,
+    pub ours_count: usize,
+    pub theirs_start: usize,
+    pub theirs_count: usize,
+    pub base_lines: Vec<String>,
+    pub ours_lines: Vec<String>,
+    pub theirs_lines: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum MergeStatus {
+    /// Same on all three sides.
+    Unchanged,
+    /// Changed only in file A, which the merge takes.
+    Ours,
+    /// Changed only in file B, which the merge takes.
+    Theirs,
+    /// Changed the same way on both sides.
+    Both,
+    /// Changed differently on both sides; the merge can't pick one.
+    Conflict,
+}
+
+/// Changed lines by kind, summed over every file in directory mode. Context and
+/// `NoNewline` lines are not counted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiffStats {
+    pub inserted: usize,
+    pub deleted: usize,
+    pub replaced: usize,
+    /// Moved lines, counted once for both of their places.
+    pub moved: usize,
+    /// Changed lines with any part of either side redacted.
+    pub redacted: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FileDiff {
+    pub path: String,
+    pub status: FileStatus,
+    pub output: DiffOutput,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum FileStatus {
+    Added,
+    Removed,
+    Modified,
+    Unchanged,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiffLine {
+    pub line_number_a: Option<usize>,
+    pub line_number_b: Option<usize>,
+    pub operation: DiffOperation,
+    pub content: Option<String>,
+    /// Index of the content in the output's `line_dict`, in dedup mode.
+    pub content_index: Option<usize>,
+    pub redacted_length: Option<usize>,
+    pub new_content: Option<String>,
+    pub new_content_index: Option<usize>,
+    pub new_redacted_length: Option<usize>,
+    pub eol: Option<LineEnding>,
+    pub new_eol: Option<LineEnding>,
+    pub redacted_spans: Option<Vec<RedactedSpan>>,
+    pub new_redacted_spans: Option<Vec<RedactedSpan>>,
+    /// The content of either side was cut to `max_line_len` bytes, and the byte
+    /// length it had before is in `full_length` or `new_full_length`.
+    pub truncated: bool,
+    pub full_length: Option<usize>,
+    pub new_full_length: Option<usize>,
+    pub word_edits: Option<Vec<WordEdit>>,
+    pub char_edits: Option<Vec<WordEdit>>,
+    pub moved_line: Option<usize>,
+    /// Inclusion paths of the file A and file B line, from the leaf up, in Merkle
+    /// mode.
+    pub merkle_path_a: Option<Vec<MerkleStep>>,
+    pub merkle_path_b: Option<Vec<MerkleStep>>,
+}
+
+/// One level of a Merkle inclusion path: the hash next to the current node and the
+/// side it is on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MerkleStep {
+    pub sibling: [u8; 32],
+    pub sibling_on_left: bool,
+}
+
+/// Original terminator of a line, recorded when line endings are normalized.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Missing,
+}
+
+/// A run of changed tokens within a replaced line. Ranges are half-open indices
+/// into the whitespace-split tokens of the old and new line for `word_edits`, and
+/// into their characters for `char_edits`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WordEdit {
+    pub operation: DiffOperation,
+    pub old_start: usize,
+    pub old_end: usize,
+    pub new_start: usize,
+    pub new_end: usize,
+}
+
+/// Characters `start..end` of the original line, removed from `content`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RedactedSpan {
+    pub start: usize,
+    pub end: usize,
+    pub redacted_length: usize,
+}
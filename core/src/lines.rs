@@ -0,0 +1,1056 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    hash_bytes, hash_content_with, myers_diff, DiffLine, DiffOperation, DiffOptions, Edit, EditOp,
+    Granularity, IgnoredLines, LengthUnit, LineEnding, PatternRedaction, RedactedSpan,
+    RedactionRange, SyncAnchors, WordEdit,
+};
+
+/// `content` with every tab replaced by spaces up to the next multiple of `width`
+/// columns, like `expand -t`. Columns count chars and restart after each newline.
+pub fn tab_expanded(content: &str, width: usize) -> String {
+    let mut expanded = String::with_capacity(content.len());
+    let mut column = 0;
+    for c in content.chars() {
+        match c {
+            '\t' => {
+                let spaces = width - column % width;
+                expanded.push_str(&" ".repeat(spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            _ => {
+                expanded.push(c);
+                column += 1;
+            }
+        }
+    }
+    expanded
+}
+
+/// Lines of `content` like `str::lines`, or with a `separator` its records: the
+/// text up to each separator, and after the last one if there is any. A separator
+/// ending the content doesn't start another, empty, record.
+pub fn records(content: &str, separator: Option<u8>) -> Vec<&str> {
+    match separator {
+        Some(separator) => content.split_terminator(char::from(separator)).collect(),
+        None => content.lines().collect(),
+    }
+}
+
+/// Lines as compared by the diff: each keeps its line terminator, so a last line
+/// without a final newline never matches the same text that has one. With a record
+/// separator, the records keep theirs the same way. With `normalize_eol`, CRLF
+/// terminators compare equal to LF. With `ignore_whitespace`, leading and trailing
+/// whitespace is dropped and inner runs collapse to one space. With `ignore_case`,
+/// ASCII letters compare equal regardless of case. The keys only decide the edit
+/// script; hashes and emitted content use the original lines.
+pub fn comparison_keys(content: &str, options: &DiffOptions) -> Vec<String> {
+    let separator = String::from(options.record_separator.map_or('\n', char::from));
+    content
+        .split_inclusive(separator.as_str())
+        .map(|line| {
+            let (mut text, terminator) = match line.strip_suffix(separator.as_str()) {
+                Some(text) => (text, separator.as_str()),
+                None => (line, ""),
+            };
+            if options.normalize_eol && !terminator.is_empty() {
+                text = text.strip_suffix('\r').unwrap_or(text);
+            }
+            let mut key = if options.ignore_whitespace {
+                let words: Vec<&str> = text.split_whitespace().collect();
+                format!("{}{}", words.join(" "), terminator)
+            } else {
+                format!("{}{}", text, terminator)
+            };
+            if options.ignore_case {
+                key.make_ascii_lowercase();
+            }
+            key
+        })
+        .collect()
+}
+
+/// Replaces the comparison key of each of `lines`, 1-based, with one that only
+/// other ignored lines share, so they compare equal whatever they hold. The key
+/// keeps the line terminator, and starts with one, newline or record `separator`,
+/// which no line's own key can.
+pub fn ignore_matched_lines(keys: &mut [String], lines: &[usize], separator: Option<u8>) {
+    let separator = String::from(separator.map_or('\n', char::from));
+    for &n in lines {
+        let terminator = if keys[n - 1].ends_with(&separator) { separator.as_str() } else { "" };
+        keys[n - 1] = format!("{}ignored{}", separator, terminator);
+    }
+}
+
+pub fn line_endings(content: &str) -> Vec<LineEnding> {
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            if line.ends_with("\r\n") {
+                LineEnding::Crlf
+            } else if line.ends_with('\n') {
+                LineEnding::Lf
+            } else {
+                LineEnding::Missing
+            }
+        })
+        .collect()
+}
+
+/// Records the original terminator of every emitted line, alongside `content` in
+/// `eol` and alongside `new_content` in `new_eol`.
+pub fn annotate_line_endings(
+    diff_lines: &mut [DiffLine],
+    endings_a: &[LineEnding],
+    endings_b: &[LineEnding],
+) {
+    for line in diff_lines {
+        let ending_a = line.line_number_a.map(|n| endings_a[n - 1]);
+        let ending_b = line.line_number_b.map(|n| endings_b[n - 1]);
+        match line.operation {
+            DiffOperation::Delete | DiffOperation::Context => line.eol = ending_a,
+            DiffOperation::Insert => line.eol = ending_b,
+            DiffOperation::Move => line.eol = ending_a.or(ending_b),
+            DiffOperation::Replace => {
+                line.eol = ending_a;
+                line.new_eol = ending_b;
+            }
+            DiffOperation::NoNewline | DiffOperation::Any => {}
+        }
+    }
+}
+
+/// Whether the last line of `content` has no newline after it, or with a
+/// `separator` the last record no separator.
+pub fn missing_final_newline(content: &str, separator: Option<u8>) -> bool {
+    !content.is_empty() && !content.ends_with(separator.map_or('\n', char::from))
+}
+
+/// Inserts a `NoNewline` marker after the diff line showing the last line of each
+/// file that lacks a final newline. Files whose last line is unchanged get no marker.
+pub fn mark_missing_newlines(
+    diff_lines: &mut Vec<DiffLine>,
+    lines_a: usize,
+    lines_b: usize,
+    no_newline_a: bool,
+    no_newline_b: bool,
+) {
+    if no_newline_a {
+        let last = diff_lines.iter().rposition(|line| {
+            line.operation != DiffOperation::Insert && line.line_number_a == Some(lines_a)
+        });
+        if let Some(pos) = last {
+            diff_lines.insert(pos + 1, DiffLine {
+                line_number_a: Some(lines_a),
+                ..empty_line(DiffOperation::NoNewline)
+            });
+        }
+    }
+    if no_newline_b {
+        let last = diff_lines.iter().rposition(|line| {
+            line.operation != DiffOperation::NoNewline && line.line_number_b == Some(lines_b)
+        });
+        if let Some(pos) = last {
+            // keep the file A marker first when both annotate the same replaced line
+            let marked_a = diff_lines
+                .get(pos + 1)
+                .is_some_and(|line| line.operation == DiffOperation::NoNewline);
+            let pos = if marked_a { pos + 2 } else { pos + 1 };
+            diff_lines.insert(pos, DiffLine {
+                line_number_b: Some(lines_b),
+                ..empty_line(DiffOperation::NoNewline)
+            });
+        }
+    }
+}
+
+/// Keeps only the diff lines whose operation `keep` accepts, along with the missing
+/// newline markers of the lines kept.
+pub fn retain_operations(diff_lines: &mut Vec<DiffLine>, keep: impl Fn(&DiffOperation) -> bool) {
+    let mut kept = false;
+    diff_lines.retain(|line| {
+        if line.operation != DiffOperation::NoNewline {
+            kept = keep(&line.operation);
+        }
+        kept
+    });
+}
+
+/// Clears both line numbers of every line with any redacted content, and of the
+/// missing newline markers following one, which would otherwise give away the
+/// length of its file. Runs after everything that places lines by their numbers.
+pub fn hide_redacted_line_numbers(diff_lines: &mut [DiffLine]) {
+    let mut hidden = false;
+    for line in diff_lines {
+        if line.operation != DiffOperation::NoNewline {
+            hidden = line.redacted_length.is_some()
+                || line.new_redacted_length.is_some()
+                || line.redacted_spans.is_some()
+                || line.new_redacted_spans.is_some();
+        }
+        if hidden {
+            line.line_number_a = None;
+            line.line_number_b = None;
+        }
+    }
+}
+
+/// Cuts the committed content of each side of every line down to its first
+/// `max_len` bytes, at a character boundary, marking the line `truncated` and
+/// recording the length the side had. The lines were compared in full before this,
+/// so only what the proof shows is shortened.
+pub fn truncate_long_lines(diff_lines: &mut [DiffLine], max_len: usize) {
+    for line in diff_lines {
+        line.full_length = truncate_content(&mut line.content, max_len);
+        line.new_full_length = truncate_content(&mut line.new_content, max_len);
+        line.truncated = line.full_length.is_some() || line.new_full_length.is_some();
+    }
+}
+
+fn truncate_content(content: &mut Option<String>, max_len: usize) -> Option<usize> {
+    let content = content.as_mut().filter(|content| content.len() > max_len)?;
+    let full_length = content.len();
+    let cut = (0..=max_len).rev().find(|&i| content.is_char_boundary(i)).unwrap_or(0);
+    content.truncate(cut);
+    Some(full_length)
+}
+
+/// File B rebuilt from `diff_lines` alone, or `None` unless they list every line
+/// of it in order with its text and terminator in full, as with `include_unchanged`.
+pub fn replayed_file_b(diff_lines: &[DiffLine]) -> Option<String> {
+    let mut file_b = String::new();
+    let mut next_b = 1;
+    for line in diff_lines {
+        let (content, eol, spans, full_length) = match line.operation {
+            DiffOperation::Context | DiffOperation::Insert | DiffOperation::Move
+                if line.line_number_b.is_some() =>
+            {
+                (&line.content, line.eol, &line.redacted_spans, line.full_length)
+            }
+            DiffOperation::Replace => {
+                (&line.new_content, line.new_eol, &line.new_redacted_spans, line.new_full_length)
+            }
+            _ => continue,
+        };
+        if line.line_number_b != Some(next_b) || spans.is_some() || full_length.is_some() {
+            return None;
+        }
+        file_b.push_str(content.as_ref()?);
+        file_b.push_str(match eol? {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Missing => "",
+        });
+        next_b += 1;
+    }
+    Some(file_b)
+}
+
+/// Replaces the content of both sides of each of `lines` by its index in the
+/// returned dictionary, which holds every distinct content once, in order of first
+/// use. Redacted sides have no content and are left as they are.
+pub fn dedup_line_contents<'a>(lines: impl IntoIterator<Item = &'a mut DiffLine>) -> Vec<String> {
+    let mut dict = Vec::new();
+    let mut indices: BTreeMap<String, usize> = BTreeMap::new();
+    for line in lines {
+        let sides = [
+            (&mut line.content, &mut line.content_index),
+            (&mut line.new_content, &mut line.new_content_index),
+        ];
+        for (content, index) in sides {
+            if let Some(content) = content.take() {
+                let next = dict.len();
+                *index = Some(*indices.entry(content.clone()).or_insert_with(|| {
+                    dict.push(content);
+                    next
+                }));
+            }
+        }
+    }
+    dict
+}
+
+/// Puts back the content `dedup_line_contents` replaced by indices into `dict`, or
+/// returns the first index outside it.
+pub fn restore_line_contents<'a>(
+    lines: impl IntoIterator<Item = &'a mut DiffLine>,
+    dict: &[String],
+) -> Result<(), usize> {
+    for line in lines {
+        let sides = [
+            (&mut line.content, &mut line.content_index),
+            (&mut line.new_content, &mut line.new_content_index),
+        ];
+        for (content, index) in sides {
+            if let Some(index) = index.take() {
+                *content = Some(dict.get(index).ok_or(index)?.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Everything redaction hid in `diff_lines`, in diff order: each hidden line, or
+/// each hidden span of a line, followed by a newline, file A's side of a replaced
+/// line before file B's. Expects the line numbers still in place.
+pub fn redacted_text(diff_lines: &[DiffLine], lines_a: &[&str], lines_b: &[&str]) -> String {
+    let mut text = String::new();
+    for line in diff_lines {
+        let source = match line.operation {
+            DiffOperation::Insert => line.line_number_b.map(|b| lines_b[b - 1]),
+            DiffOperation::Delete | DiffOperation::Context | DiffOperation::Replace => {
+                line.line_number_a.map(|a| lines_a[a - 1])
+            }
+            DiffOperation::Move | DiffOperation::NoNewline | DiffOperation::Any => None,
+        };
+        if let Some(source) = source {
+            push_hidden(&mut text, source, line.redacted_length, &line.redacted_spans);
+        }
+        if let (DiffOperation::Replace, Some(b)) = (&line.operation, line.line_number_b) {
+            let (length, spans) = (line.new_redacted_length, &line.new_redacted_spans);
+            push_hidden(&mut text, lines_b[b - 1], length, spans);
+        }
+    }
+    text
+}
+
+fn push_hidden(
+    text: &mut String,
+    line: &str,
+    redacted_length: Option<usize>,
+    spans: &Option<Vec<RedactedSpan>>,
+) {
+    if redacted_length.is_some() {
+        text.push_str(line);
+        text.push('\n');
+    }
+    for span in spans.iter().flatten() {
+        text.extend(line.chars().skip(span.start).take(span.end - span.start));
+        text.push('\n');
+    }
+}
+
+/// Shortest block reported as a move; shorter ones, like a lone closing brace, match
+/// elsewhere too easily.
+const MIN_MOVE_LINES: usize = 3;
+
+/// Turns every run of deleted lines that reappears as a run of inserted lines with
+/// the same comparison keys into `Move` lines at both places. Runs are taken in
+/// diff order and each pairs with the first unpaired match. Runs with any redaction
+/// are skipped, since pairing them would reveal that the hidden lines are equal.
+pub fn mark_moves(diff_lines: &mut [DiffLine], keys_a: &[&str], keys_b: &[&str]) {
+    let deletes = runs(diff_lines, DiffOperation::Delete, |line| line.line_number_a);
+    let inserts = runs(diff_lines, DiffOperation::Insert, |line| line.line_number_b);
+    let mut paired = vec![false; inserts.len()];
+
+    for &(delete_start, len) in &deletes {
+        let start_a = diff_lines[delete_start].line_number_a.unwrap();
+        let found = (0..inserts.len()).find(|&i| {
+            let (insert_start, insert_len) = inserts[i];
+            let start_b = diff_lines[insert_start].line_number_b.unwrap();
+            !paired[i]
+                && insert_len == len
+                && (0..len).all(|j| keys_a[start_a - 1 + j] == keys_b[start_b - 1 + j])
+        });
+        let Some(i) = found else { continue };
+        paired[i] = true;
+
+        let insert_start = inserts[i].0;
+        let start_b = diff_lines[insert_start].line_number_b.unwrap();
+        for j in 0..len {
+            diff_lines[delete_start + j].operation = DiffOperation::Move;
+            diff_lines[delete_start + j].moved_line = Some(start_b + j);
+            diff_lines[insert_start + j].operation = DiffOperation::Move;
+            diff_lines[insert_start + j].moved_line = Some(start_a + j);
+        }
+    }
+}
+
+/// Maximal runs of fully visible `operation` lines with consecutive line numbers,
+/// as (index of first line, length), keeping only those long enough to be a move.
+fn runs(
+    diff_lines: &[DiffLine],
+    operation: DiffOperation,
+    line_number: fn(&DiffLine) -> Option<usize>,
+) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (i, line) in diff_lines.iter().enumerate() {
+        let visible = line.content.is_some() && line.redacted_spans.is_none();
+        let extends = runs.last().is_some_and(|&(start, len)| {
+            start + len == i && line_number(&diff_lines[i - 1]).map(|n| n + 1) == line_number(line)
+        });
+        match (line.operation == operation && visible, extends) {
+            (true, true) => runs.last_mut().unwrap().1 += 1,
+            (true, false) => runs.push((i, 1)),
+            (false, _) => {}
+        }
+    }
+    runs.retain(|&(_, len)| len >= MIN_MOVE_LINES);
+    runs
+}
+
+/// Turns an edit script into diff lines, in a canonical order that doesn't depend
+/// on how the algorithm interleaved its edits: changes appear in file order, and
+/// within each run of changes between unchanged lines the deleted lines come before
+/// the inserted ones, each in ascending line number. A run with as many deletions
+/// as insertions becomes replaced lines, paired in that order.
+pub fn create_diff_lines(
+    edits: &[Edit],
+    lines_a: &[&str],
+    lines_b: &[&str],
+    redaction_ranges: &[RedactionRange],
+    options: &DiffOptions,
+) -> Vec<DiffLine> {
+    let mut diff_lines = Vec::new();
+    let in_context = if options.include_unchanged {
+        vec![true; edits.len()]
+    } else {
+        context_edits(edits, options.context, options.merge_gap.unwrap_or(0))
+    };
+    let mut i = 0;
+    
+    while i < edits.len() {
+        if edits[i].operation == EditOp::Keep {
+            if in_context[i] {
+                diff_lines.push(context_line(&edits[i], lines_a, redaction_ranges, options));
+            }
+            i += 1;
+            continue;
+        }
+        
+        let block_start = i;
+        while i < edits.len() && edits[i].operation != EditOp::Keep {
+            i += 1;
+        }
+        let block = &edits[block_start..i];
+        
+        let deletes: Vec<&Edit> = block.iter().filter(|e| e.operation == EditOp::Delete).collect();
+        let inserts: Vec<&Edit> = block.iter().filter(|e| e.operation == EditOp::Insert).collect();
+        
+        // a block with matching counts is a run of modified lines; pair them up in order
+        if deletes.len() == inserts.len() {
+            for (delete, insert) in deletes.iter().zip(inserts.iter()) {
+                diff_lines.push(replace_line(
+                    delete.old_index,
+                    insert.new_index,
+                    lines_a,
+                    lines_b,
+                    redaction_ranges,
+                    options,
+                ));
+            }
+            continue;
+        }
+        
+        // canonical order, whatever order the algorithm produced the edits in: all
+        // deletions then all insertions, each by ascending line number
+        for edit in deletes.into_iter().chain(inserts) {
+            match edit.operation {
+                EditOp::Delete => {
+                    let line_content = lines_a[edit.old_index];
+                    let ranges: Vec<&RedactionRange> =
+                        matching_ranges(edit.old_index, &DiffOperation::Delete, redaction_ranges)
+                            .collect();
+                    let redaction = redact_spans(line_content, &ranges);
+                    let side = apply_redaction(line_content, redaction, options);
+                    
+                    diff_lines.push(DiffLine {
+                        line_number_a: Some(edit.old_index + 1),
+                        content: side.content,
+                        redacted_length: side.redacted_length,
+                        redacted_spans: side.redacted_spans,
+                        ..empty_line(DiffOperation::Delete)
+                    });
+                }
+                EditOp::Insert => {
+                    let line_content = lines_b[edit.new_index];
+                    let ranges: Vec<&RedactionRange> =
+                        matching_ranges(edit.new_index, &DiffOperation::Insert, redaction_ranges)
+                            .collect();
+                    let redaction = redact_spans(line_content, &ranges);
+                    let side = apply_redaction(line_content, redaction, options);
+                    
+                    diff_lines.push(DiffLine {
+                        line_number_b: Some(edit.new_index + 1),
+                        content: side.content,
+                        redacted_length: side.redacted_length,
+                        redacted_spans: side.redacted_spans,
+                        ..empty_line(DiffOperation::Insert)
+                    });
+                }
+                EditOp::Keep => unreachable!(),
+            }
+        }
+    }
+    
+    diff_lines
+}
+
+/// Marks the Keep edits within `context` lines of a change, and all of those
+/// between two changes that would leave at most `merge_gap` of them out.
+fn context_edits(edits: &[Edit], context: usize, merge_gap: usize) -> Vec<bool> {
+    let mut in_context = vec![false; edits.len()];
+    if context == 0 && merge_gap == 0 {
+        return in_context;
+    }
+    
+    let mut i = 0;
+    while i < edits.len() {
+        if edits[i].operation != EditOp::Keep {
+            i += 1;
+            continue;
+        }
+        let run_start = i;
+        while i < edits.len() && edits[i].operation == EditOp::Keep {
+            i += 1;
+        }
+        let run_end = i;
+        
+        let between_changes = run_start > 0 && run_end < edits.len();
+        if between_changes && run_end - run_start <= 2 * context + merge_gap {
+            in_context[run_start..run_end].fill(true);
+            continue;
+        }
+        if run_start > 0 {
+            in_context[run_start..run_end.min(run_start + context)].fill(true);
+        }
+        if run_end < edits.len() {
+            in_context[run_end.saturating_sub(context).max(run_start)..run_end].fill(true);
+        }
+    }
+    in_context
+}
+
+fn context_line(
+    edit: &Edit,
+    lines_a: &[&str],
+    redaction_ranges: &[RedactionRange],
+    options: &DiffOptions,
+) -> DiffLine {
+    let line_content = lines_a[edit.old_index];
+    let ranges: Vec<&RedactionRange> =
+        matching_ranges(edit.old_index, &DiffOperation::Context, redaction_ranges).collect();
+    let redaction = redact_spans(line_content, &ranges);
+    let side = apply_redaction(line_content, redaction, options);
+    
+    DiffLine {
+        line_number_a: Some(edit.old_index + 1),
+        line_number_b: Some(edit.new_index + 1),
+        content: side.content,
+        redacted_length: side.redacted_length,
+        redacted_spans: side.redacted_spans,
+        ..empty_line(DiffOperation::Context)
+    }
+}
+
+fn replace_line(
+    old_index: usize,
+    new_index: usize,
+    lines_a: &[&str],
+    lines_b: &[&str],
+    redaction_ranges: &[RedactionRange],
+    options: &DiffOptions,
+) -> DiffLine {
+    let old_line = lines_a[old_index];
+    let new_line = lines_b[new_index];
+    
+    // replace ranges are given in file A line numbers and hide both sides; delete and
+    // insert ranges still hide their own side so existing redactions keep covering
+    // lines that are now reported as replacements
+    let old_ranges: Vec<&RedactionRange> =
+        matching_ranges(old_index, &DiffOperation::Replace, redaction_ranges)
+            .chain(matching_ranges(old_index, &DiffOperation::Delete, redaction_ranges))
+            .collect();
+    let new_ranges: Vec<&RedactionRange> =
+        matching_ranges(old_index, &DiffOperation::Replace, redaction_ranges)
+            .chain(matching_ranges(new_index, &DiffOperation::Insert, redaction_ranges))
+            .collect();
+    let redact_old = redact_spans(old_line, &old_ranges);
+    let redact_new = redact_spans(new_line, &new_ranges);
+    
+    if options.granularity == Granularity::Char {
+        return char_replace_line(
+            old_index,
+            new_index,
+            old_line,
+            new_line,
+            redact_old,
+            redact_new,
+            options,
+        );
+    }
+    
+    // token positions would leak the shape of a redacted side, so only break down
+    // lines that are fully visible
+    let word_edits = if options.granularity == Granularity::Word
+        && redact_old == Redaction::Visible
+        && redact_new == Redaction::Visible
+    {
+        let old_tokens: Vec<&str> = old_line.split_whitespace().collect();
+        let new_tokens: Vec<&str> = new_line.split_whitespace().collect();
+        Some(span_edits(&old_tokens, &new_tokens))
+    } else {
+        None
+    };
+    
+    let old_side = apply_redaction(old_line, redact_old, options);
+    let new_side = apply_redaction(new_line, redact_new, options);
+    
+    DiffLine {
+        line_number_a: Some(old_index + 1),
+        line_number_b: Some(new_index + 1),
+        content: old_side.content,
+        redacted_length: old_side.redacted_length,
+        redacted_spans: old_side.redacted_spans,
+        new_content: new_side.content,
+        new_redacted_length: new_side.redacted_length,
+        new_redacted_spans: new_side.redacted_spans,
+        word_edits,
+        ..empty_line(DiffOperation::Replace)
+    }
+}
+
+/// Character-level replace: a side redacted as a whole line only loses the
+/// characters that changed, keeping the unchanged prefix, suffix and any text in
+/// between as content. Column redactions still hide exactly their columns.
+fn char_replace_line(
+    old_index: usize,
+    new_index: usize,
+    old_line: &str,
+    new_line: &str,
+    redact_old: Redaction,
+    redact_new: Redaction,
+    options: &DiffOptions,
+) -> DiffLine {
+    let old_chars = char_tokens(old_line);
+    let new_chars = char_tokens(new_line);
+    let char_edits = span_edits(&old_chars, &new_chars);
+    
+    let redact_old = match redact_old {
+        Redaction::Whole => {
+            Redaction::Spans(char_edits.iter().map(|e| (e.old_start, e.old_end)).collect())
+        }
+        other => other,
+    };
+    let redact_new = match redact_new {
+        Redaction::Whole => {
+            Redaction::Spans(char_edits.iter().map(|e| (e.new_start, e.new_end)).collect())
+        }
+        other => other,
+    };
+    let old_side = apply_redaction(old_line, redact_old, options);
+    let new_side = apply_redaction(new_line, redact_new, options);
+    
+    DiffLine {
+        line_number_a: Some(old_index + 1),
+        line_number_b: Some(new_index + 1),
+        content: old_side.content,
+        redacted_spans: old_side.redacted_spans,
+        new_content: new_side.content,
+        new_redacted_spans: new_side.redacted_spans,
+        char_edits: Some(char_edits),
+        ..empty_line(DiffOperation::Replace)
+    }
+}
+
+fn char_tokens(line: &str) -> Vec<&str> {
+    line.char_indices()
+        .map(|(i, c)| &line[i..i + c.len_utf8()])
+        .collect()
+}
+
+/// How much of one side of a diff line is hidden.
+#[derive(Debug, Clone, PartialEq)]
+enum Redaction {
+    Visible,
+    Whole,
+    /// Half-open char ranges, sorted and non-overlapping.
+    Spans(Vec<(usize, usize)>),
+}
+
+/// One side of a diff line after redaction.
+struct RedactedText {
+    content: Option<String>,
+    redacted_length: Option<usize>,
+    redacted_spans: Option<Vec<RedactedSpan>>,
+}
+
+/// Resolves the ranges that apply to a line into what to hide: any range without
+/// columns hides the whole line, otherwise the union of the column spans is hidden.
+fn redact_spans(line: &str, ranges: &[&RedactionRange]) -> Redaction {
+    if ranges.is_empty() {
+        return Redaction::Visible;
+    }
+    
+    let char_count = line.chars().count();
+    let mut spans = Vec::new();
+    for range in ranges {
+        match (range.start_col, range.end_col) {
+            (None, None) => return Redaction::Whole,
+            (start, end) => {
+                // columns are 1-based and inclusive like line numbers
+                let start = start.unwrap_or(1).saturating_sub(1).min(char_count);
+                let end = end.unwrap_or(char_count).min(char_count);
+                if start < end {
+                    spans.push((start, end));
+                }
+            }
+        }
+    }
+    
+    spans.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    
+    if merged.is_empty() {
+        Redaction::Visible
+    } else if merged == [(0, char_count)] {
+        Redaction::Whole
+    } else {
+        Redaction::Spans(merged)
+    }
+}
+
+fn apply_redaction(line: &str, redaction: Redaction, options: &DiffOptions) -> RedactedText {
+    match redaction {
+        Redaction::Visible => RedactedText {
+            content: Some(line.to_string()),
+            redacted_length: None,
+            redacted_spans: None,
+        },
+        Redaction::Whole => RedactedText {
+            content: None,
+            redacted_length: Some(redacted_length(line, options)),
+            redacted_spans: None,
+        },
+        Redaction::Spans(spans) => {
+            let (content, redacted_spans) = should_redact_span(&char_tokens(line), &spans, options);
+            RedactedText {
+                content: Some(content),
+                redacted_length: None,
+                redacted_spans,
+            }
+        }
+    }
+}
+
+/// Length of hidden text in `length_unit`. A span's graphemes are counted on its
+/// own text, so a span cutting through a cluster counts the pieces it holds. With a
+/// redaction salt it is instead the first 4 bytes of `SHA256(salt || text)`, which
+/// tells two hidden texts apart without revealing their length.
+fn redacted_length(text: &str, options: &DiffOptions) -> usize {
+    if let Some(salt) = &options.redaction_salt {
+        let tag = hash_bytes(&[salt.as_slice(), text.as_bytes()].concat());
+        return u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]) as usize;
+    }
+    match options.length_unit {
+        LengthUnit::Bytes => text.len(),
+        LengthUnit::Chars => text.chars().count(),
+        LengthUnit::Graphemes => text.graphemes(true).count(),
+    }
+}
+
+/// Removes the given half-open char ranges from a line, returning the remaining
+/// content and the spans that were hidden. Empty ranges hide nothing.
+fn should_redact_span(
+    chars: &[&str],
+    spans: &[(usize, usize)],
+    options: &DiffOptions,
+) -> (String, Option<Vec<RedactedSpan>>) {
+    let mut content = String::new();
+    let mut redacted_spans = Vec::new();
+    let mut pos = 0;
+    
+    for &(start, end) in spans {
+        if start == end {
+            continue;
+        }
+        content.push_str(&chars[pos..start].concat());
+        redacted_spans.push(RedactedSpan {
+            start,
+            end,
+            redacted_length: redacted_length(&chars[start..end].concat(), options),
+        });
+        pos = end;
+    }
+    content.push_str(&chars[pos..].concat());
+    
+    if redacted_spans.is_empty() {
+        (content, None)
+    } else {
+        (content, Some(redacted_spans))
+    }
+}
+
+/// Groups an edit script over tokens into runs of changes with half-open token
+/// ranges on both sides.
+fn span_edits(old_tokens: &[&str], new_tokens: &[&str]) -> Vec<WordEdit> {
+    let edits = myers_diff(old_tokens, new_tokens);
+    
+    let mut span_edits = Vec::new();
+    let mut i = 0;
+    
+    while i < edits.len() {
+        if edits[i].operation == EditOp::Keep {
+            i += 1;
+            continue;
+        }
+        
+        let old_start = edits[i].old_index;
+        let new_start = edits[i].new_index;
+        let mut deleted = 0;
+        let mut inserted = 0;
+        while i < edits.len() && edits[i].operation != EditOp::Keep {
+            match edits[i].operation {
+                EditOp::Delete => deleted += 1,
+                EditOp::Insert => inserted += 1,
+                EditOp::Keep => unreachable!(),
+            }
+            i += 1;
+        }
+        
+        let operation = match (deleted, inserted) {
+            (_, 0) => DiffOperation::Delete,
+            (0, _) => DiffOperation::Insert,
+            _ => DiffOperation::Replace,
+        };
+        span_edits.push(WordEdit {
+            operation,
+            old_start,
+            old_end: old_start + deleted,
+            new_start,
+            new_end: new_start + inserted,
+        });
+    }
+    
+    span_edits
+}
+
+pub(crate) fn empty_line(operation: DiffOperation) -> DiffLine {
+    DiffLine {
+        line_number_a: None,
+        line_number_b: None,
+        operation,
+        content: None,
+        content_index: None,
+        redacted_length: None,
+        new_content: None,
+        new_content_index: None,
+        new_redacted_length: None,
+        eol: None,
+        new_eol: None,
+        redacted_spans: None,
+        new_redacted_spans: None,
+        truncated: false,
+        full_length: None,
+        new_full_length: None,
+        word_edits: None,
+        char_edits: None,
+        moved_line: None,
+        merkle_path_a: None,
+        merkle_path_b: None,
+    }
+}
+
+fn matching_ranges<'a>(
+    line_number: usize,
+    operation: &'a DiffOperation,
+    redaction_ranges: &'a [RedactionRange],
+) -> impl Iterator<Item = &'a RedactionRange> {
+    // replaced lines are looked up per side as deleted and inserted, so `Any`
+    // matches each side by its own number
+    let any = *operation != DiffOperation::Replace;
+    redaction_ranges.iter().filter(move |range| {
+        (range.operation == *operation || any && range.operation == DiffOperation::Any) &&
+        line_number >= range.start_line.saturating_sub(1) &&
+        line_number < range.end_line
+    })
+}
+
+/// A regex can't be re-run here, but it must give the same answer for identical
+/// lines: every line equal to a matched line has to be matched too, in both files.
+pub fn pattern_matches_consistent(
+    redaction: &PatternRedaction,
+    lines_a: &[&str],
+    lines_b: &[&str],
+) -> bool {
+    let in_bounds = |numbers: &[usize], len: usize| numbers.iter().all(|&n| n >= 1 && n <= len);
+    if !in_bounds(&redaction.lines_a, lines_a.len())
+        || !in_bounds(&redaction.lines_b, lines_b.len())
+    {
+        return false;
+    }
+    
+    let matched: BTreeSet<&str> = redaction
+        .lines_a
+        .iter()
+        .map(|&n| lines_a[n - 1])
+        .chain(redaction.lines_b.iter().map(|&n| lines_b[n - 1]))
+        .collect();
+    let marked_a: BTreeSet<usize> = redaction.lines_a.iter().copied().collect();
+    let marked_b: BTreeSet<usize> = redaction.lines_b.iter().copied().collect();
+    
+    let consistent = |lines: &[&str], marked: &BTreeSet<usize>| {
+        lines
+            .iter()
+            .enumerate()
+            .all(|(i, line)| !matched.contains(line) || marked.contains(&(i + 1)))
+    };
+    consistent(lines_a, &marked_a) && consistent(lines_b, &marked_b)
+}
+
+/// Same check as for redaction patterns, so the host can't leave out some of the
+/// lines equal to an ignored one.
+pub fn ignored_lines_consistent(
+    ignored: &IgnoredLines,
+    lines_a: &[&str],
+    lines_b: &[&str],
+) -> bool {
+    let as_redaction = PatternRedaction {
+        pattern: ignored.pattern.clone(),
+        lines_a: ignored.lines_a.clone(),
+        lines_b: ignored.lines_b.clone(),
+    };
+    pattern_matches_consistent(&as_redaction, lines_a, lines_b)
+}
+
+pub fn sync_anchors_consistent(anchors: &SyncAnchors, lines_a: &[&str], lines_b: &[&str]) -> bool {
+    let as_redaction = PatternRedaction {
+        pattern: anchors.pattern.clone(),
+        lines_a: anchors.lines_a.clone(),
+        lines_b: anchors.lines_b.clone(),
+    };
+    pattern_matches_consistent(&as_redaction, lines_a, lines_b)
+}
+
+/// Matched lines become single-line ranges: file A lines hide deleted, context and
+/// old replaced text, file B lines hide inserted and new replaced text.
+pub fn pattern_redaction_ranges(redaction: &PatternRedaction) -> Vec<RedactionRange> {
+    let single = |line: usize, operation: DiffOperation| RedactionRange {
+        start_line: line,
+        end_line: line,
+        start_col: None,
+        end_col: None,
+        operation,
+    };
+    redaction
+        .lines_a
+        .iter()
+        .flat_map(|&n| [single(n, DiffOperation::Delete), single(n, DiffOperation::Context)])
+        .chain(redaction.lines_b.iter().map(|&n| single(n, DiffOperation::Insert)))
+        .collect()
+}
+
+/// Whole-line ranges hiding every line of files of `len_a` and `len_b` lines: file
+/// A lines deleted, kept as context or replaced, and file B lines inserted or
+/// replacing them.
+pub fn redact_all_ranges(len_a: usize, len_b: usize) -> Vec<RedactionRange> {
+    let whole = |len: usize, operation: DiffOperation| RedactionRange {
+        start_line: 1,
+        end_line: len,
+        start_col: None,
+        end_col: None,
+        operation,
+    };
+    [
+        whole(len_a, DiffOperation::Delete),
+        whole(len_a, DiffOperation::Context),
+        whole(len_b, DiffOperation::Insert),
+    ]
+    .into_iter()
+    .filter(|range| range.end_line > 0)
+    .collect()
+}
+
+/// Column ranges hiding every occurrence of the redaction keywords, found ignoring
+/// ASCII case, and with `redact_keyword_values` the `=value` after each one up to
+/// the next whitespace. As for patterns, file A lines hide deleted, context and old
+/// replaced text, file B lines inserted and new replaced text.
+///
+/// # Panics
+///
+/// Panics if a keyword is empty.
+pub fn keyword_redaction_ranges(
+    options: &DiffOptions,
+    lines_a: &[&str],
+    lines_b: &[&str],
+) -> Vec<RedactionRange> {
+    let keywords: Vec<Vec<char>> = options
+        .redact_keywords
+        .iter()
+        .map(|keyword| keyword.chars().map(|c| c.to_ascii_lowercase()).collect())
+        .collect();
+    assert!(keywords.iter().all(|k| !k.is_empty()), "Empty redaction keyword");
+
+    let mut ranges = Vec::new();
+    let sides = [
+        (lines_a, &[DiffOperation::Delete, DiffOperation::Context][..]),
+        (lines_b, &[DiffOperation::Insert][..]),
+    ];
+    for (lines, operations) in sides {
+        for (i, line) in lines.iter().enumerate() {
+            for (start, end) in keyword_spans(line, &keywords, options.redact_keyword_values) {
+                ranges.extend(operations.iter().map(|operation| RedactionRange {
+                    start_line: i + 1,
+                    end_line: i + 1,
+                    start_col: Some(start + 1),
+                    end_col: Some(end),
+                    operation: operation.clone(),
+                }));
+            }
+        }
+    }
+    ranges
+}
+
+/// Half-open char spans of the keyword matches in `line`, overlapping ones included.
+fn keyword_spans(line: &str, keywords: &[Vec<char>], values: bool) -> Vec<(usize, usize)> {
+    let chars: Vec<char> = line.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let mut spans = Vec::new();
+    for keyword in keywords {
+        for start in 0..chars.len().saturating_sub(keyword.len() - 1) {
+            if chars[start..start + keyword.len()] == keyword[..] {
+                let end = start + keyword.len();
+                spans.push((start, if values { value_end(&chars, end) } else { end }));
+            }
+        }
+    }
+    spans
+}
+
+/// End of the `= value` starting at `end`, blanks around the `=` allowed, or `end`
+/// itself when no `=` follows.
+fn value_end(chars: &[char], end: usize) -> usize {
+    let blank = |c: &char| *c == ' ' || *c == '\t';
+    let skip_blanks = |i: usize| i + chars[i..].iter().take_while(|c| blank(c)).count();
+    let equals = skip_blanks(end);
+    if chars.get(equals) != Some(&'=') {
+        return end;
+    }
+    let value = skip_blanks(equals + 1);
+    value + chars[value..].iter().take_while(|c| !c.is_whitespace()).count()
+}
+
+/// What the output commits of the keywords: their sorted, deduplicated hashes,
+/// lowercased since they match ignoring case.
+pub fn keyword_hashes(options: &DiffOptions) -> Vec<[u8; 32]> {
+    let mut hashes: Vec<[u8; 32]> = options
+        .redact_keywords
+        .iter()
+        .map(|keyword| hash_content_with(options.hash, &keyword.to_ascii_lowercase()))
+        .collect();
+    hashes.sort();
+    hashes.dedup();
+    hashes
+}
@@ -0,0 +1,115 @@
+use crate::diff::line_edits;
+use crate::{comparison_keys, records, Algorithm, DiffInput, EditOp, MergeChunk, MergeStatus};
+
+/// Three-way merge of file A (ours) and file B (theirs) against `input.merge_base`,
+/// in base order, the way `diff3` does it. Both sides are diffed against the base
+/// with the chosen algorithm, comparing lines as the diff does. Runs of base lines
+/// kept by both sides are unchanged regions, and every stretch between them is
+/// taken from whichever side changed it, from either when both made the same
+/// change, and is a conflict otherwise. Empty without a merge base.
+///
+/// # Panics
+///
+/// Panics if the input also redacts lines, since merge regions carry their lines in
+/// full.
+pub fn merge_chunks(input: &DiffInput) -> Vec<MergeChunk> {
+    let Some(base) = &input.merge_base else {
+        return Vec::new();
+    };
+    assert!(
+        input.redaction_ranges.is_empty()
+            && input.pattern_redactions.is_empty()
+            && input.options.redact_keywords.is_empty(),
+        "Redaction isn't supported in merge mode"
+    );
+
+    let separator = input.options.record_separator;
+    let lines_base = records(&base.content, separator);
+    let lines_ours = records(&input.file_a_content, separator);
+    let lines_theirs = records(&input.file_b_content, separator);
+    let keys_base = comparison_keys(&base.content, &input.options);
+    let keys_ours = comparison_keys(&input.file_a_content, &input.options);
+    let keys_theirs = comparison_keys(&input.file_b_content, &input.options);
+    let keys_base: Vec<&str> = keys_base.iter().map(String::as_str).collect();
+    let keys_ours: Vec<&str> = keys_ours.iter().map(String::as_str).collect();
+    let keys_theirs: Vec<&str> = keys_theirs.iter().map(String::as_str).collect();
+
+    let algorithm = input.options.algorithm;
+    let kept_ours = kept_lines(&keys_base, &keys_ours, algorithm);
+    let kept_theirs = kept_lines(&keys_base, &keys_theirs, algorithm);
+
+    let mut chunks = Vec::new();
+    let (mut i, mut o, mut t) = (0, 0, 0);
+    loop {
+        let stable = i;
+        while i < lines_base.len() && kept_ours[i] == Some(o) && kept_theirs[i] == Some(t) {
+            i += 1;
+            o += 1;
+            t += 1;
+        }
+        if i > stable {
+            let count = i - stable;
+            chunks.push(MergeChunk {
+                status: MergeStatus::Unchanged,
+                base_start: stable + 1,
+                base_count: count,
+                ours_start: o - count + 1,
+                ours_count: count,
+                theirs_start: t - count + 1,
+                theirs_count: count,
+                base_lines: Vec::new(),
+                ours_lines: Vec::new(),
+                theirs_lines: Vec::new(),
+            });
+        }
+        if i == lines_base.len() && o == lines_ours.len() && t == lines_theirs.len() {
+            break;
+        }
+
+        // the changed stretch runs up to the next base line both sides keep
+        let next = (i..lines_base.len()).find_map(|j| Some((j, kept_ours[j]?, kept_theirs[j]?)));
+        let (end, end_o, end_t) =
+            next.unwrap_or((lines_base.len(), lines_ours.len(), lines_theirs.len()));
+        let ours_changed = keys_ours[o..end_o] != keys_base[i..end];
+        let theirs_changed = keys_theirs[t..end_t] != keys_base[i..end];
+        let status = match (ours_changed, theirs_changed) {
+            (false, false) => MergeStatus::Unchanged,
+            (true, false) => MergeStatus::Ours,
+            (false, true) => MergeStatus::Theirs,
+            (true, true) if keys_ours[o..end_o] == keys_theirs[t..end_t] => MergeStatus::Both,
+            (true, true) => MergeStatus::Conflict,
+        };
+        let owned = |lines: &[&str]| -> Vec<String> {
+            if status == MergeStatus::Unchanged {
+                Vec::new()
+            } else {
+                lines.iter().map(|line| line.to_string()).collect()
+            }
+        };
+        chunks.push(MergeChunk {
+            status,
+            base_start: i + 1,
+            base_count: end - i,
+            ours_start: o + 1,
+            ours_count: end_o - o,
+            theirs_start: t + 1,
+            theirs_count: end_t - t,
+            base_lines: owned(&lines_base[i..end]),
+            ours_lines: owned(&lines_ours[o..end_o]),
+            theirs_lines: owned(&lines_theirs[t..end_t]),
+        });
+        (i, o, t) = (end, end_o, end_t);
+    }
+    chunks
+}
+
+/// For every line of `base`, the index of the line of `other` it is kept as.
+fn kept_lines(base: &[&str], other: &[&str], algorithm: Algorithm) -> Vec<Option<usize>> {
+    let mut kept = vec![None; base.len()];
+    for edit in line_edits(algorithm, base, other) {
+        if edit.operation == EditOp::Keep {
+            kept[edit.old_index] = Some(edit.new_index);
+        }
+    }
+    kept
+}
@@ -0,0 +1,123 @@
+use sha2::{Digest, Sha256};
+
+use crate::{hash_content, DiffLine, DiffOperation, MerkleStep};
+
+// domain separation so a leaf can never be mistaken for an inner node
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Leaf hash of one line: `SHA256(0x00 || line)`, where `line` is the line's text as
+/// `str::lines` yields it, without its terminator.
+pub fn merkle_leaf(line: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(line.as_bytes());
+    hasher.finalize().into()
+}
+
+fn merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Every level of the tree over `leaves`, leaves first and the root last. Each level
+/// pairs up adjacent nodes as `SHA256(0x01 || left || right)`; an odd last node is
+/// carried up unchanged rather than paired with itself.
+fn merkle_levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => merkle_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Root of the tree over `lines`. A file without lines has `SHA256("")` as root.
+pub fn merkle_root(lines: &[&str]) -> [u8; 32] {
+    merkle_root_of_leaves(&leaves_of(lines))
+}
+
+/// Root of the tree over precomputed leaf hashes, as `merkle_leaf` gives them.
+pub fn merkle_root_of_leaves(leaves: &[[u8; 32]]) -> [u8; 32] {
+    root_of(&merkle_levels(leaves.to_vec()))
+}
+
+fn leaves_of(lines: &[&str]) -> Vec<[u8; 32]> {
+    lines.iter().map(|line| merkle_leaf(line)).collect()
+}
+
+fn root_of(levels: &[Vec<[u8; 32]>]) -> [u8; 32] {
+    match levels.last().and_then(|level| level.first()) {
+        Some(root) => *root,
+        None => hash_content(""),
+    }
+}
+
+/// Siblings from the leaf at `index` up to the root. Levels where the node is
+/// carried up unpaired contribute no step.
+fn merkle_path(levels: &[Vec<[u8; 32]>], mut index: usize) -> Vec<MerkleStep> {
+    let mut path = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            path.push(MerkleStep {
+                sibling: level[sibling],
+                sibling_on_left: sibling < index,
+            });
+        }
+        index /= 2;
+    }
+    path
+}
+
+/// Root reached by folding `path` into the leaf of `line`. The line belongs to the
+/// file when this equals the committed root.
+pub fn merkle_path_root(line: &str, path: &[MerkleStep]) -> [u8; 32] {
+    path.iter().fold(merkle_leaf(line), |hash, step| {
+        if step.sibling_on_left {
+            merkle_node(&step.sibling, &hash)
+        } else {
+            merkle_node(&hash, &step.sibling)
+        }
+    })
+}
+
+/// Attaches to every diff line the inclusion path of each side it shows and returns
+/// the roots over `lines_a` and `lines_b`.
+pub fn attach_merkle_paths(
+    diff_lines: &mut [DiffLine],
+    lines_a: &[&str],
+    lines_b: &[&str],
+) -> ([u8; 32], [u8; 32]) {
+    attach_leaf_paths(diff_lines, leaves_of(lines_a), leaves_of(lines_b))
+}
+
+/// `attach_merkle_paths` over precomputed leaf hashes.
+pub fn attach_leaf_paths(
+    diff_lines: &mut [DiffLine],
+    leaves_a: Vec<[u8; 32]>,
+    leaves_b: Vec<[u8; 32]>,
+) -> ([u8; 32], [u8; 32]) {
+    let levels_a = merkle_levels(leaves_a);
+    let levels_b = merkle_levels(leaves_b);
+    for line in diff_lines.iter_mut() {
+        if line.operation == DiffOperation::NoNewline {
+            continue;
+        }
+        line.merkle_path_a = line.line_number_a.map(|a| merkle_path(&levels_a, a - 1));
+        line.merkle_path_b = line.line_number_b.map(|b| merkle_path(&levels_b, b - 1));
+    }
+    (root_of(&levels_a), root_of(&levels_b))
+}
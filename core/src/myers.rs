@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub operation: EditOp,
+    pub old_index: usize,
+    pub new_index: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum EditOp {
+    Insert,
+    Delete,
+    Keep,
+}
+
+/// Shortest edit script from `a` to `b`, using the linear-space refinement of
+/// Myers' algorithm: the middle snake of each subproblem is found with a forward
+/// and a backward search, and the halves on either side are solved recursively. Only
+/// O(N+M) memory is needed; within each changed run deletions come before insertions.
+pub fn myers_diff(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let mut matches = Vec::new();
+    diff_range(a, b, 0, 0, &mut matches);
+    edits_from_matches(&matches, a.len(), b.len())
+}
+
+/// Collects the (old, new) index of every kept line of `a` against `b`, where both
+/// slices start at `x0` and `y0` in the full inputs.
+fn diff_range(a: &[&str], b: &[&str], x0: usize, y0: usize, matches: &mut Vec<(usize, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    matches.extend((0..prefix).map(|i| (x0 + i, y0 + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (x0, y0) = (x0 + prefix, y0 + prefix);
+    
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    
+    // with the common ends stripped, an empty side means the rest is pure inserts
+    // or deletes and there is nothing left to match
+    if !a.is_empty() && !b.is_empty() {
+        if let Some((x, y)) = middle_split(a, b) {
+            diff_range(&a[..x], &b[..y], x0, y0, matches);
+            diff_range(&a[x..], &b[y..], x0 + x, y0 + y, matches);
+        }
+    }
+    
+    matches.extend((0..suffix).map(|i| (x0 + a.len() + i, y0 + b.len() + i)));
+}
+
+/// Runs the forward and backward searches until their furthest paths overlap and
+/// returns the point where the forward path reached the overlap. That point lies on
+/// a shortest edit path, so the two halves can be diffed independently.
+fn middle_split(a: &[&str], b: &[&str]) -> Option<(usize, usize)> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let len = (2 * max_d + 2) as usize;
+    
+    // furthest x on each diagonal; the backward search works on the reversed inputs
+    let mut v1 = vec![-1isize; len];
+    let mut v2 = vec![-1isize; len];
+    v1[(offset + 1) as usize] = 0;
+    v2[(offset + 1) as usize] = 0;
+    
+    let delta = n - m;
+    // with an odd delta the paths can only meet on a forward step, otherwise on a
+    // backward step
+    let front = delta % 2 != 0;
+    
+    // diagonals that ran off the grid are skipped in later rounds
+    let mut k1_start = 0;
+    let mut k1_end = 0;
+    let mut k2_start = 0;
+    let mut k2_end = 0;
+    
+    for d in 0..max_d {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let k1_off = (offset + k1) as usize;
+            let mut x1 = if k1 == -d || (k1 != d && v1[k1_off - 1] < v1[k1_off + 1]) {
+                v1[k1_off + 1]
+            } else {
+                v1[k1_off - 1] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && a[x1 as usize] == b[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            v1[k1_off] = x1;
+            
+            if x1 > n {
+                k1_end += 2;
+            } else if y1 > m {
+                k1_start += 2;
+            } else if front {
+                let k2_off = offset + delta - k1;
+                if k2_off >= 0 && (k2_off as usize) < len && v2[k2_off as usize] != -1 {
+                    let x2 = n - v2[k2_off as usize];
+                    if x1 >= x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+            k1 += 2;
+        }
+        
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let k2_off = (offset + k2) as usize;
+            let mut x2 = if k2 == -d || (k2 != d && v2[k2_off - 1] < v2[k2_off + 1]) {
+                v2[k2_off + 1]
+            } else {
+                v2[k2_off - 1] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && a[(n - x2 - 1) as usize] == b[(m - y2 - 1) as usize] {
+                x2 += 1;
+                y2 += 1;
+            }
+            v2[k2_off] = x2;
+            
+            if x2 > n {
+                k2_end += 2;
+            } else if y2 > m {
+                k2_start += 2;
+            } else if !front {
+                let k1_off = offset + delta - k2;
+                if k1_off >= 0 && (k1_off as usize) < len && v1[k1_off as usize] != -1 {
+                    let x1 = v1[k1_off as usize];
+                    let y1 = offset + x1 - k1_off;
+                    if x1 >= n - x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+    
+    None
+}
+
+/// Turns the kept line pairs into a full edit script.
+pub(crate) fn edits_from_matches(matches: &[(usize, usize)], n: usize, m: usize) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut x = 0;
+    let mut y = 0;
+    
+    for &(match_x, match_y) in matches.iter().chain([(n, m)].iter()) {
+        while x < match_x {
+            edits.push(Edit {
+                operation: EditOp::Delete,
+                old_index: x,
+                new_index: y,
+            });
+            x += 1;
+        }
+        while y < match_y {
+            edits.push(Edit {
+                operation: EditOp::Insert,
+                old_index: x,
+                new_index: y,
+            });
+            y += 1;
+        }
+        if x < n && y < m {
+            edits.push(Edit {
+                operation: EditOp::Keep,
+                old_index: x,
+                new_index: y,
+            });
+            x += 1;
+            y += 1;
+        }
+    }
+    
+    edits
+}
+
+/// The original Myers algorithm, which keeps the furthest-reaching paths of every
+/// round and backtracks through them. Needs O((N+M)^2) memory in the worst case;
+/// kept as a reference for `myers_diff`.
+pub fn myers_diff_trace(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let n = a.len();
+    let m = b.len();
+    let max_d = n + m;
+    if max_d == 0 {
+        return Vec::new();
+    }
+    
+    let mut v = vec![0i32; 2 * max_d + 1];
+    let offset = max_d as i32;
+    
+    let mut trace = Vec::new();
+    
+    for d in 0..=max_d {
+        trace.push(v.clone());
+        
+        let start_k = -(d as i32);
+        let end_k = d as i32;
+        
+        for k in (start_k..=end_k).step_by(2) {
+            let idx = (k + offset) as usize;
+            
+            let x = if k == start_k || (k != end_k && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            
+            let mut x_cur = x;
+            let mut y_cur = x - k;
+            
+            while (x_cur as usize) < n && (y_cur as usize) < m && 
+                  a[x_cur as usize] == b[y_cur as usize] {
+                x_cur += 1;
+                y_cur += 1;
+            }
+            
+            v[idx] = x_cur;
+            
+            if (x_cur as usize) >= n && (y_cur as usize) >= m {
+                return backtrack(&trace, a, b, d);
+            }
+        }
+    }
+    
+    Vec::new()
+}
+
+fn backtrack(trace: &[Vec<i32>], a: &[&str], b: &[&str], d: usize) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut x = a.len() as i32;
+    let mut y = b.len() as i32;
+    let max_d = a.len() + b.len();
+    let offset = max_d as i32;
+    
+    for trace_d in (0..=d).rev() {
+        let v = &trace[trace_d];
+        let k = x - y;
+        let idx = (k + offset) as usize;
+        
+        let prev_k = if k == -(trace_d as i32) || 
+                        (k != trace_d as i32 && v[idx - 1] < v[idx + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        
+        let prev_idx = (prev_k + offset) as usize;
+        let prev_x = if trace_d > 0 { v[prev_idx] } else { 0 };
+        let prev_y = prev_x - prev_k;
+        
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit {
+                operation: EditOp::Keep,
+                old_index: x as usize,
+                new_index: y as usize,
+            });
+        }
+        
+        if trace_d > 0 {
+            if x > prev_x {
+                x -= 1;
+                edits.push(Edit {
+                    operation: EditOp::Delete,
+                    old_index: x as usize,
+                    new_index: y as usize,
+                });
+            } else if y > prev_y {
+                y -= 1;
+                edits.push(Edit {
+                    operation: EditOp::Insert,
+                    old_index: x as usize,
+                    new_index: y as usize,
+                });
+            }
+        }
+    }
+    
+    edits.reverse();
+    edits
+}
+
+/// Number of inserted and deleted lines in the edit script.
+pub fn edit_distance(edits: &[Edit]) -> usize {
+    edits.iter().filter(|e| e.operation != EditOp::Keep).count()
+}
+
+/// Share of lines kept unchanged across both files, from 0.0 to 1.0. Two empty
+/// files are identical.
+pub fn similarity(edit_distance: usize, lines_a: usize, lines_b: usize) -> f32 {
+    let total = lines_a + lines_b;
+    if total == 0 {
+        return 1.0;
+    }
+    (total - edit_distance) as f32 / total as f32
+}
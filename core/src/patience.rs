@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use crate::myers::edits_from_matches;
+use crate::{myers_diff, Edit, EditOp};
+
+/// Edit script from `a` to `b` using patience diff: lines that occur exactly once
+/// in both inputs are matched up as anchors, keeping the longest run that appears in
+/// the same order on both sides, and the gaps between anchors are diffed
+/// recursively. A gap without unique common lines falls back to `myers_diff`. The
+/// script is valid but not always the shortest.
+pub fn patience_diff(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let mut matches = Vec::new();
+    patience_range(a, b, 0, 0, &mut matches);
+    edits_from_matches(&matches, a.len(), b.len())
+}
+
+/// Collects the (old, new) index of every kept line of `a` against `b`, where both
+/// slices start at `x0` and `y0` in the full inputs.
+fn patience_range(a: &[&str], b: &[&str], x0: usize, y0: usize, matches: &mut Vec<(usize, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    matches.extend((0..prefix).map(|i| (x0 + i, y0 + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (x0, y0) = (x0 + prefix, y0 + prefix);
+
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    if !a.is_empty() && !b.is_empty() {
+        let anchors = unique_anchors(a, b);
+        if anchors.is_empty() {
+            matches.extend(
+                myers_diff(a, b)
+                    .iter()
+                    .filter(|edit| edit.operation == EditOp::Keep)
+                    .map(|edit| (x0 + edit.old_index, y0 + edit.new_index)),
+            );
+        } else {
+            let (mut x, mut y) = (0, 0);
+            for (anchor_x, anchor_y) in anchors {
+                patience_range(&a[x..anchor_x], &b[y..anchor_y], x0 + x, y0 + y, matches);
+                matches.push((x0 + anchor_x, y0 + anchor_y));
+                x = anchor_x + 1;
+                y = anchor_y + 1;
+            }
+            patience_range(&a[x..], &b[y..], x0 + x, y0 + y, matches);
+        }
+    }
+
+    matches.extend((0..suffix).map(|i| (x0 + a.len() + i, y0 + b.len() + i)));
+}
+
+/// Lines unique to both `a` and `b`, as (old, new) index pairs forming the longest
+/// sequence that is increasing on both sides.
+fn unique_anchors(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    // occurrences in a and b, with the index of the last one on each side
+    let mut counts: BTreeMap<&str, (usize, usize, usize, usize)> = BTreeMap::new();
+    for (i, line) in a.iter().enumerate() {
+        let entry = counts.entry(*line).or_default();
+        entry.0 += 1;
+        entry.1 = i;
+    }
+    for (j, line) in b.iter().enumerate() {
+        if let Some(entry) = counts.get_mut(line) {
+            entry.2 += 1;
+            entry.3 = j;
+        }
+    }
+
+    let mut candidates: Vec<(usize, usize)> = counts
+        .values()
+        .filter(|&&(count_a, _, count_b, _)| count_a == 1 && count_b == 1)
+        .map(|&(_, x, _, y)| (x, y))
+        .collect();
+    candidates.sort_unstable();
+    longest_increasing(&candidates)
+}
+
+/// Longest subsequence of `pairs` (sorted by old index) whose new indices increase,
+/// found by patience sorting: each pair goes on the leftmost pile whose top has a
+/// larger new index, remembering the top of the pile to its left.
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut pile_tops: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = Vec::with_capacity(pairs.len());
+
+    for (i, &(_, y)) in pairs.iter().enumerate() {
+        let pile = pile_tops.partition_point(|&top| pairs[top].1 < y);
+        previous.push(pile.checked_sub(1).map(|left| pile_tops[left]));
+        if pile == pile_tops.len() {
+            pile_tops.push(i);
+        } else {
+            pile_tops[pile] = i;
+        }
+    }
+
+    let mut sequence = Vec::new();
+    let mut current = pile_tops.last().copied();
+    while let Some(i) = current {
+        sequence.push(pairs[i]);
+        current = previous[i];
+    }
+    sequence.reverse();
+    sequence
+}
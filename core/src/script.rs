@@ -0,0 +1,146 @@
+use crate::lines::empty_line;
+use crate::{
+    attach_leaf_paths, create_proof_hash, edit_distance, similarity, DiffInput, DiffLine,
+    DiffOperation, DiffOutput, Edit, EditOp, ScriptInput,
+};
+
+/// Checks a precomputed edit script against the line hashes and commits it. What
+/// is attested: the script turns file A into file B, every kept line has the same
+/// leaf hash on both sides, and the changes sit at the committed line numbers. The
+/// roots of both leaf trees stand in for the file hashes. Nothing about the content
+/// is known here, so lines carry only numbers and Merkle paths, and the script is
+/// not checked to be minimal.
+///
+/// # Panics
+///
+/// Panics if the script doesn't cover both files exactly, or keeps a line whose
+/// hashes differ.
+pub fn script_diff(input: &DiffInput, script: &ScriptInput) -> DiffOutput {
+    let edits = script_edits(script);
+    let edit_distance = edit_distance(&edits);
+    let similarity = similarity(edit_distance, script.leaves_a.len(), script.leaves_b.len());
+    let mut diff_lines = script_lines(&edits);
+    let (root_a, root_b) =
+        attach_leaf_paths(&mut diff_lines, script.leaves_a.clone(), script.leaves_b.clone());
+
+    let mut output = DiffOutput {
+        file_a_hash: root_a,
+        file_b_hash: root_b,
+        lines_a: script.leaves_a.len(),
+        lines_b: script.leaves_b.len(),
+        diff_lines,
+        line_dict: Vec::new(),
+        edit_distance,
+        similarity,
+        algorithm: input.options.algorithm,
+        hash: input.options.hash,
+        no_newline_a: false,
+        no_newline_b: false,
+        record_separator: None,
+        normalize_eol: false,
+        ignore_whitespace: false,
+        ignore_case: false,
+        ignore_blank_lines: false,
+        ignore_pattern: None,
+        anchor_pattern: None,
+        detect_moves: false,
+        include_unchanged: false,
+        expand_tabs: None,
+        max_line_len: None,
+        merge_gap: None,
+        prefix_lines: None,
+        length_unit: input.options.length_unit,
+        salted_redaction: false,
+        range_a: None,
+        range_b: None,
+        merkle_root_a: Some(root_a),
+        merkle_root_b: Some(root_b),
+        binary: false,
+        precomputed_script: true,
+        redaction_patterns: Vec::new(),
+        redaction_keyword_hashes: Vec::new(),
+        redact_keyword_values: false,
+        redact_all: false,
+        redact_paths: Vec::new(),
+        redact_line_numbers: false,
+        tokenizer: None,
+        suppressed: Vec::new(),
+        redaction_profile: None,
+        hunk_pattern: None,
+        hunk_headers: Vec::new(),
+        hunks: Vec::new(),
+        base_hash: None,
+        merge_chunks: Vec::new(),
+        structure_only: false,
+        files: Vec::new(),
+        stats: None,
+        proof_hash: [0; 32],
+    };
+    output.proof_hash = create_proof_hash(&output);
+    output
+}
+
+/// Replays the operations into indexed edits, checking each against the leaves.
+fn script_edits(script: &ScriptInput) -> Vec<Edit> {
+    let (a, b) = (&script.leaves_a, &script.leaves_b);
+    let (mut x, mut y) = (0, 0);
+    let mut edits = Vec::with_capacity(script.operations.len());
+    for operation in &script.operations {
+        edits.push(Edit {
+            operation: operation.clone(),
+            old_index: x,
+            new_index: y,
+        });
+        match operation {
+            EditOp::Keep => {
+                assert!(x < a.len() && y < b.len(), "Edit script runs past a file");
+                assert_eq!(
+                    a[x],
+                    b[y],
+                    "Edit script keeps lines {} and {} that differ",
+                    x + 1,
+                    y + 1
+                );
+                x += 1;
+                y += 1;
+            }
+            EditOp::Delete => {
+                assert!(x < a.len(), "Edit script runs past file A");
+                x += 1;
+            }
+            EditOp::Insert => {
+                assert!(y < b.len(), "Edit script runs past file B");
+                y += 1;
+            }
+        }
+    }
+    assert!(x == a.len() && y == b.len(), "Edit script doesn't cover both files");
+    edits
+}
+
+/// Diff lines without content, in the same canonical order and with the same
+/// replacement pairing as `create_diff_lines`.
+fn script_lines(edits: &[Edit]) -> Vec<DiffLine> {
+    let mut diff_lines = Vec::new();
+    for block in edits.split(|edit| edit.operation == EditOp::Keep) {
+        let deletes: Vec<&Edit> = block.iter().filter(|e| e.operation == EditOp::Delete).collect();
+        let inserts: Vec<&Edit> = block.iter().filter(|e| e.operation == EditOp::Insert).collect();
+        if deletes.len() == inserts.len() {
+            diff_lines.extend(deletes.iter().zip(&inserts).map(|(delete, insert)| DiffLine {
+                line_number_a: Some(delete.old_index + 1),
+                line_number_b: Some(insert.new_index + 1),
+                ..empty_line(DiffOperation::Replace)
+            }));
+            continue;
+        }
+        diff_lines.extend(deletes.iter().map(|delete| DiffLine {
+            line_number_a: Some(delete.old_index + 1),
+            ..empty_line(DiffOperation::Delete)
+        }));
+        diff_lines.extend(inserts.iter().map(|insert| DiffLine {
+            line_number_b: Some(insert.new_index + 1),
+            ..empty_line(DiffOperation::Insert)
+        }));
+    }
+    diff_lines
+}
@@ -0,0 +1,132 @@
+use crate::diff::line_edits;
+use crate::myers::edits_from_matches;
+use crate::{Algorithm, Edit, EditOp, Tokenizer};
+
+// ends every line's tokens; tokenize never yields it since it skips whitespace,
+// and it gives blank lines a token to be matched by
+const LINE_BREAK: &str = "\n";
+
+// longest first, so a longer operator wins over its prefix
+const RUST_OPERATORS: [&str; 24] = [
+    "<<=", ">>=", "...", "..=", "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "^=", "&=", "|=", "<<", ">>", "..",
+];
+
+/// Splits `line` into tokens by `tokenizer`, skipping whitespace. A run of letters,
+/// digits and underscores is one token and every other character is a token of
+/// its own. The Rust tokenizer also keeps string and char literals, lifetimes, line
+/// comments and multi-character operators whole; a literal left open runs to the
+/// end of the line.
+pub fn tokenize(line: &str, tokenizer: Tokenizer) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while let Some(first) = rest.chars().next() {
+        let rust_len = match tokenizer {
+            Tokenizer::Rust => rust_token_len(rest),
+            Tokenizer::Generic => None,
+        };
+        let len = rust_len.unwrap_or_else(|| generic_token_len(rest, first));
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+    tokens
+}
+
+/// Line edit script from `a` to `b`, found by diffing their token streams with
+/// `algorithm`. Every line ends in a line break token, so blank lines are matched
+/// too. A pair of lines is kept when all the tokens of both are kept as each
+/// other's and the lines compare equal; every other line is changed.
+pub fn token_line_edits(
+    algorithm: Algorithm,
+    tokenizer: Tokenizer,
+    a: &[&str],
+    b: &[&str],
+) -> Vec<Edit> {
+    let (tokens_a, line_of_a) = token_stream(a, tokenizer);
+    let (tokens_b, line_of_b) = token_stream(b, tokenizer);
+    let tokens_on = |lines: usize, line_of: &[usize]| {
+        let mut counts = vec![0; lines];
+        line_of.iter().for_each(|&line| counts[line] += 1);
+        counts
+    };
+    let (count_a, count_b) = (tokens_on(a.len(), &line_of_a), tokens_on(b.len(), &line_of_b));
+
+    // kept tokens are in order on both sides, so all those shared by one pair of
+    // lines are consecutive
+    let mut runs: Vec<(usize, usize, usize)> = Vec::new();
+    for edit in line_edits(algorithm, &tokens_a, &tokens_b) {
+        if edit.operation != EditOp::Keep {
+            continue;
+        }
+        let (x, y) = (line_of_a[edit.old_index], line_of_b[edit.new_index]);
+        match runs.last_mut() {
+            Some((run_x, run_y, kept)) if (*run_x, *run_y) == (x, y) => *kept += 1,
+            _ => runs.push((x, y, 1)),
+        }
+    }
+    let matches: Vec<(usize, usize)> = runs
+        .into_iter()
+        .filter(|&(x, y, kept)| kept == count_a[x] && kept == count_b[y] && a[x] == b[y])
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    edits_from_matches(&matches, a.len(), b.len())
+}
+
+/// Tokens of all `lines` in order, and the index of the line each one is on.
+fn token_stream<'a>(lines: &[&'a str], tokenizer: Tokenizer) -> (Vec<&'a str>, Vec<usize>) {
+    let mut tokens = Vec::new();
+    let mut line_of = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        for token in tokenize(line, tokenizer).into_iter().chain([LINE_BREAK]) {
+            tokens.push(token);
+            line_of.push(i);
+        }
+    }
+    (tokens, line_of)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn generic_token_len(text: &str, first: char) -> usize {
+    if is_word(first) {
+        text.find(|c| !is_word(c)).unwrap_or(text.len())
+    } else {
+        first.len_utf8()
+    }
+}
+
+/// Length of the Rust token `text` starts with, when it isn't a generic one.
+fn rust_token_len(text: &str) -> Option<usize> {
+    if text.starts_with("//") {
+        return Some(text.trim_end().len());
+    }
+    let mut chars = text.chars();
+    match chars.next()? {
+        '"' => Some(quoted_len(text, '"')),
+        '\'' => match (chars.next(), chars.next()) {
+            (Some('\\'), _) => Some(quoted_len(text, '\'')),
+            (Some(c), Some('\'')) => Some(2 + c.len_utf8()),
+            (Some(c), _) if is_word(c) => Some(1 + generic_token_len(&text[1..], c)),
+            _ => None,
+        },
+        _ => RUST_OPERATORS.iter().find(|op| text.starts_with(*op)).map(|op| op.len()),
+    }
+}
+
+/// Length of the literal opened by `quote` at the start of `text`, up to its
+/// unescaped closing quote.
+fn quoted_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    text.trim_end().len()
+}
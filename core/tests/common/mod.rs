@@ -0,0 +1,68 @@
+// each test binary uses its own subset of these helpers
+#![allow(dead_code)]
+
+use zkdiff_core::{Edit, EditOp};
+
+/// Replays an edit script over `a`, checking every index along the way, and
+/// returns the resulting lines.
+pub fn apply<'a>(edits: &[Edit], a: &[&'a str], b: &[&'a str]) -> Vec<&'a str> {
+    let mut out = Vec::new();
+    let mut x = 0;
+    let mut y = 0;
+    for edit in edits {
+        assert_eq!((edit.old_index, edit.new_index), (x, y), "edit out of order");
+        match edit.operation {
+            EditOp::Keep => {
+                assert_eq!(a[x], b[y], "kept lines differ");
+                out.push(a[x]);
+                x += 1;
+                y += 1;
+            }
+            EditOp::Delete => x += 1,
+            EditOp::Insert => {
+                out.push(b[y]);
+                y += 1;
+            }
+        }
+    }
+    assert_eq!((x, y), (a.len(), b.len()), "script does not cover both inputs");
+    out
+}
+
+pub fn lines(text: &str) -> Vec<&str> {
+    text.lines().collect()
+}
+
+/// Pseudo-random pairs of short inputs over small alphabets, which give plenty of
+/// repeated lines and competing alignments. The same `count` always yields the same
+/// pairs.
+pub fn generated_inputs(count: usize) -> Vec<(Vec<&'static str>, Vec<&'static str>)> {
+    generated_inputs_up_to(count, 20, 4)
+}
+
+/// Like `generated_inputs`, with inputs of up to `max_len - 1` lines over up to
+/// `max_symbols` distinct lines.
+pub fn generated_inputs_up_to(
+    count: usize,
+    max_len: usize,
+    max_symbols: usize,
+) -> Vec<(Vec<&'static str>, Vec<&'static str>)> {
+    let alphabet = ["a", "b", "c", "d", "e", "f", "g", "h"];
+    let max_symbols = max_symbols.min(alphabet.len());
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+
+    (0..count)
+        .map(|_| {
+            let len_a = next() % max_len;
+            let len_b = next() % max_len;
+            let symbols = 1 + next() % max_symbols;
+            let a: Vec<&str> = (0..len_a).map(|_| alphabet[next() % symbols]).collect();
+            let b: Vec<&str> = (0..len_b).map(|_| alphabet[next() % symbols]).collect();
+            (a, b)
+        })
+        .collect()
+}
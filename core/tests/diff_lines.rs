@@ -0,0 +1,784 @@
+mod common;
+
+use common::generated_inputs;
+use zkdiff_core::{
+    compute_diff, create_proof_hash, group_hunks, hash_content, hash_content_with, merkle_leaf,
+    merkle_root, myers_diff, path_matches, records, replayed_file_b, restore_line_contents,
+    tab_expanded, tree_hash, Algorithm, DiffHunk, DiffInput, DiffLine, DiffOperation, DiffOptions,
+    DiffOutput, EditOp, FileInput, FileStatus, Granularity, HashAlgorithm, IgnoredLines,
+    RedactionRange, ScriptInput, SyncAnchors,
+};
+
+fn input(a: &str, b: &str, options: DiffOptions) -> DiffInput {
+    DiffInput {
+        file_a_hash: hash_content(a),
+        file_b_hash: hash_content(b),
+        file_a_content: a.to_string(),
+        file_b_content: b.to_string(),
+        file_a_bytes: Vec::new(),
+        file_b_bytes: Vec::new(),
+        redaction_ranges: Vec::new(),
+        redaction_profile: None,
+        pattern_redactions: Vec::new(),
+        hunk_anchors: None,
+        ignored_lines: None,
+        sync_anchors: None,
+        options,
+        files: Vec::new(),
+        script: None,
+        merge_base: None,
+    }
+}
+
+fn output(a: &str, b: &str, options: DiffOptions) -> DiffOutput {
+    compute_diff(&input(a, b, options))
+}
+
+fn diff(a: &str, b: &str, options: DiffOptions) -> Vec<DiffLine> {
+    output(a, b, options).diff_lines
+}
+
+/// Diff lines in a compact form: `-3` deletes line 3 of A, `+4` inserts line 4 of
+/// B, `3~4` replaces one with the other and `3=4` is context.
+fn sequence(a: &str, b: &str, options: DiffOptions) -> Vec<String> {
+    diff(a, b, options)
+        .iter()
+        .map(|line| {
+            let (a, b) = (line.line_number_a.unwrap_or(0), line.line_number_b.unwrap_or(0));
+            match line.operation {
+                DiffOperation::Delete => format!("-{}", a),
+                DiffOperation::Insert => format!("+{}", b),
+                DiffOperation::Replace => format!("{}~{}", a, b),
+                DiffOperation::Context => format!("{}={}", a, b),
+                _ => format!("{:?}", line.operation),
+            }
+        })
+        .collect()
+}
+
+fn with_algorithm(algorithm: Algorithm) -> DiffOptions {
+    DiffOptions { algorithm, ..Default::default() }
+}
+
+const ALGORITHMS: [Algorithm; 3] = [Algorithm::Myers, Algorithm::Patience, Algorithm::Histogram];
+
+#[test]
+fn deletes_come_before_inserts_in_a_change() {
+    let a = "keep\nold 1\nold 2\nold 3\nkeep too\n";
+    let b = "keep\nnew 1\nnew 2\nkeep too\n";
+    for algorithm in ALGORITHMS {
+        assert_eq!(
+            sequence(a, b, with_algorithm(algorithm)),
+            ["-2", "-3", "-4", "+2", "+3"],
+            "{:?}",
+            algorithm
+        );
+    }
+}
+
+#[test]
+fn equal_runs_are_paired_as_replacements() {
+    let a = "a\nb\nc\nd\n";
+    let b = "a\nB\nC\nd\n";
+    for algorithm in ALGORITHMS {
+        assert_eq!(sequence(a, b, with_algorithm(algorithm)), ["2~2", "3~3"], "{:?}", algorithm);
+    }
+}
+
+#[test]
+fn changes_appear_in_file_order() {
+    let a = "1\n2\n3\n4\n5\n6\n7\n8\n";
+    let b = "0\n1\n2\n3x\n4\n6\n7\n8\n9\n";
+    for algorithm in ALGORITHMS {
+        assert_eq!(
+            sequence(a, b, with_algorithm(algorithm)),
+            ["+1", "3~4", "-5", "+9"],
+            "{:?}",
+            algorithm
+        );
+    }
+}
+
+#[test]
+fn context_surrounds_each_change() {
+    let a = "1\n2\n3\n4\n5\n6\n7\n";
+    let b = "1\n2\nthree\n4\n5\n6\n7\nnew\n";
+    let options = DiffOptions { context: 1, ..Default::default() };
+    assert_eq!(sequence(a, b, options), ["2=2", "3~3", "4=4", "7=7", "+8"]);
+}
+
+#[test]
+fn interleaved_scripts_are_emitted_in_canonical_order() {
+    // competing alignments over a tiny alphabet make the scripts interleave
+    // deletions and insertions; with one line of context every change is delimited
+    // by context lines
+    for (a, b) in generated_inputs(300) {
+        let a: String = a.iter().map(|line| format!("{}\n", line)).collect();
+        let b: String = b.iter().map(|line| format!("{}\n", line)).collect();
+        for algorithm in ALGORITHMS {
+            let options = DiffOptions { algorithm, context: 1, ..Default::default() };
+            let lines = diff(&a, &b, options);
+            let changes = lines.split(|line| line.operation == DiffOperation::Context);
+            for change in changes.filter(|change| !change.is_empty()) {
+                let deletes = change.iter().take_while(|l| l.operation == DiffOperation::Delete);
+                let inserts = change.iter().skip(deletes.clone().count());
+                let numbers_a: Vec<usize> = deletes.filter_map(|l| l.line_number_a).collect();
+                let numbers_b: Vec<usize> = inserts
+                    .map(|l| {
+                        assert!(
+                            matches!(l.operation, DiffOperation::Insert | DiffOperation::Replace),
+                            "deletion after an insertion for {:?} -> {:?} with {:?}",
+                            a,
+                            b,
+                            algorithm
+                        );
+                        l.line_number_b.unwrap()
+                    })
+                    .collect();
+                assert!(numbers_a.windows(2).all(|w| w[0] + 1 == w[1]), "{:?}", numbers_a);
+                assert!(numbers_b.windows(2).all(|w| w[0] + 1 == w[1]), "{:?}", numbers_b);
+            }
+        }
+    }
+}
+
+#[test]
+fn an_empty_file_diffs_as_all_inserts_or_all_deletes() {
+    for algorithm in ALGORITHMS {
+        assert_eq!(sequence("", "a\nb\nc\n", with_algorithm(algorithm)), ["+1", "+2", "+3"]);
+        assert_eq!(sequence("a\nb\nc\n", "", with_algorithm(algorithm)), ["-1", "-2", "-3"]);
+    }
+    let inserted = output("", "a\nb\n", DiffOptions::default());
+    assert_eq!((inserted.edit_distance, inserted.similarity), (2, 0.0));
+    let contents: Vec<_> = inserted.diff_lines.iter().map(|l| l.content.as_deref()).collect();
+    assert_eq!(contents, [Some("a"), Some("b")]);
+}
+
+#[test]
+fn two_empty_files_give_a_well_formed_empty_diff() {
+    for algorithm in ALGORITHMS {
+        let output = output("", "", DiffOptions { context: 3, ..with_algorithm(algorithm) });
+        assert!(output.diff_lines.is_empty());
+        assert_eq!((output.edit_distance, output.similarity), (0, 1.0));
+        assert_eq!(output.file_a_hash, hash_content(""));
+        assert!(!output.no_newline_a && !output.no_newline_b);
+        assert_eq!(output.proof_hash, create_proof_hash(&output));
+    }
+}
+
+#[test]
+fn tabs_expand_to_the_next_stop() {
+    assert_eq!(tab_expanded("a\tbc\td\n\te\t", 4), "a   bc  d\n    e   ");
+}
+
+#[test]
+fn expanded_tabs_are_compared_and_shown_as_spaces() {
+    let options = DiffOptions { expand_tabs: Some(8), ..Default::default() };
+    let mut input = input("\tx = 1;\nab\tc\n", "        x = 1;\nab      c!\n", options);
+    input.expand_tabs();
+    let output = compute_diff(&input);
+    assert_eq!(output.expand_tabs, Some(8));
+    assert_eq!(output.diff_lines.len(), 1);
+    let line = &output.diff_lines[0];
+    assert_eq!(line.operation, DiffOperation::Replace);
+    assert_eq!(line.content.as_deref(), Some("ab      c"));
+    assert_eq!(line.new_content.as_deref(), Some("ab      c!"));
+}
+
+fn script_input(a: &str, b: &str, operations: Vec<EditOp>) -> DiffInput {
+    DiffInput {
+        file_a_hash: [0; 32],
+        file_b_hash: [0; 32],
+        file_a_content: String::new(),
+        file_b_content: String::new(),
+        file_a_bytes: Vec::new(),
+        file_b_bytes: Vec::new(),
+        redaction_ranges: Vec::new(),
+        redaction_profile: None,
+        pattern_redactions: Vec::new(),
+        hunk_anchors: None,
+        ignored_lines: None,
+        sync_anchors: None,
+        options: DiffOptions::default(),
+        files: Vec::new(),
+        script: Some(ScriptInput {
+            leaves_a: a.lines().map(merkle_leaf).collect(),
+            leaves_b: b.lines().map(merkle_leaf).collect(),
+            operations,
+        }),
+        merge_base: None,
+    }
+}
+
+#[test]
+fn precomputed_script_matches_the_computed_diff() {
+    let a = "keep\nold 1\nold 2\nold 3\nkeep too\nsame\nchanged\n";
+    let b = "keep\nnew 1\nnew 2\nkeep too\nsame\nchanged!\nadded\n";
+    let (lines_a, lines_b): (Vec<&str>, Vec<&str>) = (a.lines().collect(), b.lines().collect());
+    let operations = myers_diff(&lines_a, &lines_b).into_iter().map(|e| e.operation).collect();
+
+    let output = compute_diff(&script_input(a, b, operations));
+    assert!(output.precomputed_script);
+    assert_eq!(output.file_a_hash, merkle_root(&lines_a));
+    assert_eq!(output.file_b_hash, merkle_root(&lines_b));
+    let numbers = |lines: &[DiffLine]| -> Vec<(Option<usize>, Option<usize>)> {
+        lines.iter().map(|l| (l.line_number_a, l.line_number_b)).collect()
+    };
+    let computed = diff(a, b, DiffOptions::default());
+    assert_eq!(numbers(&output.diff_lines), numbers(&computed));
+    assert!(output.diff_lines.iter().all(|line| line.content.is_none()));
+}
+
+#[test]
+#[should_panic(expected = "keeps lines")]
+fn precomputed_script_cannot_keep_a_changed_line() {
+    let operations = vec![EditOp::Keep, EditOp::Keep];
+    compute_diff(&script_input("a\nb\n", "a\nc\n", operations));
+}
+
+#[test]
+#[should_panic(expected = "doesn't cover")]
+fn precomputed_script_must_cover_both_files() {
+    compute_diff(&script_input("a\nb\n", "a\n", vec![EditOp::Keep]));
+}
+
+#[test]
+fn append_only_lists_just_the_appended_lines() {
+    let options = || DiffOptions { append_only: true, ..Default::default() };
+    let (a, b) = ("one\ntwo\n", "one\ntwo\nthree\nfour\n");
+    assert_eq!(sequence(a, b, options()), ["+3", "+4"]);
+    let output = output(a, b, options());
+    assert_eq!(output.prefix_lines, Some(2));
+    assert_eq!(output.edit_distance, 2);
+    assert_eq!(output.proof_hash, create_proof_hash(&output));
+    assert!(output.proof_hash != compute_diff(&input(a, b, DiffOptions::default())).proof_hash);
+    assert_eq!(sequence(a, a, options()), Vec::<String>::new());
+}
+
+#[test]
+#[should_panic(expected = "doesn't just append")]
+fn append_only_refuses_a_changed_prefix() {
+    let options = DiffOptions { append_only: true, ..Default::default() };
+    compute_diff(&input("one\ntwo\n", "one\n2\nthree\n", options));
+}
+
+#[test]
+#[should_panic(expected = "doesn't just append")]
+fn append_only_refuses_extending_a_last_line_without_newline() {
+    let options = DiffOptions { append_only: true, ..Default::default() };
+    compute_diff(&input("one\ntwo", "one\ntwo\nthree\n", options));
+}
+
+#[test]
+fn the_chosen_hash_function_is_recorded_and_used_for_the_proof_hash() {
+    let (a, b) = ("one\ntwo\n", "one\n2\n");
+    let options = DiffOptions { hash: HashAlgorithm::Blake3, ..Default::default() };
+    let mut blake3_input = input(a, b, options);
+    blake3_input.file_a_hash = hash_content_with(HashAlgorithm::Blake3, a);
+    blake3_input.file_b_hash = hash_content_with(HashAlgorithm::Blake3, b);
+    let blake3 = compute_diff(&blake3_input);
+    let sha256 = output(a, b, DiffOptions::default());
+
+    assert_eq!(hash_content_with(HashAlgorithm::Sha256, a), hash_content(a));
+    assert_eq!(sha256.hash, HashAlgorithm::Sha256);
+    assert_eq!(blake3.hash, HashAlgorithm::Blake3);
+    assert!(blake3.file_a_hash != sha256.file_a_hash);
+    assert_eq!(blake3.proof_hash, create_proof_hash(&blake3));
+    assert!(blake3.proof_hash != sha256.proof_hash);
+    assert_eq!(sequence(a, b, blake3_input.options.clone()), ["2~2"]);
+}
+
+#[test]
+fn keywords_are_hidden_where_the_guest_finds_them() {
+    let keywords = |values| DiffOptions {
+        redact_keywords: vec!["password".to_string(), "TOKEN".to_string()],
+        redact_keyword_values: values,
+        ..Default::default()
+    };
+    let (a, b) = ("host=db\n", "host=db\nPassword = hunter2 user=bob\nmytoken=abc\n");
+
+    let lines = diff(a, b, keywords(false));
+    assert_eq!(lines[0].content.as_deref(), Some(" = hunter2 user=bob"));
+    let spans = lines[0].redacted_spans.as_ref().unwrap();
+    assert_eq!((spans[0].start, spans[0].end), (0, 8));
+    assert_eq!(lines[1].content.as_deref(), Some("my=abc"));
+
+    let output = output(a, b, keywords(true));
+    assert_eq!(output.diff_lines[0].content.as_deref(), Some(" user=bob"));
+    assert_eq!(output.diff_lines[1].content.as_deref(), Some("my"));
+    let mut hashes = vec![hash_content("password"), hash_content("token")];
+    hashes.sort();
+    assert_eq!(output.redaction_keyword_hashes, hashes);
+    assert!(output.redact_keyword_values);
+    assert_eq!(output.proof_hash, create_proof_hash(&output));
+}
+
+#[test]
+fn redacted_lines_can_be_committed_without_line_numbers() {
+    let (a, b) = ("one\ntwo\nthree\n", "one\nTWO\nthree\nfour");
+    let options = DiffOptions { context: 1, redact_line_numbers: true, ..Default::default() };
+    let mut input = input(a, b, options);
+    input.redaction_ranges.push(RedactionRange {
+        start_line: 2,
+        end_line: 2,
+        start_col: None,
+        end_col: None,
+        operation: DiffOperation::Replace,
+    });
+    let mut output = compute_diff(&input);
+
+    let hidden = &output.diff_lines[1];
+    assert_eq!(hidden.operation, DiffOperation::Replace);
+    assert_eq!((hidden.line_number_a, hidden.line_number_b), (None, None));
+    assert_eq!(output.diff_lines[3].line_number_b, Some(4));
+    let hunks = group_hunks(&output.diff_lines);
+    assert_eq!(hunks.len(), 1);
+    assert_eq!((hunks[0].old_count, hunks[0].new_count, hunks[0].lines.len()), (3, 4, 5));
+
+    assert_eq!(output.proof_hash, create_proof_hash(&output));
+    output.redact_line_numbers = false;
+    assert!(output.proof_hash != create_proof_hash(&output));
+}
+
+fn password_redaction(line: usize, expected: &str) -> DiffInput {
+    let (a, b) = ("user=bob\npass=hunter2\n", "user=bob\npass=swordfish\n");
+    let expected_redaction_hash = Some(hash_content(expected));
+    let mut input = input(a, b, DiffOptions { expected_redaction_hash, ..Default::default() });
+    input.redaction_ranges.push(RedactionRange {
+        start_line: line,
+        end_line: line,
+        start_col: Some(6),
+        end_col: None,
+        operation: DiffOperation::Replace,
+    });
+    input
+}
+
+#[test]
+fn redacting_the_expected_text_passes() {
+    let output = compute_diff(&password_redaction(2, "hunter2\nswordfish\n"));
+    assert_eq!(output.diff_lines[0].content.as_deref(), Some("pass="));
+}
+
+#[test]
+#[should_panic(expected = "expected hash")]
+fn redacting_other_lines_fails() {
+    compute_diff(&password_redaction(1, "hunter2\nswordfish\n"));
+}
+
+#[test]
+fn suppressed_operations_are_left_out_and_committed() {
+    let (a, b) = ("one\ntwo\n", "one\nTWO\nthree");
+    assert_eq!(sequence(a, b, DiffOptions::default()), ["-2", "+2", "+3", "NoNewline"]);
+    let suppress = DiffOptions { suppress: vec![DiffOperation::Insert], ..Default::default() };
+    assert_eq!(sequence(a, b, suppress.clone()), ["-2"]);
+
+    let mut output = output(a, b, suppress);
+    assert_eq!(output.suppressed, [DiffOperation::Insert]);
+    assert_eq!(output.edit_distance, 3);
+    assert_eq!(output.proof_hash, create_proof_hash(&output));
+    output.suppressed.clear();
+    assert!(output.proof_hash != create_proof_hash(&output));
+}
+
+#[test]
+fn committed_hunks_group_the_diff_lines() {
+    let a = "1\n2\n3\n4\n5\n6\n7\n8\n";
+    let b = "1\ntwo\n3\n4\n5\n6\n7\n8\nnine\n";
+    let hunks = DiffOptions { context: 1, hunks: true, ..Default::default() };
+    assert!(output(a, b, DiffOptions::default()).hunks.is_empty());
+    let mut output = output(a, b, hunks);
+    let spans: Vec<_> =
+        output.hunks.iter().map(|h| (h.old_start, h.old_count, h.new_start, h.new_count)).collect();
+    assert_eq!(spans, [(1, 3, 1, 3), (8, 1, 8, 2)]);
+    let committed: Vec<usize> = output.hunks.iter().map(|hunk| hunk.lines.len()).collect();
+    let grouped: Vec<usize> =
+        group_hunks(&output.diff_lines).iter().map(|hunk| hunk.lines.len()).collect();
+    assert_eq!((committed, grouped), (vec![3, 2], vec![3, 2]));
+
+    assert_eq!(output.proof_hash, create_proof_hash(&output));
+    output.hunks[1].new_count = 1;
+    assert!(output.proof_hash != create_proof_hash(&output));
+}
+
+#[test]
+fn hunks_a_short_gap_apart_are_merged() {
+    let a = "1\n2\n3\n4\n5\n6\n7\n8\n";
+    let b = "1\ntwo\n3\n4\n5\n6\n7\n8\nnine\n";
+    let spans = |merge_gap| {
+        let options = DiffOptions { context: 1, merge_gap, hunks: true, ..Default::default() };
+        let output = output(a, b, options);
+        assert_eq!(output.merge_gap, merge_gap);
+        let span = |h: &DiffHunk| (h.old_start, h.old_count, h.new_start, h.new_count);
+        (output.hunks.iter().map(span).collect::<Vec<_>>(), output.proof_hash)
+    };
+    // lines 4-7 are left out between the two changes' context
+    let (apart, apart_hash) = spans(Some(3));
+    assert_eq!(apart, [(1, 3, 1, 3), (8, 1, 8, 2)]);
+    let (merged, merged_hash) = spans(Some(4));
+    assert_eq!(merged, [(1, 8, 1, 9)]);
+    assert_ne!(apart_hash, spans(None).1);
+    assert_ne!(apart_hash, merged_hash);
+}
+
+#[test]
+fn blank_line_changes_are_ignored() {
+    let ignore = DiffOptions { ignore_blank_lines: true, context: 1, ..Default::default() };
+    let a = "fn a() {\n    x();\n}\nfn b() {}\n";
+    let b = "fn a() {\n\n    x();\n}\n  \nfn b() {}\n";
+    assert_eq!(sequence(a, b, DiffOptions::default()), ["+2", "+5"]);
+    assert!(sequence(a, b, ignore.clone()).is_empty());
+
+    // line numbers still count the blank lines skipped around a change
+    let (a, b) = ("x\n\ny\nz\n", "x\ny\n\nZ\n");
+    assert_eq!(sequence(a, b, ignore.clone()), ["3=2", "4~4"]);
+    let mut output = output(a, b, ignore);
+    assert!(output.ignore_blank_lines);
+    assert_eq!(output.edit_distance, 2);
+    assert_eq!(output.proof_hash, create_proof_hash(&output));
+    output.ignore_blank_lines = false;
+    assert!(output.proof_hash != create_proof_hash(&output));
+}
+
+#[test]
+fn ignoring_blank_lines_diffs_the_other_lines_alone() {
+    let ignore = DiffOptions { ignore_blank_lines: true, context: 2, ..Default::default() };
+    // "a" lines become blank ones, whitespace-only in file A and empty in file B
+    let text = |lines: &[&str], blank: &str| -> String {
+        lines.iter().map(|&l| format!("{}\n", if l == "a" { blank } else { l })).collect()
+    };
+    let without_blanks = |text: &str| -> String {
+        text.split_inclusive('\n').filter(|l| !l.trim().is_empty()).collect()
+    };
+    for (a, b) in generated_inputs(300) {
+        let (text_a, text_b) = (text(&a, " "), text(&b, ""));
+        let output = output(&text_a, &text_b, ignore.clone());
+        let alone = compute_diff(&input(
+            &without_blanks(&text_a),
+            &without_blanks(&text_b),
+            ignore.clone(),
+        ));
+        assert_eq!(output.edit_distance, alone.edit_distance, "{:?} -> {:?}", a, b);
+
+        let (lines_a, lines_b): (Vec<&str>, Vec<&str>) =
+            (text_a.lines().collect(), text_b.lines().collect());
+        for line in &output.diff_lines {
+            let text = match (line.line_number_a, line.line_number_b) {
+                (_, Some(b)) if line.operation == DiffOperation::Insert => lines_b[b - 1],
+                (Some(a), _) => lines_a[a - 1],
+                _ => unreachable!(),
+            };
+            assert_eq!(line.content.as_deref(), Some(text));
+            let one_sided = matches!(line.operation, DiffOperation::Insert | DiffOperation::Delete);
+            assert!(!(one_sided && text.trim().is_empty()), "{:?} -> {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn line_counts_are_committed() {
+    let (a, b) = ("one\ntwo\nthree\nfour\nfive\n", "one\nTWO\nthree\nfour\nfive\nsix");
+    let stats_only = DiffOptions { stats_only: true, ..Default::default() };
+    for options in [DiffOptions::default(), stats_only] {
+        let mut output = output(a, b, options);
+        assert_eq!((output.lines_a, output.lines_b), (5, 6));
+        assert_eq!(output.proof_hash, create_proof_hash(&output));
+        output.lines_a = 5000;
+        assert!(output.proof_hash != create_proof_hash(&output));
+    }
+}
+
+#[test]
+fn redact_all_hides_every_line() {
+    let (a, b) = ("keep\nold line\nsame\n", "keep\nnew line\nsame\nadded");
+    for granularity in [Granularity::Line, Granularity::Word] {
+        let redact_all =
+            DiffOptions { redact_all: true, context: 1, granularity, ..Default::default() };
+        let mut output = output(a, b, redact_all);
+        let shown = output.diff_lines.iter().filter(|l| l.operation != DiffOperation::NoNewline);
+        assert_eq!(shown.clone().count(), 4);
+        for line in shown {
+            assert!(line.content.is_none() && line.new_content.is_none(), "{:?}", line);
+            assert!(line.word_edits.is_none() && line.char_edits.is_none(), "{:?}", line);
+            assert!(line.line_number_a.is_some() || line.line_number_b.is_some());
+        }
+        assert!(output.redact_all);
+        assert_eq!(output.proof_hash, create_proof_hash(&output));
+        output.redact_all = false;
+        assert!(output.proof_hash != create_proof_hash(&output));
+    }
+
+    let hidden = DiffOptions { redact_all: true, redact_line_numbers: true, ..Default::default() };
+    let lines = diff(a, b, hidden);
+    assert!(lines.iter().all(|l| l.line_number_a.is_none() && l.line_number_b.is_none()));
+}
+
+#[test]
+#[should_panic(expected = "char granularity")]
+fn redact_all_refuses_char_breakdowns() {
+    let options =
+        DiffOptions { redact_all: true, granularity: Granularity::Char, ..Default::default() };
+    output("old line\n", "new line\n", options);
+}
+
+#[test]
+fn long_lines_are_truncated_but_compared_in_full() {
+    let long = "é".repeat(10);
+    let (a, b) = (format!("short\n{}x\n", long), format!("added\nshort\n{}y\n", long));
+    let capped = DiffOptions { max_line_len: Some(5), ..Default::default() };
+    let mut output = output(&a, &b, capped);
+    let added = &output.diff_lines[0];
+    assert_eq!((added.content.as_deref(), added.truncated), (Some("added"), false));
+    let replaced = &output.diff_lines[1];
+    assert_eq!(replaced.operation, DiffOperation::Replace);
+    assert!(replaced.truncated);
+    assert_eq!(replaced.content.as_deref(), Some("éé"));
+    assert_eq!((replaced.full_length, replaced.new_full_length), (Some(21), Some(21)));
+
+    // the lines differ only past the cut, and are still reported as changed
+    assert_eq!(output.edit_distance, 3);
+    assert_eq!(output.max_line_len, Some(5));
+    assert_eq!(output.proof_hash, create_proof_hash(&output));
+    output.diff_lines[1].full_length = Some(20);
+    assert!(output.proof_hash != create_proof_hash(&output));
+}
+
+#[test]
+fn include_unchanged_commits_every_line_of_file_b() {
+    let (a, b) = ("one\ntwo\nthree\n", "one\n2\nthree\nfour");
+    let full = DiffOptions { include_unchanged: true, ..Default::default() };
+    assert_eq!(sequence(a, b, full.clone()), ["1=1", "2~2", "3=3", "+4", "NoNewline"]);
+    let mut output = output(a, b, full.clone());
+    assert_eq!(replayed_file_b(&output.diff_lines).as_deref(), Some(b));
+    assert_eq!(output.proof_hash, create_proof_hash(&output));
+    output.include_unchanged = false;
+    assert!(output.proof_hash != create_proof_hash(&output));
+
+    let same = diff(a, a, full);
+    assert_eq!(same.len(), 3);
+    assert!(same.iter().all(|line| line.operation == DiffOperation::Context));
+}
+
+#[test]
+#[should_panic(expected = "don't rebuild file B")]
+fn include_unchanged_refuses_hidden_lines_of_file_b() {
+    let full = DiffOptions { include_unchanged: true, ..Default::default() };
+    let mut input = input("one\n", "two\n", full);
+    input.redaction_ranges = vec![RedactionRange {
+        start_line: 1,
+        end_line: 1,
+        start_col: None,
+        end_col: None,
+        operation: DiffOperation::Replace,
+    }];
+    compute_diff(&input);
+}
+
+fn ignoring(a: &str, b: &str, lines_a: Vec<usize>, lines_b: Vec<usize>) -> DiffInput {
+    let mut input = input(a, b, DiffOptions { context: 1, ..Default::default() });
+    input.ignored_lines = Some(IgnoredLines { pattern: "^built ".into(), lines_a, lines_b });
+    input
+}
+
+#[test]
+fn ignored_lines_compare_equal_but_keep_their_content() {
+    let (a, b) = ("built 10:00
+x = 1
+", "built 11:30
+x = 2
+");
+    let output = compute_diff(&ignoring(a, b, vec![1], vec![1]));
+    assert_eq!(output.edit_distance, 2);
+    let first = &output.diff_lines[0];
+    assert_eq!(first.operation, DiffOperation::Context);
+    assert_eq!(first.content.as_deref(), Some("built 10:00"));
+    assert_eq!(output.ignore_pattern.as_deref(), Some("^built "));
+    assert_ne!(output.proof_hash, compute_diff(&input(a, b, DiffOptions::default())).proof_hash);
+}
+
+#[test]
+#[should_panic(expected = "Inconsistent matches for ignore pattern")]
+fn ignored_lines_must_include_every_equal_line() {
+    let a = "built 10:00
+x = 1
+built 10:00
+";
+    compute_diff(&ignoring(a, "built 11:30
+", vec![1], vec![1]));
+}
+
+fn anchored(a: &str, b: &str, lines_a: Vec<usize>, lines_b: Vec<usize>) -> DiffInput {
+    let mut input = input(a, b, DiffOptions::default());
+    input.sync_anchors = Some(SyncAnchors { pattern: r"^\[".into(), lines_a, lines_b });
+    input
+}
+
+#[test]
+fn anchors_align_and_every_segment_is_diffed_on_its_own() {
+    let (a, b) = ("[a]\nx\ny\n[b]\n", "[a]\n[b]\nx\ny\n");
+    assert_eq!(compute_diff(&input(a, b, DiffOptions::default())).edit_distance, 2);
+
+    let output = compute_diff(&anchored(a, b, vec![1, 4], vec![1, 2]));
+    assert_eq!(output.edit_distance, 4);
+    let changes: Vec<_> = output
+        .diff_lines
+        .iter()
+        .map(|line| (line.operation.clone(), line.line_number_a.or(line.line_number_b)))
+        .collect();
+    let expected = [
+        (DiffOperation::Delete, Some(2)),
+        (DiffOperation::Delete, Some(3)),
+        (DiffOperation::Insert, Some(3)),
+        (DiffOperation::Insert, Some(4)),
+    ];
+    assert_eq!(changes, expected);
+    assert_eq!(output.anchor_pattern.as_deref(), Some(r"^\["));
+}
+
+#[test]
+#[should_panic(expected = "Inconsistent matches for anchor pattern")]
+fn anchors_must_include_every_equal_line() {
+    compute_diff(&anchored("[a]\nx\n", "x\n[a]\n", vec![1], vec![]));
+}
+
+#[test]
+fn paths_match_globs_like_git_pathspecs() {
+    let cases = [
+        ("secrets/**", "secrets/prod/db.key", true),
+        ("secrets", "secrets/prod/db.key", true),
+        ("secrets/*", "secrets/prod/db.key", true),
+        ("secrets/*.key", "secrets/prod/db.key", false),
+        ("**/*.key", "secrets/prod/db.key", true),
+        ("**/*.key", "db.key", true),
+        ("*.key", "secrets/db.key", false),
+        ("db.?ey", "db.key", true),
+        ("secret", "secrets/db.key", false),
+    ];
+    for (glob, path, expected) in cases {
+        assert_eq!(path_matches(glob, path), expected, "{} on {}", glob, path);
+    }
+}
+
+#[test]
+fn files_matching_a_redacted_path_are_redacted_whole() {
+    let options = DiffOptions { redact_paths: vec!["secrets/**".into()], ..Default::default() };
+    let file = |path: &str, a: &str, b: &str| FileInput {
+        path: path.into(),
+        in_a: true,
+        in_b: true,
+        input: input(a, b, options.clone()),
+    };
+    let files = vec![
+        file("app.toml", "port = 1\n", "port = 2\n"),
+        file("secrets/key", "k1\n", "k2\n"),
+    ];
+    let mut tree = input("", "", options);
+    tree.file_a_hash = tree_hash(&files, false, HashAlgorithm::Sha256);
+    tree.file_b_hash = tree_hash(&files, true, HashAlgorithm::Sha256);
+    tree.files = files;
+
+    let output = compute_diff(&tree);
+    assert_eq!(output.redact_paths, ["secrets/**"]);
+    let (app, secret) = (&output.files[0].output, &output.files[1].output);
+    assert!(!app.redact_all && app.diff_lines.iter().all(|line| line.content.is_some()));
+    assert!(secret.redact_all && secret.diff_lines.iter().all(|line| line.content.is_none()));
+    assert_eq!(output.files[1].status, FileStatus::Modified);
+}
+
+#[test]
+fn structure_only_commits_the_files_without_diffing_them() {
+    let tree = |structure_only, b: &str| {
+        let options = DiffOptions { structure_only, ..Default::default() };
+        let file = |path: &str, in_a, in_b, a: &str, b: &str| FileInput {
+            path: path.into(),
+            in_a,
+            in_b,
+            input: input(a, b, options.clone()),
+        };
+        let files = vec![
+            file("changed", true, true, "one\ntwo\n", b),
+            file("new", false, true, "", "x\n"),
+            file("same", true, true, "s\n", "s\n"),
+        ];
+        let mut tree = input("", "", options);
+        tree.file_a_hash = tree_hash(&files, false, HashAlgorithm::Sha256);
+        tree.file_b_hash = tree_hash(&files, true, HashAlgorithm::Sha256);
+        tree.files = files;
+        compute_diff(&tree)
+    };
+
+    let output = tree(true, "one\n2\n");
+    assert!(output.structure_only && output.diff_lines.is_empty());
+    assert_eq!((output.lines_a, output.lines_b, output.edit_distance), (0, 0, 0));
+    let statuses: Vec<FileStatus> = output.files.iter().map(|file| file.status).collect();
+    assert_eq!(statuses, [FileStatus::Modified, FileStatus::Added, FileStatus::Unchanged]);
+    for file in &output.files {
+        assert!(file.output.structure_only && file.output.diff_lines.is_empty());
+    }
+    assert_eq!(output.files[0].output.file_b_hash, hash_content("one\n2\n"));
+
+    // a different file B changes its hash and so the proof, but not the diff
+    let other = tree(true, "one\n3\n");
+    assert_ne!(other.proof_hash, output.proof_hash);
+    assert_ne!(tree(false, "one\n2\n").proof_hash, output.proof_hash);
+    assert_eq!(tree(false, "one\n2\n").edit_distance, 3);
+}
+
+#[test]
+fn records_split_on_the_separator_and_may_hold_newlines() {
+    assert_eq!(records("a\0b\nc\0", Some(0)), ["a", "b\nc"]);
+    assert_eq!(records("a\0b\nc", Some(0)), ["a", "b\nc"]);
+    assert_eq!(records("\0", Some(0)), [""]);
+    assert!(records("", Some(0)).is_empty());
+
+    let options = DiffOptions { record_separator: Some(0), ..Default::default() };
+    let a = "one\0two\nlines\0three\0";
+    let output = output(a, "one\0two\nmore\0three\0", options.clone());
+    assert_eq!((output.lines_a, output.record_separator), (3, Some(0)));
+    assert_eq!(output.diff_lines.len(), 1);
+    assert_eq!(output.diff_lines[0].content.as_deref(), Some("two\nlines"));
+    assert_eq!(output.diff_lines[0].new_content.as_deref(), Some("two\nmore"));
+    let unseparated = DiffOutput { record_separator: None, ..output.clone() };
+    assert_ne!(output.proof_hash, create_proof_hash(&unseparated));
+
+    // a last record without a separator differs from the same text with one
+    assert_eq!(sequence(a, "one\0two\nlines\0three", options), ["3~3", "NoNewline"]);
+}
+
+#[test]
+fn dedup_commits_each_line_content_once() {
+    let a = "start\nGET /\nGET /\nend\n";
+    let b = "begin\nGET /\nGET /\nGET /\nGET /\nend\n";
+    let options = DiffOptions { dedup_lines: true, ..Default::default() };
+    let mut deduped = output(a, b, options.clone());
+    assert_eq!(deduped.line_dict, ["start", "begin", "GET /"]);
+    assert!(deduped.diff_lines.iter().all(|line| line.content.is_none()));
+    let indices: Vec<Option<usize>> = deduped.diff_lines.iter().map(|l| l.content_index).collect();
+    assert_eq!(indices, [Some(0), Some(1), Some(2), Some(2)]);
+    assert_eq!(deduped.proof_hash, create_proof_hash(&deduped));
+
+    // restored, the lines are those a diff without the dictionary commits
+    let lines = deduped.diff_lines.iter_mut();
+    restore_line_contents(lines, &deduped.line_dict).unwrap();
+    let plain = diff(a, b, DiffOptions::default());
+    for (restored, plain) in deduped.diff_lines.iter().zip(&plain) {
+        assert_eq!(restored.content, plain.content);
+        assert_eq!(restored.new_content, plain.new_content);
+        assert_eq!((restored.content_index, restored.new_content_index), (None, None));
+    }
+    assert_ne!(deduped.proof_hash, create_proof_hash(&deduped));
+
+    let mut forged = output(a, b, options);
+    forged.diff_lines[0].content_index = Some(3);
+    let lines = forged.diff_lines.iter_mut();
+    assert_eq!(restore_line_contents(lines, &forged.line_dict), Err(3));
+}
+
+#[test]
+fn the_proof_hash_is_pinned() {
+    // the guest is 32-bit, so a hash depending on the width of usize would change
+    // between it and the host and every real proof would fail its hash check
+    let output = output("one\ntwo\nthree\n", "one\n2\nthree\nfour\n", Default::default());
+    let hex: String = output.proof_hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+    assert_eq!(hex, "16dd28a861a9152c9ebe72503ca6443e0e748d9b3fcd1689c47919e15c122018");
+}
@@ -0,0 +1,15 @@
+impl Config {
+    pub fn new() -> Self {
+        Self { verbose: false, depth: 1 }
+    }
+
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn verbose(mut self) -> Self {
+        self.verbose = true;
+        self
+    }
+}
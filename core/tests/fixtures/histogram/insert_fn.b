@@ -0,0 +1,14 @@
+fn parse(input: &str) -> Vec<u32> {
+    input
+        .split(',')
+        .map(|s| s.trim().parse().unwrap())
+        .collect()
+}
+
+fn largest(values: &[u32]) -> Option<u32> {
+    values.iter().copied().max()
+}
+
+fn total(values: &[u32]) -> u32 {
+    values.iter().sum()
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{apply, generated_inputs};
+use zkdiff_core::{edit_distance, histogram_diff, myers_diff, Edit, EditOp};
+
+/// Hunk headers as printed by `git diff -U0`.
+fn hunks(edits: &[Edit]) -> Vec<String> {
+    let range = |start: usize, count: usize| match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    };
+
+    let mut headers = Vec::new();
+    let mut i = 0;
+    while i < edits.len() {
+        if edits[i].operation == EditOp::Keep {
+            i += 1;
+            continue;
+        }
+        let (old_start, new_start) = (edits[i].old_index, edits[i].new_index);
+        let (mut deleted, mut inserted) = (0, 0);
+        while i < edits.len() && edits[i].operation != EditOp::Keep {
+            match edits[i].operation {
+                EditOp::Delete => deleted += 1,
+                _ => inserted += 1,
+            }
+            i += 1;
+        }
+        headers.push(format!("@@ -{} +{} @@", range(old_start, deleted), range(new_start, inserted)));
+    }
+    headers
+}
+
+fn git_hunks(a: &str, b: &str) -> Vec<String> {
+    let a: Vec<&str> = a.split_inclusive('\n').collect();
+    let b: Vec<&str> = b.split_inclusive('\n').collect();
+    let edits = histogram_diff(&a, &b);
+    assert_eq!(apply(&edits, &a, &b), b);
+    hunks(&edits)
+}
+
+// expected hunks come from `git diff --no-index --histogram -U0 <a> <b>`
+
+#[test]
+fn matches_git_on_inserted_function() {
+    let a = include_str!("fixtures/histogram/insert_fn.a");
+    let b = include_str!("fixtures/histogram/insert_fn.b");
+    assert_eq!(git_hunks(a, b), ["@@ -7,0 +8,4 @@"]);
+}
+
+#[test]
+fn matches_git_on_rewritten_functions() {
+    let a = include_str!("fixtures/histogram/frobnitz.a");
+    let b = include_str!("fixtures/histogram/frobnitz.b");
+    assert_eq!(
+        git_hunks(a, b),
+        ["@@ -2,0 +3,9 @@", "@@ -9 +17,0 @@", "@@ -14,9 +21,0 @@", "@@ -25 +24 @@"]
+    );
+}
+
+#[test]
+fn matches_git_on_added_method() {
+    let a = include_str!("fixtures/histogram/builder.a");
+    let b = include_str!("fixtures/histogram/builder.b");
+    assert_eq!(git_hunks(a, b), ["@@ -3 +3,6 @@"]);
+}
+
+#[test]
+fn produces_valid_scripts() {
+    for (a, b) in generated_inputs(500) {
+        let histogram = histogram_diff(&a, &b);
+        assert_eq!(apply(&histogram, &a, &b), b, "script does not produce b: {:?} {:?}", a, b);
+        assert!(edit_distance(&histogram) >= edit_distance(&myers_diff(&a, &b)));
+    }
+}
@@ -0,0 +1,95 @@
+use zkdiff_core::{
+    compute_diff, hash_content, DiffInput, DiffOptions, MergeBase, MergeChunk, MergeStatus,
+};
+
+fn merge(base: &str, ours: &str, theirs: &str) -> Vec<MergeChunk> {
+    let input = DiffInput {
+        file_a_hash: hash_content(ours),
+        file_b_hash: hash_content(theirs),
+        file_a_content: ours.to_string(),
+        file_b_content: theirs.to_string(),
+        file_a_bytes: Vec::new(),
+        file_b_bytes: Vec::new(),
+        redaction_ranges: Vec::new(),
+        redaction_profile: None,
+        pattern_redactions: Vec::new(),
+        hunk_anchors: None,
+        ignored_lines: None,
+        sync_anchors: None,
+        options: DiffOptions::default(),
+        files: Vec::new(),
+        script: None,
+        merge_base: Some(MergeBase { hash: hash_content(base), content: base.to_string() }),
+    };
+    let output = compute_diff(&input);
+    assert_eq!(output.base_hash, Some(hash_content(base)));
+    output.merge_chunks
+}
+
+/// Chunks as `Status base+count ours+count theirs+count`, each side by its start.
+fn layout(chunks: &[MergeChunk]) -> Vec<String> {
+    chunks
+        .iter()
+        .map(|c| {
+            format!(
+                "{:?} {}+{} {}+{} {}+{}",
+                c.status,
+                c.base_start,
+                c.base_count,
+                c.ours_start,
+                c.ours_count,
+                c.theirs_start,
+                c.theirs_count
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn changes_to_different_regions_merge_cleanly() {
+    let base = "a\nb\nc\nd\ne\n";
+    let ours = "a\nB\nc\nd\ne\n";
+    let theirs = "a\nb\nc\nd\nE\nf\n";
+    let chunks = merge(base, ours, theirs);
+    assert_eq!(
+        layout(&chunks),
+        [
+            "Unchanged 1+1 1+1 1+1",
+            "Ours 2+1 2+1 2+1",
+            "Unchanged 3+2 3+2 3+2",
+            "Theirs 5+1 5+1 5+2",
+        ]
+    );
+    assert_eq!(chunks[1].ours_lines, ["B"]);
+    assert_eq!(chunks[3].theirs_lines, ["E", "f"]);
+    assert!(chunks[0].base_lines.is_empty() && chunks[0].ours_lines.is_empty());
+}
+
+#[test]
+fn different_changes_to_one_region_conflict() {
+    let chunks = merge("a\nb\nc\n", "a\nmine\nc\n", "a\ntheirs\nc\n");
+    assert_eq!(
+        layout(&chunks),
+        ["Unchanged 1+1 1+1 1+1", "Conflict 2+1 2+1 2+1", "Unchanged 3+1 3+1 3+1"]
+    );
+    let conflict = &chunks[1];
+    assert_eq!(conflict.status, MergeStatus::Conflict);
+    assert_eq!(conflict.base_lines, ["b"]);
+    assert_eq!(conflict.ours_lines, ["mine"]);
+    assert_eq!(conflict.theirs_lines, ["theirs"]);
+}
+
+#[test]
+fn the_same_change_on_both_sides_is_not_a_conflict() {
+    let chunks = merge("a\nb\n", "a\nx\n", "a\nx\n");
+    assert_eq!(layout(&chunks), ["Unchanged 1+1 1+1 1+1", "Both 2+1 2+1 2+1"]);
+}
+
+#[test]
+fn insertions_at_the_same_place_conflict() {
+    let chunks = merge("a\nb\n", "a\nours\nb\n", "a\ntheirs\nb\n");
+    assert_eq!(
+        layout(&chunks),
+        ["Unchanged 1+1 1+1 1+1", "Conflict 2+0 2+1 2+1", "Unchanged 2+1 3+1 3+1"]
+    );
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use common::{apply, generated_inputs, generated_inputs_up_to, lines};
+use zkdiff_core::{edit_distance, myers_diff, myers_diff_trace, EditOp};
+
+fn assert_equivalent(a: &[&str], b: &[&str]) {
+    let linear = myers_diff(a, b);
+    let trace = myers_diff_trace(a, b);
+    assert_eq!(apply(&linear, a, b), b, "linear script does not produce b: {:?} {:?}", a, b);
+    assert_eq!(apply(&trace, a, b), b);
+    assert_eq!(
+        edit_distance(&linear),
+        edit_distance(&trace),
+        "edit distances differ for {:?} -> {:?}",
+        a,
+        b
+    );
+}
+
+#[test]
+fn matches_trace_on_examples() {
+    let cases = [
+        ("", ""),
+        ("a\nb\nc", ""),
+        ("", "a\nb\nc"),
+        ("a\nb\nc", "a\nb\nc"),
+        ("a\nb\nc", "a\nx\nc"),
+        ("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc"),
+        ("a\nb\nc\nd\ne\nf", "f\ne\nd\nc\nb\na"),
+        ("x\na\nb\nc", "a\nb\nc\nx"),
+        ("a\na\na\na", "a\na"),
+        ("a\nb", "c\nd"),
+        ("one\ntwo\nthree\nfour", "zero\none\nthree\nfour\nfive"),
+    ];
+    for (a, b) in cases {
+        assert_equivalent(&lines(a), &lines(b));
+    }
+}
+
+#[test]
+fn matches_trace_on_generated_inputs() {
+    for (a, b) in generated_inputs(500) {
+        assert_equivalent(&a, &b);
+    }
+}
+
+// long, lopsided and near-disjoint inputs push the trace version's diagonals to
+// the edges of its vectors, where backtracking indexes next to them
+#[test]
+fn matches_trace_on_long_generated_inputs() {
+    for (a, b) in generated_inputs_up_to(400, 60, 8) {
+        assert_equivalent(&a, &b);
+    }
+    for len in [1, 2, 59] {
+        let long = vec!["a"; len];
+        for (a, b) in [(&long[..], &[][..]), (&[][..], &long[..]), (&long[..], &["b"][..])] {
+            assert_equivalent(a, b);
+            assert_equivalent(b, a);
+        }
+    }
+}
+
+/// Random line vectors of up to 100 lines from a seeded xorshift. Each line is
+/// drawn from a tiny alphabet, so lines repeat often and compete for alignment, or
+/// is an arbitrary string of up to 8 chars, which is mostly unique.
+fn random_lines(next: &mut impl FnMut() -> u64) -> Vec<String> {
+    let len = next() % 100;
+    (0..len)
+        .map(|_| match next() % 3 {
+            0 => "abc"[..(next() % 3) as usize].to_string(),
+            1 => ["a", "b", "c"][(next() % 3) as usize].to_string(),
+            _ => (0..next() % 9).filter_map(|_| char::from_u32((next() % 0x3000) as u32)).collect(),
+        })
+        .collect()
+}
+
+// ZKDIFF_TEST_SEED runs another sequence of inputs than the default one
+#[test]
+fn matches_trace_on_random_inputs() {
+    let seed = std::env::var("ZKDIFF_TEST_SEED").ok().and_then(|s| s.parse().ok());
+    let mut state: u64 = seed.unwrap_or(0x9e37_79b9_7f4a_7c15) | 1;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for _ in 0..1000 {
+        let (a, b) = (random_lines(&mut next), random_lines(&mut next));
+        let a: Vec<&str> = a.iter().map(String::as_str).collect();
+        let b: Vec<&str> = b.iter().map(String::as_str).collect();
+        assert_equivalent(&a, &b);
+    }
+}
+
+#[test]
+fn deletes_come_before_inserts() {
+    let a = lines("a\nb\nc");
+    let b = lines("a\nx\ny\nc");
+    let ops: Vec<EditOp> = myers_diff(&a, &b).into_iter().map(|e| e.operation).collect();
+    assert_eq!(
+        ops,
+        [EditOp::Keep, EditOp::Delete, EditOp::Insert, EditOp::Insert, EditOp::Keep]
+    );
+}
+
+#[test]
+fn empty_inputs_give_one_sided_scripts() {
+    let edits = |a: &[&str], b: &[&str]| -> Vec<Vec<(EditOp, usize, usize)>> {
+        [myers_diff(a, b), myers_diff_trace(a, b)]
+            .iter()
+            .map(|script| {
+                script.iter().map(|e| (e.operation.clone(), e.old_index, e.new_index)).collect()
+            })
+            .collect()
+    };
+    let abc = lines("a\nb\nc");
+
+    for script in edits(&[], &abc) {
+        assert_eq!(script, [(EditOp::Insert, 0, 0), (EditOp::Insert, 0, 1), (EditOp::Insert, 0, 2)]);
+    }
+    for script in edits(&abc, &[]) {
+        assert_eq!(script, [(EditOp::Delete, 0, 0), (EditOp::Delete, 1, 0), (EditOp::Delete, 2, 0)]);
+    }
+    for script in edits(&[], &[]) {
+        assert!(script.is_empty());
+    }
+}
+
+#[test]
+fn common_ends_only_shift_the_script() {
+    let ends = 50;
+    let script = |a: &[&str], b: &[&str]| -> Vec<(EditOp, usize, usize)> {
+        myers_diff(a, b).into_iter().map(|e| (e.operation, e.old_index, e.new_index)).collect()
+    };
+    for (a, b) in generated_inputs(200) {
+        let wrap = |lines: &[&'static str]| {
+            [vec!["head"; ends], lines.to_vec(), vec!["tail"; ends]].concat()
+        };
+        let mut expected: Vec<_> = (0..ends).map(|i| (EditOp::Keep, i, i)).collect();
+        expected.extend(script(&a, &b).into_iter().map(|(op, x, y)| (op, x + ends, y + ends)));
+        let (end_a, end_b) = (ends + a.len(), ends + b.len());
+        expected.extend((0..ends).map(|i| (EditOp::Keep, end_a + i, end_b + i)));
+        assert_eq!(script(&wrap(&a), &wrap(&b)), expected, "{:?} -> {:?}", a, b);
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{apply, generated_inputs, lines};
+use zkdiff_core::{edit_distance, myers_diff, patience_diff, Edit, EditOp};
+
+fn kept<'a>(edits: &[Edit], a: &[&'a str]) -> Vec<&'a str> {
+    edits
+        .iter()
+        .filter(|e| e.operation == EditOp::Keep)
+        .map(|e| a[e.old_index])
+        .collect()
+}
+
+#[test]
+fn produces_valid_scripts() {
+    for (a, b) in generated_inputs(500) {
+        let patience = patience_diff(&a, &b);
+        assert_eq!(apply(&patience, &a, &b), b, "script does not produce b: {:?} {:?}", a, b);
+        assert!(edit_distance(&patience) >= edit_distance(&myers_diff(&a, &b)));
+    }
+}
+
+#[test]
+fn anchors_on_unique_lines_instead_of_braces() {
+    let a = lines("int x;\n{\n  foo();\n}\n{\n  bar();\n}");
+    let b = lines("int x;\n{\n  bar();\n}\n{\n  baz();\n}\n{\n  foo();\n}");
+
+    // myers keeps every brace, so no statement survives and three are rewritten
+    assert_eq!(kept(&myers_diff(&a, &b), &a), ["int x;", "{", "}", "{", "}"]);
+    // patience matches the block that really stayed and moves the others around it
+    assert_eq!(kept(&patience_diff(&a, &b), &a), ["int x;", "{", "  bar();", "}"]);
+}
+
+#[test]
+fn identical_and_empty_inputs() {
+    let a = lines("a\nb\nc");
+    assert!(patience_diff(&a, &a).iter().all(|e| e.operation == EditOp::Keep));
+    assert_eq!(edit_distance(&patience_diff(&a, &[])), 3);
+    assert_eq!(edit_distance(&patience_diff(&[], &a)), 3);
+    assert!(patience_diff(&[], &[]).is_empty());
+}
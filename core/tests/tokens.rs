@@ -0,0 +1,99 @@
+mod common;
+
+use common::{apply, generated_inputs, lines};
+use zkdiff_core::{
+    compute_diff, hash_content, token_line_edits, tokenize, Algorithm, DiffInput, DiffOptions,
+    EditOp, Tokenizer,
+};
+
+fn changed(a: &str, b: &str, tokenizer: Tokenizer) -> (Vec<usize>, Vec<usize>) {
+    let (a, b) = (lines(a), lines(b));
+    let edits = token_line_edits(Algorithm::Myers, tokenizer, &a, &b);
+    assert_eq!(apply(&edits, &a, &b), b);
+    let deleted = edits.iter().filter(|e| e.operation == EditOp::Delete).map(|e| e.old_index);
+    let inserted = edits.iter().filter(|e| e.operation == EditOp::Insert).map(|e| e.new_index);
+    (deleted.map(|x| x + 1).collect(), inserted.map(|y| y + 1).collect())
+}
+
+#[test]
+fn generic_tokens_are_words_and_single_characters() {
+    assert_eq!(
+        tokenize("  let x_1 = foo(a, 2);\n", Tokenizer::Generic),
+        ["let", "x_1", "=", "foo", "(", "a", ",", "2", ")", ";"]
+    );
+    assert_eq!(
+        tokenize("a::b -> \"c d\"", Tokenizer::Generic),
+        ["a", ":", ":", "b", "-", ">", "\"", "c", "d", "\""]
+    );
+    assert!(tokenize(" \t\n", Tokenizer::Generic).is_empty());
+}
+
+#[test]
+fn rust_tokens_keep_literals_comments_and_operators_whole() {
+    assert_eq!(
+        tokenize("a::b -> \"c \\\" d\" // note\n", Tokenizer::Rust),
+        ["a", "::", "b", "->", "\"c \\\" d\"", "// note"]
+    );
+    assert_eq!(
+        tokenize("f<'a>(c) { c == '\\n' || c == 'x' }", Tokenizer::Rust),
+        ["f", "<", "'a", ">", "(", "c", ")", "{", "c", "==", "'\\n'", "||", "c", "==", "'x'", "}"]
+    );
+    assert_eq!(tokenize("x..=y \"open", Tokenizer::Rust), ["x", "..=", "y", "\"open"]);
+}
+
+#[test]
+fn only_lines_with_changed_tokens_are_changed() {
+    let a = "use a;\n\nfn main() {\n    run(1);\n}\n";
+    let b = "use a;\n\nfn main() {\n    run(2);\n}\n";
+    for tokenizer in [Tokenizer::Generic, Tokenizer::Rust] {
+        assert_eq!(changed(a, b, tokenizer), (vec![4], vec![4]));
+    }
+}
+
+#[test]
+fn lines_split_or_respaced_are_changed() {
+    let (deleted, inserted) = changed("f(a, b);\nend\n", "f(a,\n  b);\nend\n", Tokenizer::Rust);
+    assert_eq!((deleted, inserted), (vec![1], vec![1, 2]));
+    assert_eq!(changed("f(a, b);\n", "f(a,b);\n", Tokenizer::Generic), (vec![1], vec![1]));
+}
+
+#[test]
+fn token_scripts_rebuild_file_b() {
+    for (a, b) in generated_inputs(300) {
+        for tokenizer in [Tokenizer::Generic, Tokenizer::Rust] {
+            for algorithm in [Algorithm::Myers, Algorithm::Patience, Algorithm::Histogram] {
+                let edits = token_line_edits(algorithm, tokenizer, &a, &b);
+                assert_eq!(apply(&edits, &a, &b), b, "{:?} -> {:?}", a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn the_tokenizer_is_committed() {
+    let (a, b) = ("x = 1;\n", "x = 2;\n");
+    let input = |tokenizer| DiffInput {
+        file_a_hash: hash_content(a),
+        file_b_hash: hash_content(b),
+        file_a_content: a.to_string(),
+        file_b_content: b.to_string(),
+        file_a_bytes: Vec::new(),
+        file_b_bytes: Vec::new(),
+        redaction_ranges: Vec::new(),
+        redaction_profile: None,
+        pattern_redactions: Vec::new(),
+        hunk_anchors: None,
+        ignored_lines: None,
+        sync_anchors: None,
+        options: DiffOptions { tokenizer, ..Default::default() },
+        files: Vec::new(),
+        script: None,
+        merge_base: None,
+    };
+    let lines = compute_diff(&input(None));
+    let tokens = compute_diff(&input(Some(Tokenizer::Rust)));
+    assert_eq!(tokens.tokenizer, Some(Tokenizer::Rust));
+    assert_eq!(tokens.diff_lines.len(), lines.diff_lines.len());
+    assert_ne!(tokens.proof_hash, lines.proof_hash);
+    assert_ne!(tokens.proof_hash, compute_diff(&input(Some(Tokenizer::Generic))).proof_hash);
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+use std::io;
+use std::str::Utf8Error;
+
+/// Error returned by the library, one variant per kind of failure a caller may want
+/// to handle on its own. The CLI collapses them into `anyhow` errors.
+#[derive(Debug)]
+pub enum ZkDiffError {
+    Io(io::Error),
+    Utf8(Utf8Error),
+    /// Every malformed `--redact` range, each with the text it came from.
+    BadRedactionSpec(Vec<String>),
+    /// A proof file couldn't be encoded, or bytes couldn't be decoded as one.
+    Encoding(String),
+    Prove(String),
+    /// The receipt is missing or doesn't verify against this guest, with the reason.
+    Verify(String),
+    /// File B couldn't be rebuilt from a proof and file A.
+    Apply(String),
+    /// The proof file names another guest than the one built into this library.
+    MethodIdMismatch { expected: String, found: String },
+    /// What the first proof hash that disagrees with its diff belongs to, as
+    /// `proof_hash_mismatch` names it.
+    ProofHashMismatch(String),
+    /// How the diff in the proof file differs from the one the receipt's journal
+    /// commits, as `journal_mismatch` describes it.
+    JournalMismatch(String),
+    /// The proof file didn't decode, and was written with another risc0 version
+    /// than the `current` one this library is built with.
+    Risc0Version { found: String, current: String },
+}
+
+impl fmt::Display for ZkDiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZkDiffError::Io(e) => write!(f, "{}", e),
+            ZkDiffError::Utf8(e) => write!(f, "{}", e),
+            ZkDiffError::BadRedactionSpec(problems) => {
+                write!(f, "Invalid --redact ranges: {}", problems.join("; "))
+            }
+            ZkDiffError::Encoding(reason) => write!(f, "Malformed proof file: {}", reason),
+            ZkDiffError::Prove(reason) => write!(f, "Proving failed: {}", reason),
+            ZkDiffError::Verify(reason) => write!(f, "Proof verification failed: {}", reason),
+            ZkDiffError::Apply(reason) => write!(f, "{}", reason),
+            ZkDiffError::MethodIdMismatch { expected, found } => {
+                write!(f, "Method ID mismatch: expected {}, found {}", expected, found)
+            }
+            ZkDiffError::ProofHashMismatch(mismatch) => write!(
+                f,
+                "Proof hash mismatch for {}; the diff was changed after it was computed",
+                mismatch
+            ),
+            ZkDiffError::JournalMismatch(mismatch) => {
+                write!(f, "The diff doesn't match the receipt's journal: {}", mismatch)
+            }
+            ZkDiffError::Risc0Version { found, current } => write!(
+                f,
+                "This proof was generated with risc0 {}; you have {}, and it can't be read with this version. Verify it with a zkdiff built against risc0 {}",
+                found, current, found
+            ),
+        }
+    }
+}
+
+// Io and Utf8 display their error as is, so its source is theirs
+impl std::error::Error for ZkDiffError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ZkDiffError::Io(e) => e.source(),
+            ZkDiffError::Utf8(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ZkDiffError {
+    fn from(e: io::Error) -> Self {
+        ZkDiffError::Io(e)
+    }
+}
+
+impl From<Utf8Error> for ZkDiffError {
+    fn from(e: Utf8Error) -> Self {
+        ZkDiffError::Utf8(e)
+    }
+}
+
+impl From<serde_json::Error> for ZkDiffError {
+    fn from(e: serde_json::Error) -> Self {
+        ZkDiffError::Encoding(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for ZkDiffError {
+    fn from(e: bincode::Error) -> Self {
+        ZkDiffError::Encoding(e.to_string())
+    }
+}
@@ -0,0 +1,680 @@
+use methods::METHOD_ID;
+use risc0_zkvm::Receipt;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+mod error;
+
+pub use error::ZkDiffError;
+pub use zkdiff_core::*;
+
+type Result<T, E = ZkDiffError> = std::result::Result<T, E>;
+
+/// Prefix identifying a bincode-encoded proof file. JSON proofs start with `{`.
+pub const BINCODE_MAGIC: &[u8] = b"ZKDIFF\0\x01";
+
+/// Version of this library, as recorded in the proof files it writes.
+pub const ZKDIFF_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The fields of a bincode proof file up to its receipt, in order.
+type PrefixFields = (bool, DiffOutput, String, bool, Option<String>, Option<String>);
+
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+pub struct ProofFile {
+    pub verified: bool,
+    pub output: DiffOutput,
+    pub method_id: String,
+    pub proof_generated: bool,
+    /// Versions of zkdiff and risc0 that wrote the proof file. They come before the
+    /// receipt, so they still decode when the receipt's format has changed. Older
+    /// JSON proofs without them still decode.
+    #[serde(default)]
+    pub zkdiff_version: Option<String>,
+    #[serde(default)]
+    pub risc0_version: Option<String>,
+    /// The risc0 receipt, left opaque by the schema.
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub receipt: Option<Receipt>,
+    /// Cost of proving, as measured by the host. Not covered by the receipt. Older
+    /// JSON proofs without it still decode.
+    #[serde(default)]
+    pub metrics: Option<ProofMetrics>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, JsonSchema)]
+pub struct ProofMetrics {
+    pub prove_seconds: f64,
+    pub total_cycles: u64,
+    pub user_cycles: u64,
+    pub segments: usize,
+}
+
+/// JSON Schema of a JSON-encoded `ProofFile`, derived from the structs, for clients
+/// in other languages.
+pub fn proof_file_schema() -> RootSchema {
+    schemars::schema_for!(ProofFile)
+}
+
+/// Image ID in the hex form risc0 prints and publishes: each word as little-endian
+/// bytes, whatever the host's byte order.
+pub fn method_id_hex(id: [u32; 8]) -> String {
+    hex::encode(id.iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<u8>>())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProofFormat {
+    Json,
+    Bincode,
+}
+
+impl ProofFile {
+    pub fn encode(&self, format: ProofFormat) -> Result<Vec<u8>> {
+        match format {
+            ProofFormat::Json => Ok(serde_json::to_vec_pretty(self)?),
+            ProofFormat::Bincode => {
+                let mut bytes = BINCODE_MAGIC.to_vec();
+                bincode::serialize_into(&mut bytes, self)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Decodes a proof file in either format, detected from the magic header. A
+    /// proof that doesn't decode because another risc0 version wrote its receipt
+    /// fails with `Risc0Version` rather than the decoding error.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let decoded = match bytes.strip_prefix(BINCODE_MAGIC) {
+            Some(payload) => bincode::deserialize(payload).map_err(ZkDiffError::from),
+            None => serde_json::from_slice(bytes).map_err(ZkDiffError::from),
+        };
+        decoded.map_err(|e| match risc0_version_mismatch(bytes) {
+            Some(found) => ZkDiffError::Risc0Version {
+                found,
+                current: risc0_zkvm::VERSION.to_string(),
+            },
+            None => e,
+        })
+    }
+
+    /// Reads and decodes the proof file at `path`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        Self::decode(&std::fs::read(path)?)
+    }
+
+    /// Like `encode`, but the same proof file always gives the same bytes: JSON
+    /// objects at every level, the receipt's included, have their keys sorted.
+    /// Bincode follows the field order and is canonical already.
+    pub fn encode_canonical(&self, format: ProofFormat) -> Result<Vec<u8>> {
+        match format {
+            ProofFormat::Json => {
+                Ok(serde_json::to_vec_pretty(&sorted_keys(serde_json::to_value(self)?))?)
+            }
+            ProofFormat::Bincode => self.encode(format),
+        }
+    }
+}
+
+/// risc0 version recorded in a proof file, when it has one and it isn't the one
+/// this library was built with.
+fn risc0_version_mismatch(bytes: &[u8]) -> Option<String> {
+    let found = match bytes.strip_prefix(BINCODE_MAGIC) {
+        // bincode doesn't mind the receipt left over after the prefix
+        Some(payload) => bincode::deserialize::<PrefixFields>(payload).ok()?.5,
+        None => {
+            let proof: Value = serde_json::from_slice(bytes).ok()?;
+            proof.get("risc0_version")?.as_str().map(String::from)
+        }
+    }?;
+    (found != risc0_zkvm::VERSION).then_some(found)
+}
+
+// sorted explicitly, since serde_json keeps insertion order when any crate in the
+// build enables its preserve_order feature
+fn sorted_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            let sorted = entries.into_iter().map(|(key, value)| (key, sorted_keys(value)));
+            Value::Object(sorted.collect())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sorted_keys).collect()),
+        other => other,
+    }
+}
+
+/// Outcome of every check `verify_proof_file` makes, each reported on its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyReport {
+    /// Method ID the proof file names, and whether it is the guest built into this
+    /// library.
+    pub method_id: String,
+    pub method_id_matches: bool,
+    pub receipt: ReceiptStatus,
+    /// What the first proof hash that disagrees with its diff belongs to, as
+    /// `proof_hash_mismatch` names it.
+    pub proof_hash_mismatch: Option<String>,
+    /// How the diff in the proof file strays from the one the receipt's journal
+    /// commits, as `journal_mismatch` describes it. None without a receipt.
+    pub journal_mismatch: Option<String>,
+    /// Changed lines by kind, and diff lines of every kind, over all files.
+    pub stats: DiffStats,
+    pub diff_lines: usize,
+}
+
+impl VerifyReport {
+    /// The proof is attested by this guest and its diff is the one proven.
+    pub fn passed(&self) -> bool {
+        self.method_id_matches
+            && self.receipt == ReceiptStatus::Valid
+            && self.proof_hash_mismatch.is_none()
+            && self.journal_mismatch.is_none()
+    }
+
+    /// `Ok` when `passed`, else the first failed check as an error, in the order
+    /// `verify` reports them: method ID, proof hash, receipt, journal.
+    pub fn check(&self) -> Result<()> {
+        if !self.method_id_matches {
+            return Err(ZkDiffError::MethodIdMismatch {
+                expected: method_id_hex(METHOD_ID),
+                found: self.method_id.clone(),
+            });
+        }
+        if let Some(mismatch) = &self.proof_hash_mismatch {
+            return Err(ZkDiffError::ProofHashMismatch(mismatch.clone()));
+        }
+        match &self.receipt {
+            ReceiptStatus::Valid => {}
+            ReceiptStatus::Invalid(reason) => return Err(ZkDiffError::Verify(reason.clone())),
+            ReceiptStatus::Missing => {
+                return Err(ZkDiffError::Verify("the proof file has no receipt".to_string()))
+            }
+        }
+        match &self.journal_mismatch {
+            Some(mismatch) => Err(ZkDiffError::JournalMismatch(mismatch.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReceiptStatus {
+    Valid,
+    /// The receipt doesn't verify against this guest, with risc0's reason.
+    Invalid(String),
+    /// The proof file has no receipt, as with `--dev` or `--json-only`.
+    Missing,
+}
+
+/// Checks a proof file against the guest built into this library without printing
+/// anything: its method ID, its receipt, the proof hash of its diff and that the
+/// diff is the one the receipt's journal holds. Failed checks are part of the
+/// report rather than errors. Redaction patterns aren't checked against visible
+/// lines here. Decoding the file is `ProofFile::decode`'s job, so nothing is left
+/// that could fail.
+pub fn verify_proof_file(proof_file: &ProofFile) -> VerifyReport {
+    let output = &proof_file.output;
+    let receipt = match &proof_file.receipt {
+        Some(receipt) => match receipt.verify(METHOD_ID) {
+            Ok(()) => ReceiptStatus::Valid,
+            Err(e) => ReceiptStatus::Invalid(e.to_string()),
+        },
+        None => ReceiptStatus::Missing,
+    };
+    let journal_mismatch = proof_file.receipt.as_ref().and_then(|r| journal_mismatch(r, output));
+    VerifyReport {
+        method_id: proof_file.method_id.clone(),
+        method_id_matches: proof_file.method_id == method_id_hex(METHOD_ID),
+        receipt,
+        proof_hash_mismatch: proof_hash_mismatch(output).map(str::to_string),
+        journal_mismatch,
+        stats: diff_stats(output),
+        diff_lines: output.diff_lines.len()
+            + output.files.iter().map(|file| file.output.diff_lines.len()).sum::<usize>(),
+    }
+}
+
+/// Recomputes the proof hash of every file output and of `output` itself from the
+/// decoded fields, returning what the first disagreeing hash belongs to: a file's
+/// path, or "the diff".
+pub fn proof_hash_mismatch(output: &DiffOutput) -> Option<&str> {
+    for file in &output.files {
+        if create_proof_hash(&file.output) != file.output.proof_hash {
+            return Some(&file.path);
+        }
+    }
+    if create_proof_hash(output) != output.proof_hash {
+        return Some("the diff");
+    }
+    None
+}
+
+/// Puts the content of a dedup-mode output back into its lines, and those of its
+/// files into theirs, from the line dictionaries they commit. Check the proof hash
+/// first: the result no longer matches it.
+pub fn expand_line_dict(output: &mut DiffOutput) -> Result<()> {
+    for file in &mut output.files {
+        expand_line_dict(&mut file.output)?;
+    }
+    let hunk_lines = output.hunks.iter_mut().flat_map(|hunk| hunk.lines.iter_mut());
+    let lines = output.diff_lines.iter_mut().chain(hunk_lines);
+    restore_line_contents(lines, &output.line_dict).map_err(|index| {
+        ZkDiffError::Encoding(format!(
+            "line content index {} is outside the line dictionary of {} entries",
+            index,
+            output.line_dict.len()
+        ))
+    })
+}
+
+/// Compares `output` with the diff the journal of `receipt` commits, which is what
+/// the receipt attests rather than what the proof file claims. Returns the first
+/// top-level field whose value differs, as "field `name` differs", or why the journal
+/// isn't a diff at all.
+pub fn journal_mismatch(receipt: &Receipt, output: &DiffOutput) -> Option<String> {
+    let journal: DiffOutput = match receipt.journal.decode() {
+        Ok(journal) => journal,
+        Err(e) => return Some(format!("the journal doesn't decode as a diff: {}", e)),
+    };
+    // both sides as JSON objects, whose keys are sorted
+    let fields = |output: &DiffOutput| match serde_json::to_value(output) {
+        Ok(Value::Object(fields)) => fields,
+        _ => unreachable!("a DiffOutput serializes to an object"),
+    };
+    let (journal, output) = (fields(&journal), fields(output));
+    let differing = output.iter().find(|(name, value)| journal.get(*name) != Some(*value));
+    differing
+        .map(|(name, _)| name)
+        .or_else(|| journal.keys().find(|name| !output.contains_key(*name)))
+        .map(|name| format!("field `{}` differs", name))
+}
+
+/// Parses `--redact` ranges, `operation:start-end` or
+/// `operation:start-end:col_start-col_end` separated by commas. Every malformed
+/// range is reported, each with the text it came from, rather than stopping at the
+/// first or skipping it. Line ranges whose start is after their end parse, and are
+/// left to `redaction_range_problems`. An `any` range hides the lines it covers
+/// however the diff shows them, inserted lines by their number in file B and all
+/// others by their number in file A; a delete range hides the old side of replaced
+/// lines too.
+pub fn parse_redaction_ranges(redact_str: &str) -> Result<Vec<RedactionRange>> {
+    if redact_str.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut ranges = Vec::new();
+    let mut errors = Vec::new();
+    for range_str in redact_str.split(',') {
+        match parse_redaction_range(range_str) {
+            Ok(range) => ranges.push(range),
+            Err(reason) => errors.push(format!("{:?}: {}", range_str, reason)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ZkDiffError::BadRedactionSpec(errors));
+    }
+    Ok(ranges)
+}
+
+fn parse_redaction_range(range_str: &str) -> std::result::Result<RedactionRange, String> {
+    let range_str = range_str.trim();
+    if range_str.is_empty() {
+        return Err("empty range".into());
+    }
+    let parts: Vec<&str> = range_str.split(':').collect();
+    if parts.len() != 2 && parts.len() != 3 {
+        return Err(
+            "expected operation:start-end, optionally followed by :col_start-col_end".into(),
+        );
+    }
+
+    let operation = match parts[0] {
+        "insert" | "i" => DiffOperation::Insert,
+        "delete" | "d" => DiffOperation::Delete,
+        "replace" | "r" => DiffOperation::Replace,
+        "context" | "c" => DiffOperation::Context,
+        "any" | "a" => DiffOperation::Any,
+        "" => return Err("missing operation".into()),
+        other => {
+            return Err(format!(
+                "unknown operation {:?}, expected insert, delete, replace, context or any",
+                other
+            ))
+        }
+    };
+    let (start_line, end_line) = parse_span(parts[1], "line")?;
+    let (start_col, end_col) = match parts.get(2) {
+        Some(cols) => {
+            let (start, end) = parse_span(cols, "column")?;
+            if start > end {
+                return Err(format!("start column {} is after end column {}", start, end));
+            }
+            (Some(start), Some(end))
+        }
+        None => (None, None),
+    };
+
+    Ok(RedactionRange {
+        start_line,
+        end_line,
+        start_col,
+        end_col,
+        operation,
+    })
+}
+
+/// `start-end` of 1-based line or column numbers.
+fn parse_span(span: &str, unit: &str) -> std::result::Result<(usize, usize), String> {
+    if span.strip_prefix('-').is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit())) {
+        return Err(format!("{} numbers can't be negative", unit));
+    }
+    let Some((start, end)) = span.split_once('-') else {
+        return Err(format!("{} range {:?} should be START-END", unit, span));
+    };
+    let number = |text: &str, which: &str| match text.parse::<usize>() {
+        Ok(0) if unit == "column" => Err("columns start at 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(_) if text.is_empty() => Err(format!("missing {} {}", which, unit)),
+        Err(_) if text.starts_with('-') => Err(format!("{} numbers can't be negative", unit)),
+        Err(_) => Err(format!("{} {} {:?} isn't a whole number", which, unit, text)),
+    };
+    Ok((number(start, "start")?, number(end, "end")?))
+}
+
+/// Describes every range that can't redact what it names: one whose start is after
+/// its end, or that reaches outside its file. Insert ranges count lines of file B,
+/// `any` ranges those of the longer file, all others lines of file A.
+pub fn redaction_range_problems(
+    ranges: &[RedactionRange],
+    lines_a: usize,
+    lines_b: usize,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    for range in ranges {
+        let (lines, outside) = match range.operation {
+            DiffOperation::Insert => {
+                (lines_b, format!("outside file B, which has {} lines", lines_b))
+            }
+            DiffOperation::Any => (
+                lines_a.max(lines_b),
+                format!("outside both files, which have {} and {} lines", lines_a, lines_b),
+            ),
+            _ => (lines_a, format!("outside file A, which has {} lines", lines_a)),
+        };
+        let label = format!("{:?}:{}-{}", range.operation, range.start_line, range.end_line);
+        if range.start_line > range.end_line {
+            problems.push(format!("{}: start line is after end line", label));
+        } else if range.start_line == 0 || range.end_line > lines {
+            problems.push(format!("{}: {}", label, outside));
+        }
+    }
+    problems
+}
+
+/// Where `ranges` and the diff of `file_a` into `file_b` don't meet, one message per
+/// range and kind, labelled as in `redaction_range_problems`: lines the range covers
+/// that the diff shows in full under an operation the range doesn't match, and
+/// unchanged lines it covers that the diff leaves out, so there is nothing to hide.
+/// A range only hides diff lines of its own operation; an `any` range is checked in
+/// both files, against the lines it can hide in each. The contents are those the
+/// guest diffs, with a plain Myers diff of their comparison keys, so with another
+/// algorithm or tokenizer the lines can differ a little.
+pub fn redaction_range_gaps(
+    ranges: &[RedactionRange],
+    file_a: &str,
+    file_b: &str,
+    options: &DiffOptions,
+) -> Vec<String> {
+    let lines_a = records(file_a, options.record_separator);
+    let lines_b = records(file_b, options.record_separator);
+    let (keys_a, keys_b) = (comparison_keys(file_a, options), comparison_keys(file_b, options));
+    let keys_a: Vec<&str> = keys_a.iter().map(String::as_str).collect();
+    let keys_b: Vec<&str> = keys_b.iter().map(String::as_str).collect();
+    let edits = myers_diff(&keys_a, &keys_b);
+    let diff_lines = create_diff_lines(&edits, &lines_a, &lines_b, ranges, options);
+
+    // how the diff shows each line of either file, and whether it hides it
+    let mut shown_a = vec![None; lines_a.len()];
+    let mut shown_b = vec![None; lines_b.len()];
+    for line in &diff_lines {
+        let old_hidden = line.redacted_length.is_some() || line.redacted_spans.is_some();
+        let new_hidden = line.new_redacted_length.is_some() || line.new_redacted_spans.is_some();
+        let operation = &line.operation;
+        let (side_a, side_b) = match operation {
+            DiffOperation::Insert => (None, Some(old_hidden)),
+            DiffOperation::Replace => (Some(old_hidden), Some(new_hidden)),
+            _ => (Some(old_hidden), Some(old_hidden)),
+        };
+        if let (Some(a), Some(hidden)) = (line.line_number_a, side_a) {
+            shown_a[a - 1] = Some((operation.clone(), hidden));
+        }
+        if let (Some(b), Some(hidden)) = (line.line_number_b, side_b) {
+            shown_b[b - 1] = Some((operation.clone(), hidden));
+        }
+    }
+
+    const ALL: [DiffOperation; 4] = [
+        DiffOperation::Delete,
+        DiffOperation::Insert,
+        DiffOperation::Replace,
+        DiffOperation::Context,
+    ];
+    let mut gaps = Vec::new();
+    for range in ranges {
+        // the sides the range covers, with the operations it is checked against
+        let sides = match range.operation {
+            DiffOperation::Insert => vec![("file B", &shown_b, &ALL[..])],
+            DiffOperation::Any => {
+                vec![("file A", &shown_a, &ALL[..]), ("file B", &shown_b, &ALL[1..3])]
+            }
+            _ => vec![("file A", &shown_a, &ALL[..])],
+        };
+        let label = format!("{:?}:{}-{}", range.operation, range.start_line, range.end_line);
+        for (file, shown, operations) in sides {
+            let covered = range.start_line.max(1)..=range.end_line.min(shown.len());
+            for operation in operations {
+                let exposed: Vec<usize> = covered
+                    .clone()
+                    .filter(|&n| shown[n - 1] == Some((operation.clone(), false)))
+                    .collect();
+                if !exposed.is_empty() {
+                    gaps.push(format!(
+                        "{}: {} shown in full as {:?}, which the range doesn't hide",
+                        label,
+                        line_list(&exposed, file),
+                        operation
+                    ));
+                }
+            }
+            // context and `any` ranges are meant to cover lines that may not be shown
+            let unchanged: Vec<usize> = covered.filter(|&n| shown[n - 1].is_none()).collect();
+            let expected = matches!(range.operation, DiffOperation::Context | DiffOperation::Any);
+            if !unchanged.is_empty() && !expected {
+                gaps.push(format!(
+                    "{}: {} unchanged and not in the diff, so there is nothing to hide",
+                    label,
+                    line_list(&unchanged, file)
+                ));
+            }
+        }
+    }
+    gaps
+}
+
+/// `lines` of `file` as runs, like "lines 3-5, 9 of file A are".
+fn line_list(lines: &[usize], file: &str) -> String {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &line in lines {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => runs.push((line, line)),
+        }
+    }
+    let runs: Vec<String> = runs
+        .iter()
+        .map(|&(start, end)| {
+            if start == end { start.to_string() } else { format!("{}-{}", start, end) }
+        })
+        .collect();
+    match lines {
+        [_] => format!("line {} of {} is", runs[0], file),
+        _ => format!("lines {} of {} are", runs.join(", "), file),
+    }
+}
+
+/// Rebuilds file B by replaying the `diff_lines` of `output` onto `file_a`: lines
+/// the diff doesn't list are copied from file A, as are deleted and context lines,
+/// so only the text of inserted and new lines has to be visible. Inserted lines
+/// take the line terminator of file A's first line. The result is checked against
+/// `file_b_hash`; `proof_hash` and the receipt are not checked here.
+pub fn apply_diff(output: &DiffOutput, file_a: &str) -> Result<String> {
+    let unsupported = [
+        (output.stats.is_some(), "a stats-only proof has no lines"),
+        (output.precomputed_script, "a --no-redact-content-check proof has no line content"),
+        (!output.files.is_empty(), "directory proofs hold one diff per file"),
+        (output.base_hash.is_some(), "a merge proof doesn't diff file A into file B"),
+        (output.range_a.is_some() || output.range_b.is_some(), "only line ranges were diffed"),
+        (output.binary, "binary content was committed decoded lossily"),
+        (output.expand_tabs.is_some(), "changed lines were committed with tabs expanded"),
+        (
+            output.diff_lines.iter().any(|line| line.truncated),
+            "some long lines were committed truncated",
+        ),
+        (!output.suppressed.is_empty(), "some changed lines were left out of the proof"),
+        (
+            output.ignore_whitespace
+                || output.ignore_case
+                || output.normalize_eol
+                || output.ignore_pattern.is_some(),
+            "lines the diff treats as unchanged may differ in file B",
+        ),
+        (output.ignore_blank_lines, "blank lines added or removed were left out of the proof"),
+        (output.record_separator.is_some(), "the files were split into records rather than lines"),
+    ];
+    if let Some((_, reason)) = unsupported.iter().find(|(applies, _)| *applies) {
+        let reason = format!("Can't reconstruct file B from this proof: {}", reason);
+        return Err(ZkDiffError::Apply(reason));
+    }
+    if hash_content_with(output.hash, file_a) != output.file_a_hash {
+        let reason = "File A doesn't match file_a_hash in the proof";
+        return Err(ZkDiffError::Apply(reason.to_string()));
+    }
+
+    let mut replay = Replay {
+        lines_a: file_a.split_inclusive('\n').collect(),
+        next_a: 0,
+        written_b: 0,
+        file_b: String::new(),
+    };
+    let newline = match replay.lines_a.first() {
+        Some(line) if line.ends_with("\r\n") => "\r\n",
+        _ => "\n",
+    };
+    for line in &output.diff_lines {
+        let (a, b) = (line.line_number_a, line.line_number_b);
+        if line.operation == DiffOperation::NoNewline {
+            continue;
+        }
+        // the text file B gets for this line, if it gets one not taken from file A
+        let new_text = match line.operation {
+            DiffOperation::Insert | DiffOperation::Move if a.is_none() => {
+                Some((&line.content, &line.redacted_spans))
+            }
+            DiffOperation::Replace => Some((&line.new_content, &line.new_redacted_spans)),
+            _ => None,
+        };
+        match (a, b) {
+            // deleted, moved away, replaced or context: the lines before it are unchanged
+            (Some(a), _) => {
+                replay.copy_unchanged_until(|replay| replay.next_a == a - 1)?;
+                if line.operation == DiffOperation::Context {
+                    replay.copy_unchanged_until(|replay| replay.next_a == a)?;
+                    continue;
+                }
+                replay.next_a = a;
+            }
+            (None, Some(b)) => replay.copy_unchanged_until(|replay| replay.written_b == b - 1)?,
+            (None, None) => {
+                return Err(ZkDiffError::Apply(
+                    "Can't reconstruct file B from this proof: it was generated with --redact-line-numbers"
+                        .to_string(),
+                ));
+            }
+        }
+        if let Some((text, spans)) = new_text {
+            let (Some(text), None) = (text, spans) else {
+                return Err(ZkDiffError::Apply(format!(
+                    "Can't reconstruct file B: line {} of file B is redacted in the proof",
+                    b.unwrap_or_default()
+                )));
+            };
+            replay.file_b.push_str(text);
+            replay.file_b.push_str(newline);
+            replay.written_b += 1;
+        }
+    }
+    let len_a = replay.lines_a.len();
+    replay.copy_unchanged_until(|replay| replay.next_a == len_a)?;
+
+    let mut file_b = replay.file_b;
+    if output.no_newline_b {
+        let without = file_b.strip_suffix('\n').map(|b| b.strip_suffix('\r').unwrap_or(b));
+        let len = without.map_or(file_b.len(), str::len);
+        file_b.truncate(len);
+    } else if !file_b.is_empty() && !file_b.ends_with('\n') {
+        file_b.push_str(newline);
+    }
+    if hash_content_with(output.hash, &file_b) != output.file_b_hash {
+        let reason = "The reconstructed file B doesn't match file_b_hash in the proof";
+        return Err(ZkDiffError::Apply(reason.to_string()));
+    }
+    Ok(file_b)
+}
+
+/// Rebuilds file B from the diff of an `include_unchanged` proof alone, which
+/// commits every line of it, and checks it against `file_b_hash`; `proof_hash` and
+/// the receipt are not checked here.
+pub fn rebuild_file_b(output: &DiffOutput) -> Result<String> {
+    if !output.include_unchanged {
+        let reason = "Can't rebuild file B without file A: the proof wasn't generated with --include-unchanged";
+        return Err(ZkDiffError::Apply(reason.to_string()));
+    }
+    if !output.files.is_empty() {
+        let reason = "Can't rebuild file B from this proof: directory proofs hold one diff per file";
+        return Err(ZkDiffError::Apply(reason.to_string()));
+    }
+    let file_b = replayed_file_b(&output.diff_lines)
+        .filter(|file_b| hash_content_with(output.hash, file_b) == output.file_b_hash);
+    file_b.ok_or_else(|| {
+        let reason = "The lines committed in the proof don't rebuild file B to file_b_hash";
+        ZkDiffError::Apply(reason.to_string())
+    })
+}
+
+/// File B as far as `apply_diff` has rebuilt it, and how many lines of file A it
+/// has used up.
+struct Replay<'a> {
+    lines_a: Vec<&'a str>,
+    next_a: usize,
+    written_b: usize,
+    file_b: String,
+}
+
+impl Replay<'_> {
+    /// Copies lines of file A unchanged until `done` holds.
+    fn copy_unchanged_until(&mut self, done: impl Fn(&Self) -> bool) -> Result<()> {
+        while !done(self) {
+            let Some(line) = self.lines_a.get(self.next_a) else {
+                let reason = "Can't reconstruct file B: the proof's line numbers run past file A";
+                return Err(ZkDiffError::Apply(reason.to_string()));
+            };
+            self.file_b.push_str(line);
+            self.next_a += 1;
+            self.written_b += 1;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Console output for the CLI, filtered by the `--quiet` and `--verbose` flags.
+//! Errors and the results a command was run for are always printed. All of it goes
+//! through `print`, so it never interleaves with a progress bar.
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Normal as u8);
+
+static PROGRESS: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+thread_local! {
+    // the bar's state stays locked while suspended, so a nested suspend would deadlock
+    static SUSPENDED: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Progress over a number of files on stderr, for as long as it lives. It is only
+/// shown for two files or more, on a terminal and without `--quiet`; otherwise its
+/// methods do nothing.
+pub struct Progress(Option<ProgressBar>);
+
+impl Progress {
+    pub fn files(len: usize) -> Progress {
+        if len < 2 || !enabled(Level::Normal) || !io::stderr().is_terminal() {
+            return Progress(None);
+        }
+        let style = ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:30} {pos}/{len} files, ETA {eta}  {wide_msg}",
+        )
+        .unwrap()
+        .progress_chars("=> ");
+        let bar = ProgressBar::new(len as u64).with_style(style);
+        bar.enable_steady_tick(Duration::from_millis(200));
+        *PROGRESS.lock().unwrap() = Some(bar.clone());
+        Progress(Some(bar))
+    }
+
+    /// Names the file being worked on.
+    pub fn start(&self, name: &str) {
+        if let Some(bar) = &self.0 {
+            bar.set_message(name.to_string());
+        }
+    }
+
+    /// Counts the file being worked on as done.
+    pub fn finish_one(&self) {
+        if let Some(bar) = &self.0 {
+            bar.inc(1);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = self.0.take() {
+            *PROGRESS.lock().unwrap() = None;
+            bar.finish_and_clear();
+        }
+    }
+}
+
+/// Runs `f` with the progress bar, if one is shown, cleared from the terminal and
+/// not redrawn until it returns.
+pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    let bar = PROGRESS.lock().unwrap().clone();
+    match bar {
+        Some(bar) if !SUSPENDED.get() => bar.suspend(|| {
+            SUSPENDED.set(true);
+            let result = f();
+            SUSPENDED.set(false);
+            result
+        }),
+        _ => f(),
+    }
+}
+
+/// Prints `args` and a newline to stdout, above the progress bar if one is shown.
+pub fn print(args: fmt::Arguments) {
+    suspend(|| println!("{}", args));
+}
+
+/// Results and errors, printed whatever the level.
+macro_rules! out {
+    () => {
+        $crate::log::print(format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::log::print(format_args!($($arg)*))
+    };
+}
+
+/// Progress and details, hidden by `--quiet`.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Normal) {
+            $crate::log::out!($($arg)*);
+        }
+    };
+}
+
+/// Extra diagnostics shown only with `--verbose`.
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Verbose) {
+            $crate::log::out!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use info;
+pub(crate) use out;
+pub(crate) use verbose;
@@ -1,71 +1,215 @@
 use anyhow::Result;
-use clap::{Arg, Command};
-use risc0_zkvm::{default_prover, ExecutorEnv, Receipt};
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use clap::{Arg, ArgAction, Command};
+use regex::Regex;
+use risc0_zkvm::{
+    default_executor, default_prover, BonsaiProver, ExecutorEnv, ExternalProver, ProveInfo, Prover,
+};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 use methods::{METHOD_ELF, METHOD_ID};
-use zerocopy::IntoBytes;
+use zkdiff::{
+    append_mismatch, apply_diff, comparison_keys, diff_stats, edit_distance, expand_line_dict,
+    hash_bytes, hash_bytes_with, ignore_matched_lines, merkle_root_of_leaves, method_id_hex,
+    myers_diff, parse_redaction_ranges, path_matches, proof_file_schema, proof_hash_mismatch,
+    rebuild_file_b, records, redaction_range_gaps, redaction_range_problems, similarity,
+    tab_expanded, tree_hash, verify_proof_file, Algorithm, DiffInput, DiffLine, DiffOperation,
+    DiffOptions, DiffOutput, EditOp, FileInput, FileStatus, Granularity, HashAlgorithm, HunkAnchors,
+    IgnoredLines, LengthUnit, LineRange, MergeBase, MergeStatus, PatternRedaction, ProofFile,
+    ProofFormat, ProofMetrics, ReceiptStatus, RedactionRange, ScriptInput, SyncAnchors, Tokenizer,
+    ZkDiffError, MAX_TOTAL_LINES, ZKDIFF_VERSION,
+};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct DiffInput {
-    file_a_hash: [u8; 32],
-    file_b_hash: [u8; 32],
-    file_a_content: String,
-    file_b_content: String,
-    redaction_ranges: Vec<RedactionRange>,
+mod log;
+mod render;
+
+use log::{info, out, verbose, Level, Progress};
+use render::Colors;
+
+// exit codes for verify, so scripts can tell failures apart; any other error exits with
+// EXIT_ERROR
+const EXIT_OK: i32 = 0;
+const EXIT_ERROR: i32 = 1;
+const EXIT_METHOD_ID_MISMATCH: i32 = 2;
+const EXIT_RECEIPT_INVALID: i32 = 3;
+const EXIT_NO_RECEIPT: i32 = 4;
+const EXIT_REDACTION_LEAK: i32 = 5;
+const EXIT_PROOF_HASH_MISMATCH: i32 = 6;
+const EXIT_JOURNAL_MISMATCH: i32 = 7;
+
+/// How often the time spent so far is printed while a proof is generated.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(15);
+
+fn read_stdin() -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    io::stdin().read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Reads an input file, stdin for `-` or a git blob for `git:<rev>:<path>`. Files
+/// named `*.gz` or `*.zst` are decompressed, so hashes and diff are over what they
+/// hold rather than over the compressed bytes.
+fn read_input(path: &str, max_bytes: usize) -> Result<Vec<u8>> {
+    let bytes = if path == "-" {
+        return read_stdin();
+    } else if let Some(spec) = path.strip_prefix("git:") {
+        read_git_blob(spec)?
+    } else {
+        fs::read(path)?
+    };
+    decompressed(path, bytes, max_bytes)
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct RedactionRange {
-    start_line: usize,
-    end_line: usize,
-    operation: DiffOperation,
+/// `bytes` decompressed by the format the extension of `path` names, or as they are
+/// for any other path. Decompression stops past `max_bytes`, so a small archive
+/// can't expand to exhaust memory.
+fn decompressed(path: &str, bytes: Vec<u8>, max_bytes: usize) -> Result<Vec<u8>> {
+    let (format, decoder): (&str, Box<dyn Read + '_>) = if path.ends_with(".gz") {
+        ("gzip", Box::new(flate2::read::MultiGzDecoder::new(&bytes[..])))
+    } else if path.ends_with(".zst") {
+        ("zstd", Box::new(zstd::stream::read::Decoder::new(&bytes[..])?))
+    } else {
+        return Ok(bytes);
+    };
+    let mut content = Vec::new();
+    if let Err(e) = decoder.take((max_bytes as u64).saturating_add(1)).read_to_end(&mut content) {
+        anyhow::bail!("{} isn't valid {}: {}", path, format, e);
+    }
+    if content.len() > max_bytes {
+        anyhow::bail!("{} decompresses to over the --max-bytes limit of {}", path, max_bytes);
+    }
+    info!("Decompressed {} ({}): {} -> {} bytes", path, format, bytes.len(), content.len());
+    Ok(content)
+}
+
+/// Text of a file read as bytes: its lossy decoding in binary mode, otherwise the
+/// bytes themselves, which must be valid UTF-8.
+fn decode_text(name: &str, bytes: &[u8], binary: bool) -> Result<String> {
+    if binary {
+        return Ok(String::from_utf8_lossy(bytes).into_owned());
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok(text.to_string()),
+        Err(e) => anyhow::bail!(
+            "{} is not valid UTF-8 (invalid byte at offset {}); use --binary to diff it as raw bytes",
+            name,
+            e.valid_up_to()
+        ),
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-enum DiffOperation {
-    Insert,
-    Delete,
-    Replace,
+/// Reads a `<rev>:<path>` blob from the git repository in the current directory.
+fn read_git_blob(spec: &str) -> Result<Vec<u8>> {
+    if !spec.contains(':') {
+        anyhow::bail!("Invalid git spec {:?}, expected git:<rev>:<path>", spec);
+    }
+    let output = std::process::Command::new("git")
+        .args(["cat-file", "blob", spec])
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git cat-file blob {} failed: {}",
+            spec,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct DiffOutput {
-    file_a_hash: [u8; 32],
-    file_b_hash: [u8; 32],
-    diff_lines: Vec<DiffLine>,
-    proof_hash: [u8; 32],
+/// Name of input `side`, "a" or "b": the path given with `--file-a` or `--file-b`,
+/// or the option itself for `--text-a` and `--text-b`.
+fn input_name(matches: &clap::ArgMatches, side: &str) -> String {
+    if matches.contains_id(&format!("text_{}", side)) {
+        format!("--text-{}", side)
+    } else {
+        matches.get_one::<String>(&format!("file_{}", side)).unwrap().clone()
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct DiffLine {
-    line_number_a: Option<usize>,
-    line_number_b: Option<usize>,
-    operation: DiffOperation,
-    content: Option<String>,
-    redacted_length: Option<usize>,
+/// The text given with the option `name` names, if it is `--text-a` or `--text-b`.
+fn text_input<'a>(matches: &'a clap::ArgMatches, name: &str) -> Option<&'a String> {
+    match name {
+        "--text-a" => matches.get_one::<String>("text_a"),
+        "--text-b" => matches.get_one::<String>("text_b"),
+        _ => None,
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct ProofFile {
-    verified: bool,
-    output: DiffOutput,
-    method_id: String,
-    proof_generated: bool,
-    receipt: Option<serde_json::Value>,
+/// `text` given with the option `name`, with `\n`, `\r`, `\t`, `\0`, `\\` and `\xHH`
+/// for an ASCII character replaced by what they stand for. Any other backslash is
+/// an error, so that a typo doesn't silently end up in the hashed content.
+fn unescape(text: &str, name: &str) -> Result<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('0') => unescaped.push('\0'),
+            Some('\\') => unescaped.push('\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte @ 0..=0x7f) if hex.len() == 2 && !hex.starts_with('+') => {
+                        unescaped.push(byte as char)
+                    }
+                    _ => anyhow::bail!("Invalid escape \\x{} in {}, expected \\x00 to \\x7f", hex, name),
+                }
+            }
+            Some(other) => anyhow::bail!("Invalid escape \\{} in {}; use \\\\ for a backslash", other, name),
+            None => anyhow::bail!("{} ends with a lone backslash; use \\\\ for a backslash", name),
+        }
+    }
+    Ok(unescaped)
 }
 
-fn hash_content(content: &str) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(content.as_bytes());
-    hasher.finalize().into()
+/// Splits stdin at the first line equal to `delimiter`. Everything before that line,
+/// including its trailing newline, is file A and everything after it is file B.
+fn split_stdin(input: &[u8], delimiter: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut offset = 0;
+    for line in input.split_inclusive(|&b| b == b'\n') {
+        if line.strip_suffix(b"\n").unwrap_or(line) == delimiter.as_bytes() {
+            let file_a = input[..offset].to_vec();
+            let file_b = input[offset + line.len()..].to_vec();
+            return Ok((file_a, file_b));
+        }
+        offset += line.len();
+    }
+    anyhow::bail!("Delimiter line {:?} not found on stdin", delimiter)
 }
 
 fn main() -> Result<()> {
     let matches = Command::new("zkdiff")
         .about("Zero-knowledge diff implementation using Myers algorithm")
         .subcommand_required(true)
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Print only errors and the resulting file")
+                .global(true)
+                .conflicts_with("verbose")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("Also print cycle breakdown and receipt size")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
         .subcommand(
             Command::new("generate")
                 .about("Generate a zero-knowledge diff proof")
@@ -74,32 +218,583 @@ fn main() -> Result<()> {
                         .short('a')
                         .long("file-a")
                         .value_name("FILE")
-                        .help("First file to compare")
-                        .required(true),
+                        .help("First file to compare, - for stdin, or git:<rev>:<path>")
+                        .required_unless_present_any([
+                            "dir_a",
+                            "text_a",
+                            "no_redact_content_check",
+                            "base",
+                        ])
+                        .conflicts_with("dir_a"),
                 )
                 .arg(
                     Arg::new("file_b")
                         .short('b')
                         .long("file-b")
                         .value_name("FILE")
-                        .help("Second file to compare")
-                        .required(true),
+                        .help("Second file to compare, - for stdin, or git:<rev>:<path>")
+                        .required_unless_present_any([
+                            "dir_b",
+                            "text_b",
+                            "no_redact_content_check",
+                            "base",
+                        ])
+                        .conflicts_with("dir_b"),
+                )
+                .arg(
+                    Arg::new("text_a")
+                        .long("text-a")
+                        .value_name("TEXT")
+                        .help("Text to use as file A instead of a file, with \\n, \\r, \\t, \\0, \\xHH and \\\\ escapes decoded")
+                        .conflicts_with_all(["file_a", "dir_a"]),
+                )
+                .arg(
+                    Arg::new("text_b")
+                        .long("text-b")
+                        .value_name("TEXT")
+                        .help("Text to use as file B instead of a file, with the escapes of --text-a")
+                        .conflicts_with_all(["file_b", "dir_b"]),
+                )
+                .arg(
+                    Arg::new("dir_a")
+                        .long("dir-a")
+                        .value_name("DIR")
+                        .help("First directory to compare, file by file")
+                        .requires("dir_b"),
+                )
+                .arg(
+                    Arg::new("dir_b")
+                        .long("dir-b")
+                        .value_name("DIR")
+                        .help("Second directory to compare, file by file")
+                        .requires("dir_a"),
+                )
+                .arg(
+                    Arg::new("no_redact_content_check")
+                        .long("no-redact-content-check")
+                        .value_name("SCRIPT")
+                        .help("Prove a precomputed edit script against line hashes from SCRIPT, without the file contents")
+                        .conflicts_with_all([
+                            "file_a",
+                            "file_b",
+                            "text_a",
+                            "text_b",
+                            "dir_a",
+                            "range_a",
+                            "range_b",
+                            "redact",
+                            "redact_file",
+                            "profile",
+                            "redact_pattern",
+                            "hunk_pattern",
+                            "ignore_matching",
+                            "anchor",
+                            "binary",
+                            "swap",
+                        ]),
+                )
+                .arg(
+                    Arg::new("base")
+                        .long("base")
+                        .value_name("FILE")
+                        .help("Common ancestor for a three-way merge of --ours and --theirs")
+                        .requires_all(["ours", "theirs"])
+                        .conflicts_with_all([
+                            "file_a",
+                            "file_b",
+                            "text_a",
+                            "text_b",
+                            "dir_a",
+                            "no_redact_content_check",
+                            "range_a",
+                            "range_b",
+                            "redact",
+                            "redact_file",
+                            "profile",
+                            "redact_pattern",
+                            "ignore_matching",
+                            "anchor",
+                            "binary",
+                            "swap",
+                            "stats_only",
+                        ]),
+                )
+                .arg(
+                    Arg::new("ours")
+                        .long("ours")
+                        .value_name("FILE")
+                        .help("Our side of a three-way merge, diffed as file A")
+                        .requires("base"),
+                )
+                .arg(
+                    Arg::new("theirs")
+                        .long("theirs")
+                        .value_name("FILE")
+                        .help("Their side of a three-way merge, diffed as file B")
+                        .requires("base"),
+                )
+                .arg(
+                    Arg::new("swap")
+                        .long("swap")
+                        .help("Exchange file A and file B, or the two directories, before diffing")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("range_a")
+                        .long("range-a")
+                        .value_name("START-END")
+                        .help("Diff only these lines of file A; the whole file is still hashed")
+                        .conflicts_with("dir_a"),
+                )
+                .arg(
+                    Arg::new("range_b")
+                        .long("range-b")
+                        .value_name("START-END")
+                        .help("Diff only these lines of file B; the whole file is still hashed")
+                        .conflicts_with("dir_a"),
                 )
                 .arg(
                     Arg::new("redact")
                         .short('r')
                         .long("redact")
                         .value_name("RANGES")
-                        .help("Redaction ranges in format: operation:start-end,operation:start-end")
+                        .help("Redaction ranges in format: operation:start-end[:col-col],operation:start-end, the operation insert, delete, replace, context or any")
                         .default_value(""),
                 )
+                .arg(
+                    Arg::new("redact_file")
+                        .long("redact-file")
+                        .value_name("FILE")
+                        .help("JSON file with a list of redaction ranges, merged with --redact")
+                        .conflicts_with("dir_a"),
+                )
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .value_name("NAME")
+                        .help("Apply the named set of redaction ranges from --profile-file, merged with --redact")
+                        .conflicts_with("dir_a"),
+                )
+                .arg(
+                    Arg::new("profile_file")
+                        .long("profile-file")
+                        .value_name("FILE")
+                        .help("JSON file mapping profile names to lists of redaction ranges")
+                        .default_value("zkdiff-profiles.json"),
+                )
+                .arg(
+                    Arg::new("lenient_redaction")
+                        .long("lenient-redaction")
+                        .help("Warn instead of failing when a redaction range is outside its file")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("suppress")
+                        .long("suppress")
+                        .value_name("OPERATIONS")
+                        .help("Leave these comma-separated operations out of the committed diff lines")
+                        .value_delimiter(',')
+                        .value_parser(["insert", "delete"])
+                        .conflicts_with("no_redact_content_check"),
+                )
+                .arg(
+                    Arg::new("fail_on_redaction_miss")
+                        .long("fail-on-redaction-miss")
+                        .value_name("SHA256")
+                        .help("Fail the proof unless the redacted text, each piece followed by a newline, has this SHA-256")
+                        .conflicts_with_all(["dir_a", "no_redact_content_check"]),
+                )
+                .arg(
+                    Arg::new("redact_pattern")
+                        .long("redact-pattern")
+                        .value_name("REGEX")
+                        .help("Redact every diff line whose content matches REGEX (repeatable)")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("redact_keywords")
+                        .long("redact-keywords")
+                        .value_name("WORDS")
+                        .help("Hide these comma-separated words, ignoring case, wherever a line contains them")
+                        .value_delimiter(',')
+                        .conflicts_with_all(["no_redact_content_check", "base"]),
+                )
+                .arg(
+                    Arg::new("redact_keyword_values")
+                        .long("redact-keyword-values")
+                        .help("Also hide the =value after each keyword, up to the next whitespace")
+                        .requires("redact_keywords")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("redact_all")
+                        .long("redact-all")
+                        .help("Hide the content of every line, proving only which lines changed")
+                        .conflicts_with_all(["no_redact_content_check", "base"])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("redact_path")
+                        .long("redact-path")
+                        .value_name("GLOB")
+                        .help("Hide every line of the files whose relative path matches GLOB, like secrets/** (repeatable; directory mode)")
+                        .requires("dir_a")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("redact_line_numbers")
+                        .long("redact-line-numbers")
+                        .help("Commit lines with redacted content without their line numbers")
+                        .conflicts_with_all(["merkle", "hunk_pattern", "no_redact_content_check", "base"])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("hunk_pattern")
+                        .long("hunk-pattern")
+                        .value_name("REGEX")
+                        .help("Name each hunk after the nearest preceding file A line matching REGEX, e.g. '^(fn|def|class) '"),
+                )
+                .arg(
+                    Arg::new("hunks")
+                        .long("hunks")
+                        .help("Also commit the diff lines grouped into hunks with their line spans")
+                        .conflicts_with_all(["redact_line_numbers", "stats_only"])
+                        .action(ArgAction::SetTrue),
+                )
                 .arg(
                     Arg::new("output")
                         .short('o')
                         .long("output")
                         .value_name("FILE")
-                        .help("Output file for the proof")
+                        .help("Output file for the proof, or - for stdout")
                         .default_value("zkdiff.proof"),
+                )
+                .arg(
+                    Arg::new("output_dir")
+                        .long("output-dir")
+                        .value_name("DIR")
+                        .help("Write the proof into DIR, named after the inputs and a short hash of their hashes")
+                        .conflicts_with("output"),
+                )
+                .arg(
+                    Arg::new("proof_format")
+                        .long("proof-format")
+                        .value_name("FORMAT")
+                        .help("Encoding of the proof file")
+                        .value_parser(["json", "bincode"])
+                        .default_value("json"),
+                )
+                .arg(
+                    Arg::new("canonical")
+                        .long("canonical")
+                        .help("Write the proof file reproducibly: sorted JSON keys and no proving metrics")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("receipt_out")
+                        .long("receipt-out")
+                        .value_name("FILE")
+                        .help("Also write the bare receipt, bincode-encoded as risc0 serializes it, for verifying with other tools")
+                        .conflicts_with_all(["json_only", "dev"]),
+                )
+                .arg(
+                    Arg::new("summary_format")
+                        .long("summary-format")
+                        .value_name("FORMAT")
+                        .help("Format of the summary printed after generating")
+                        .value_parser(["text", "json"])
+                        .default_value("text"),
+                )
+                .arg(
+                    Arg::new("stdin_delimiter")
+                        .long("stdin-delimiter")
+                        .value_name("LINE")
+                        .help("Line separating file A from file B when both are read from stdin")
+                        .default_value("--- zkdiff ---"),
+                )
+                .arg(
+                    Arg::new("algorithm")
+                        .long("algorithm")
+                        .value_name("ALGORITHM")
+                        .help("Algorithm used to diff lines")
+                        .value_parser(["myers", "patience", "histogram"])
+                        .default_value("myers"),
+                )
+                .arg(
+                    Arg::new("tokenizer")
+                        .long("tokenizer")
+                        .value_name("TOKENIZER")
+                        .help("Diff the files' token streams instead of their lines, showing the lines of changed tokens")
+                        .value_parser(["rust", "generic"])
+                        .conflicts_with_all(["no_redact_content_check", "append_only"]),
+                )
+                .arg(
+                    Arg::new("hash")
+                        .long("hash")
+                        .value_name("HASH")
+                        .help("Hash function of the file hashes and the proof hash")
+                        .value_parser(["sha256", "blake3"])
+                        .default_value("sha256"),
+                )
+                .arg(
+                    Arg::new("granularity")
+                        .long("granularity")
+                        .value_name("LEVEL")
+                        .help("Granularity of the breakdown recorded for replaced lines")
+                        .value_parser(["line", "word", "char"])
+                        .default_value("line"),
+                )
+                .arg(
+                    Arg::new("record_separator")
+                        .long("record-separator")
+                        .value_name("BYTE")
+                        .help("Split the files into records ending in BYTE instead of into lines, e.g. \\0 or \\x1e, with the escapes of --text-a")
+                        .conflicts_with_all([
+                            "no_redact_content_check",
+                            "normalize_eol",
+                            "include_unchanged",
+                        ]),
+                )
+                .arg(
+                    Arg::new("normalize_eol")
+                        .long("normalize-eol")
+                        .help("Treat CRLF and LF line endings as equal when diffing")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("ignore_whitespace")
+                        .short('w')
+                        .long("ignore-whitespace")
+                        .help("Ignore leading, trailing and repeated whitespace when diffing")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("ignore_case")
+                        .short('i')
+                        .long("ignore-case")
+                        .help("Ignore ASCII case differences when diffing")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("ignore_blank_lines")
+                        .long("ignore-blank-lines")
+                        .help("Leave blank lines out of the comparison, and out of the diff where they don't pair up")
+                        .conflicts_with("append_only")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("ignore_matching")
+                        .long("ignore-matching")
+                        .value_name("REGEX")
+                        .help("Compare lines matching REGEX as equal to each other, e.g. timestamps or build IDs; their content is still committed"),
+                )
+                .arg(
+                    Arg::new("anchor")
+                        .long("anchor")
+                        .value_name("REGEX")
+                        .help("Align equal lines matching REGEX, e.g. section headers, and diff the segments between them on their own")
+                        .conflicts_with("append_only"),
+                )
+                .arg(
+                    Arg::new("expand_tabs")
+                        .long("expand-tabs")
+                        .value_name("N")
+                        .help("Expand tabs to stops every N columns before diffing; the proof holds the expanded text")
+                        .value_parser(clap::value_parser!(usize))
+                        .conflicts_with("no_redact_content_check"),
+                )
+                .arg(
+                    Arg::new("max_line_len")
+                        .long("max-line-len")
+                        .value_name("N")
+                        .help("Commit at most N bytes of each line, recording the full length of longer ones; lines are still compared in full")
+                        .value_parser(clap::value_parser!(usize))
+                        .conflicts_with("no_redact_content_check"),
+                )
+                .arg(
+                    Arg::new("context")
+                        .short('U')
+                        .long("context")
+                        .value_name("N")
+                        .help("Include up to N unchanged lines around each change")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::new("merge_gap")
+                        .long("merge-gap")
+                        .value_name("N")
+                        .help("Merge hunks that at most N unchanged lines separate, showing those lines as context, like git's --inter-hunk-context")
+                        .value_parser(clap::value_parser!(usize))
+                        .conflicts_with_all(["no_redact_content_check", "include_unchanged"]),
+                )
+                .arg(
+                    Arg::new("include_unchanged")
+                        .long("include-unchanged")
+                        .help("Commit every unchanged line too, so the proof holds all of file B and `apply` can rebuild it without file A")
+                        .conflicts_with_all([
+                            "no_redact_content_check",
+                            "base",
+                            "range_a",
+                            "range_b",
+                            "suppress",
+                            "stats_only",
+                            "binary",
+                            "normalize_eol",
+                            "ignore_whitespace",
+                            "ignore_case",
+                            "ignore_blank_lines",
+                            "ignore_matching",
+                            "expand_tabs",
+                            "max_line_len",
+                            "redact_all",
+                        ])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("detect_moves")
+                        .long("detect-moves")
+                        .help("Report blocks deleted in one place and inserted unchanged elsewhere as moves")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("binary")
+                        .long("binary")
+                        .help("Hash files as raw bytes and diff them decoded lossily, for non-UTF-8 input")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("merkle")
+                        .long("merkle")
+                        .help("Commit a Merkle root of each file's lines and an inclusion path for every line shown")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("length_unit")
+                        .long("length-unit")
+                        .value_name("UNIT")
+                        .help("Unit of the committed length of redacted text")
+                        .value_parser(["bytes", "chars", "graphemes"])
+                        .default_value("bytes"),
+                )
+                .arg(
+                    Arg::new("redaction_salt")
+                        .long("redaction-salt")
+                        .value_name("HEX")
+                        .help("Commit a tag of SHA256(salt || text) for redacted text instead of its length"),
+                )
+                .arg(
+                    Arg::new("append_only")
+                        .long("append-only")
+                        .help("Prove file B is file A with lines appended, committing only the appended lines")
+                        .conflicts_with_all(["no_redact_content_check", "base", "range_a", "range_b"])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("stats_only")
+                        .long("stats-only")
+                        .help("Commit only the file hashes and change counts, no line content or numbers")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("structure_only")
+                        .long("structure-only")
+                        .help("Commit only which files were added, removed or modified and their hashes, without diffing them (directory mode)")
+                        .requires("dir_a")
+                        .conflicts_with_all([
+                            "stats_only",
+                            "redact_pattern",
+                            "redact_keywords",
+                            "redact_all",
+                            "redact_path",
+                            "include_unchanged",
+                            "append_only",
+                            "ignore_matching",
+                            "anchor",
+                            "hunk_pattern",
+                            "hunks",
+                            "merkle",
+                        ])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dedup_lines")
+                        .long("dedup-lines")
+                        .help("Commit each distinct line content once, with the lines pointing into a dictionary, to shrink the proof of repetitive files")
+                        .conflicts_with_all(["stats_only", "structure_only", "no_redact_content_check"])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("max_lines")
+                        .long("max-lines")
+                        .value_name("N")
+                        .help("Refuse to prove a file with more than N lines")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("100000"),
+                )
+                .arg(
+                    Arg::new("max_bytes")
+                        .long("max-bytes")
+                        .value_name("N")
+                        .help("Refuse to prove a file larger than N bytes")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10485760"),
+                )
+                .arg(
+                    Arg::new("min_similarity")
+                        .long("min-similarity")
+                        .value_name("RATIO")
+                        .help("Refuse to prove files less similar than RATIO, from 0 to 1, by a quick diff on the host")
+                        .value_parser(clap::value_parser!(f32)),
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("DURATION")
+                        .help("Give up proving after DURATION, like 300s, 5m or 1h, without writing a proof")
+                        .conflicts_with_all(["json_only", "dev"]),
+                )
+                .arg(
+                    Arg::new("prover")
+                        .long("prover")
+                        .value_name("BACKEND")
+                        .help("Prove locally on the CPU or remotely on Bonsai, rather than as risc0's environment variables choose")
+                        .value_parser(["local", "bonsai"])
+                        .conflicts_with_all(["json_only", "dev", "dry_run"]),
+                )
+                .arg(
+                    Arg::new("json_only")
+                        .long("json-only")
+                        .value_name("FILE")
+                        .help("Run the guest without proving and write only the diff output as JSON")
+                        .conflicts_with_all(["output", "output_dir", "proof_format"]),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .long("dry-run")
+                        .help("Execute the guest without proving and print the lines the proof would commit, redactions applied, without writing anything")
+                        .conflicts_with_all(["json_only", "dev", "receipt_out"])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dev")
+                        .long("dev")
+                        .help("Run the guest without proving and write a proof file with no receipt, for testing")
+                        .conflicts_with("json_only")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("expect_id")
+                        .long("expect-id")
+                        .value_name("HEX")
+                        .help("Abort unless this build's guest image ID, as `zkdiff id` prints it, is HEX"),
+                )
+                .arg(
+                    Arg::new("status_fd")
+                        .long("status-fd")
+                        .value_name("FD")
+                        .help("Also write a one-line JSON status to this open file descriptor (Unix only)")
+                        .value_parser(clap::value_parser!(i32).range(1..)),
                 ),
         )
         .subcommand(
@@ -108,18 +803,226 @@ fn main() -> Result<()> {
                 .arg(
                     Arg::new("proof_file")
                         .value_name("PROOF_FILE")
-                        .help("Proof file to verify")
+                        .help("Proof files to verify, each checked on its own, or - for stdin")
+                        .num_args(1..)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format for the verified diff")
+                        .value_parser(["summary", "unified", "side-by-side", "merge", "names-only"])
+                        .default_value("summary"),
+                )
+                .arg(
+                    Arg::new("width")
+                        .long("width")
+                        .value_name("N")
+                        .help("Width of each column with --format side-by-side")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("60"),
+                )
+                .arg(
+                    Arg::new("color")
+                        .long("color")
+                        .value_name("WHEN")
+                        .help("Color the rendered diff; auto colors a terminal unless NO_COLOR is set")
+                        .value_parser(["auto", "always", "never"])
+                        .default_value("auto"),
+                )
+                .arg(
+                    Arg::new("no_pager")
+                        .long("no-pager")
+                        .help("Print the rendered diff directly instead of through $PAGER when it's longer than the terminal")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("emit_patch")
+                        .long("emit-patch")
+                        .value_name("FILE")
+                        .help("Write the visible hunks of a verified diff as a patch git can apply"),
+                )
+                .arg(
+                    Arg::new("patch_path")
+                        .long("patch-path")
+                        .value_name("PATH")
+                        .help("File name used in the patch headers of a single-file proof")
+                        .default_value("file"),
+                )
+                .arg(
+                    Arg::new("dump_receipt")
+                        .long("dump-receipt")
+                        .value_name("FILE")
+                        .help("Write the verified receipt on its own, bincode-encoded as risc0 serializes it")
+                        .conflicts_with("skip_crypto"),
+                )
+                .arg(
+                    Arg::new("line_numbers")
+                        .long("line-numbers")
+                        .value_name("WHEN")
+                        .help("Show line numbers in the rendered diff; off also drops the ranges from unified hunk headers")
+                        .value_parser(["on", "off"])
+                        .default_value("on"),
+                )
+                .arg(
+                    Arg::new("expand_redactions")
+                        .long("expand-redactions")
+                        .help("Show every redacted line on its own instead of one line per run of them")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("only")
+                        .long("only")
+                        .value_name("OPERATIONS")
+                        .help("Render only lines with these comma-separated operations")
+                        .value_delimiter(',')
+                        .value_parser(["insert", "delete", "replace", "move", "context"])
+                        .conflicts_with("emit_patch"),
+                )
+                .arg(
+                    Arg::new("reverse_render")
+                        .long("reverse-render")
+                        .help("Render the diff as if file A and file B were swapped")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("skip_crypto")
+                        .long("skip-crypto")
+                        .help("Only check the proof_hash of a --json-only output or --dev proof; no receipt is verified")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("expect_id")
+                        .long("expect-id")
+                        .value_name("HEX")
+                        .help("Abort unless this build's guest image ID, as `zkdiff id` prints it, is HEX"),
+                )
+                .arg(
+                    Arg::new("status_fd")
+                        .long("status-fd")
+                        .value_name("FD")
+                        .help("Also write a one-line JSON status to this open file descriptor (Unix only)")
+                        .value_parser(clap::value_parser!(i32).range(1..)),
+                ),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("Show what a proof file contains without verifying it")
+                .arg(
+                    Arg::new("proof_file")
+                        .value_name("PROOF_FILE")
+                        .help("Proof file to inspect")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("apply")
+                .about("Rebuild file B from file A and a proof of their diff")
+                .arg(
+                    Arg::new("proof_file")
+                        .value_name("PROOF_FILE")
+                        .help("Proof file, or a --json-only output, of the diff from file A to file B")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("file_a")
+                        .short('a')
+                        .long("file-a")
+                        .value_name("FILE")
+                        .help("File A the proof was generated from, - for stdin, or git:<rev>:<path>; not needed for --include-unchanged proofs"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Where to write the rebuilt file B")
                         .required(true),
                 ),
         )
+        .subcommand(
+            Command::new("id")
+                .about("Print the image ID of the guest program built into this binary"),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Print the JSON Schema of the JSON proof file format"),
+        )
         .get_matches();
 
+    if matches.get_flag("quiet") {
+        log::set_level(Level::Quiet);
+    } else if matches.get_flag("verbose") {
+        log::set_level(Level::Verbose);
+    }
+
     match matches.subcommand() {
         Some(("generate", sub_matches)) => {
-            generate_proof(sub_matches)?;
+            let status_fd = status_fd(sub_matches)?;
+            let result = generate_proof(sub_matches);
+            if let Some(fd) = status_fd {
+                let status = match &result {
+                    Ok(summary) => {
+                        let mut status = serde_json::json!({ "ok": true, "command": "generate" });
+                        if let (Some(status), Some(summary)) =
+                            (status.as_object_mut(), summary.as_object())
+                        {
+                            status.extend(summary.clone());
+                        }
+                        status
+                    }
+                    Err(e) => error_status("generate", e),
+                };
+                write_status(fd, &status)?;
+            }
+            result?;
         }
         Some(("verify", sub_matches)) => {
-            verify_proof(sub_matches)?;
+            let status_fd = status_fd(sub_matches)?;
+            let result = verify_proof(sub_matches);
+            if let Some(fd) = status_fd {
+                let status = match &result {
+                    Ok(results) => {
+                        let proofs: Vec<_> = results
+                            .iter()
+                            .map(|(path, code)| {
+                                serde_json::json!({
+                                    "file": path,
+                                    "ok": *code == EXIT_OK,
+                                    "exit_code": code,
+                                    "reason": (*code != EXIT_OK).then(|| failure_reason(*code)),
+                                })
+                            })
+                            .collect();
+                        let code = first_failure(results);
+                        serde_json::json!({
+                            "ok": code == EXIT_OK,
+                            "command": "verify",
+                            "exit_code": code,
+                            "proofs": proofs,
+                        })
+                    }
+                    Err(e) => error_status("verify", e),
+                };
+                write_status(fd, &status)?;
+            }
+            let code = first_failure(&result?);
+            if code != EXIT_OK {
+                std::process::exit(code);
+            }
+        }
+        Some(("info", sub_matches)) => {
+            proof_info(sub_matches)?;
+        }
+        Some(("apply", sub_matches)) => {
+            apply_proof(sub_matches)?;
+        }
+        Some(("id", _)) => {
+            out!("{}", method_id_hex(METHOD_ID));
+        }
+        Some(("schema", _)) => {
+            out!("{}", serde_json::to_string_pretty(&proof_file_schema())?);
         }
         _ => unreachable!(),
     }
@@ -127,175 +1030,1732 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn generate_proof(matches: &clap::ArgMatches) -> Result<()> {
-    let file_a_path = matches.get_one::<String>("file_a").unwrap();
-    let file_b_path = matches.get_one::<String>("file_b").unwrap();
+/// Generates the proof and returns its `--summary-format json` summary, which
+/// `--status-fd` also reports.
+fn generate_proof(matches: &clap::ArgMatches) -> Result<serde_json::Value> {
+    check_expected_id(matches)?;
+    let timeout = matches.get_one::<String>("timeout").map(|t| parse_timeout(t)).transpose()?;
+    let backend = matches.get_one::<String>("prover").map(|b| backend(b)).transpose()?;
     let redact_str = matches.get_one::<String>("redact").unwrap();
-    let output_path = matches.get_one::<String>("output").unwrap();
-
-    let file_a_content = fs::read_to_string(file_a_path)?;
-    let file_b_content = fs::read_to_string(file_b_path)?;
-
-    let file_a_hash = hash_content(&file_a_content);
-    let file_b_hash = hash_content(&file_b_content);
-
-    let redaction_ranges = parse_redaction_ranges(redact_str)?;
-
-    let input = DiffInput {
-        file_a_hash,
-        file_b_hash,
-        file_a_content,
-        file_b_content,
-        redaction_ranges,
+    let stdin_delimiter = matches.get_one::<String>("stdin_delimiter").unwrap();
+    let max_bytes = *matches.get_one::<usize>("max_bytes").unwrap();
+    let json_summary = matches.get_one::<String>("summary_format").unwrap() == "json";
+    let proof_to_stdout = matches.get_one::<String>("output").unwrap() == "-";
+    if json_summary && proof_to_stdout {
+        anyhow::bail!("-o - writes the proof to stdout, which leaves no room for --summary-format json");
+    }
+    if json_summary || proof_to_stdout {
+        // stdout carries nothing but the JSON object or the proof
+        log::set_level(Level::Quiet);
+    }
+    let proof_format = match matches.get_one::<String>("proof_format").unwrap().as_str() {
+        "bincode" => ProofFormat::Bincode,
+        _ => ProofFormat::Json,
     };
-
-    println!("Generating zero-knowledge proof for diff...");
-    println!("File A hash: {}", hex::encode(file_a_hash));
-    println!("File B hash: {}", hex::encode(file_b_hash));
-
-    let env = ExecutorEnv::builder()
+    let canonical = matches.get_flag("canonical");
+    let encode = |proof_file: &ProofFile| {
+        if canonical {
+            proof_file.encode_canonical(proof_format)
+        } else {
+            proof_file.encode(proof_format)
+        }
+    };
+    let algorithm = match matches.get_one::<String>("algorithm").unwrap().as_str() {
+        "patience" => Algorithm::Patience,
+        "histogram" => Algorithm::Histogram,
+        _ => Algorithm::Myers,
+    };
+    let granularity = match matches.get_one::<String>("granularity").unwrap().as_str() {
+        "word" => Granularity::Word,
+        "char" => Granularity::Char,
+        _ => Granularity::Line,
+    };
+    let length_unit = match matches.get_one::<String>("length_unit").unwrap().as_str() {
+        "chars" => LengthUnit::Chars,
+        "graphemes" => LengthUnit::Graphemes,
+        _ => LengthUnit::Bytes,
+    };
+    let options = DiffOptions {
+        algorithm,
+        granularity,
+        record_separator: matches
+            .get_one::<String>("record_separator")
+            .map(|separator| parse_record_separator(separator))
+            .transpose()?,
+        normalize_eol: matches.get_flag("normalize_eol"),
+        ignore_whitespace: matches.get_flag("ignore_whitespace"),
+        ignore_case: matches.get_flag("ignore_case"),
+        ignore_blank_lines: matches.get_flag("ignore_blank_lines"),
+        context: *matches.get_one::<usize>("context").unwrap(),
+        merge_gap: matches.get_one::<usize>("merge_gap").copied(),
+        include_unchanged: matches.get_flag("include_unchanged"),
+        detect_moves: matches.get_flag("detect_moves"),
+        length_unit,
+        redaction_salt: matches
+            .get_one::<String>("redaction_salt")
+            .map(|salt| parse_salt(salt))
+            .transpose()?,
+        range_a: matches.get_one::<String>("range_a").map(|r| parse_line_range(r)).transpose()?,
+        range_b: matches.get_one::<String>("range_b").map(|r| parse_line_range(r)).transpose()?,
+        merkle: matches.get_flag("merkle"),
+        binary: matches.get_flag("binary"),
+        stats_only: matches.get_flag("stats_only"),
+        structure_only: matches.get_flag("structure_only"),
+        dedup_lines: matches.get_flag("dedup_lines"),
+        expand_tabs: matches.get_one::<usize>("expand_tabs").copied(),
+        max_line_len: matches.get_one::<usize>("max_line_len").copied(),
+        append_only: matches.get_flag("append_only"),
+        hash: match matches.get_one::<String>("hash").unwrap().as_str() {
+            "blake3" => HashAlgorithm::Blake3,
+            _ => HashAlgorithm::Sha256,
+        },
+        redact_keywords: matches
+            .get_many::<String>("redact_keywords")
+            .map(|keywords| keywords.cloned().collect())
+            .unwrap_or_default(),
+        redact_keyword_values: matches.get_flag("redact_keyword_values"),
+        redact_all: matches.get_flag("redact_all"),
+        redact_paths: matches
+            .get_many::<String>("redact_path")
+            .map(|globs| globs.cloned().collect())
+            .unwrap_or_default(),
+        redact_line_numbers: matches.get_flag("redact_line_numbers"),
+        tokenizer: matches.get_one::<String>("tokenizer").map(|tokenizer| match tokenizer.as_str() {
+            "rust" => Tokenizer::Rust,
+            _ => Tokenizer::Generic,
+        }),
+        expected_redaction_hash: matches
+            .get_one::<String>("fail_on_redaction_miss")
+            .map(|hash| parse_redaction_hash(hash))
+            .transpose()?,
+        suppress: matches
+            .get_many::<String>("suppress")
+            .unwrap_or_default()
+            .map(|operation| parse_operation(operation))
+            .collect(),
+        hunks: matches.get_flag("hunks"),
+    };
+    if options.redact_keywords.iter().any(String::is_empty) {
+        anyhow::bail!("--redact-keywords has an empty keyword");
+    }
+    if options.expand_tabs == Some(0) {
+        anyhow::bail!("--expand-tabs must be at least 1");
+    }
+    if options.max_line_len == Some(0) {
+        anyhow::bail!("--max-line-len must be at least 1");
+    }
+    if options.merge_gap == Some(0) {
+        anyhow::bail!("--merge-gap must be at least 1");
+    }
+    if options.redact_all && options.granularity == Granularity::Char {
+        anyhow::bail!("--redact-all can't be combined with --granularity char, which shows the unchanged characters of redacted lines");
+    }
+    if !options.redact_paths.is_empty() && options.granularity == Granularity::Char {
+        anyhow::bail!("--redact-path can't be combined with --granularity char, which shows the unchanged characters of redacted lines");
+    }
+
+    let redact_patterns: Vec<String> = matches
+        .get_many::<String>("redact_pattern")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let hunk_pattern = matches.get_one::<String>("hunk_pattern");
+    let ignore_pattern = matches.get_one::<String>("ignore_matching");
+    let anchor_pattern = matches.get_one::<String>("anchor");
+
+    // everything after this, ranges and redactions included, sees the swapped order
+    let swap = matches.get_flag("swap");
+    let swapped = |a: &String, b: &String| -> (String, String) {
+        if swap {
+            (b.clone(), a.clone())
+        } else {
+            (a.clone(), b.clone())
+        }
+    };
+
+    let input = if let Some(script_path) = matches.get_one::<String>("no_redact_content_check") {
+        script_input(script_path, &options)?
+    } else if let Some(dir_a) = matches.get_one::<String>("dir_a") {
+        if !redact_str.is_empty() {
+            anyhow::bail!("--redact line ranges are per file and can't be used with --dir-a/--dir-b; use --redact-pattern");
+        }
+        let (dir_a, dir_b) = swapped(dir_a, matches.get_one::<String>("dir_b").unwrap());
+        let (dir_a, dir_b) = (Path::new(&dir_a), Path::new(&dir_b));
+        dir_input(
+            dir_a,
+            dir_b,
+            &redact_patterns,
+            hunk_pattern,
+            ignore_pattern,
+            anchor_pattern,
+            &options,
+        )?
+    } else {
+        let merge_base_path = matches.get_one::<String>("base");
+        let (file_a_path, file_b_path) = match merge_base_path {
+            Some(_) => (
+                matches.get_one::<String>("ours").unwrap().clone(),
+                matches.get_one::<String>("theirs").unwrap().clone(),
+            ),
+            None => swapped(&input_name(matches, "a"), &input_name(matches, "b")),
+        };
+        let (file_a_bytes, file_b_bytes) = if file_a_path == "-" && file_b_path == "-" {
+            let (first, second) = split_stdin(&read_stdin()?, stdin_delimiter)?;
+            if swap {
+                (second, first)
+            } else {
+                (first, second)
+            }
+        } else {
+            let read = |path: &str| match text_input(matches, path) {
+                Some(text) => unescape(text, path).map(String::into_bytes),
+                None => read_input(path, max_bytes),
+            };
+            (read(&file_a_path)?, read(&file_b_path)?)
+        };
+        let file_a_content = decode_text(&file_a_path, &file_a_bytes, options.binary)?;
+        let file_b_content = decode_text(&file_b_path, &file_b_bytes, options.binary)?;
+        let diffed_a = as_diffed(&file_a_content, &options);
+        let diffed_b = as_diffed(&file_b_content, &options);
+        let separator = options.record_separator;
+        let pattern_redactions =
+            match_redaction_patterns(&redact_patterns, &diffed_a, &diffed_b, separator)?;
+        let hunk_anchors =
+            hunk_pattern.map(|p| match_hunk_pattern(p, &diffed_a, separator)).transpose()?;
+        let ignored_lines = ignore_pattern
+            .map(|p| match_ignore_pattern(p, &diffed_a, &diffed_b, separator))
+            .transpose()?;
+        let sync_anchors = anchor_pattern
+            .map(|p| match_anchor_pattern(p, &diffed_a, &diffed_b, separator))
+            .transpose()?;
+        let mut redaction_ranges = parse_redaction_ranges(redact_str)?;
+        if let Some(redact_file) = matches.get_one::<String>("redact_file") {
+            redaction_ranges.extend(read_redaction_file(redact_file)?);
+        }
+        let redaction_profile = matches.get_one::<String>("profile").cloned();
+        if let Some(profile) = &redaction_profile {
+            let profile_file = matches.get_one::<String>("profile_file").unwrap();
+            redaction_ranges.extend(read_redaction_profile(profile_file, profile)?);
+        }
+        for (name, range, content) in [
+            ("file A", options.range_a, &file_a_content),
+            ("file B", options.range_b, &file_b_content),
+        ] {
+            let lines = records(content, options.record_separator).len();
+            if let Some(range) = range.filter(|range| range.end > lines) {
+                anyhow::bail!(
+                    "Line range {}-{} is outside {}, which has {} lines",
+                    range.start,
+                    range.end,
+                    name,
+                    lines
+                );
+            }
+        }
+        let problems = redaction_range_problems(
+            &redaction_ranges,
+            records(&file_a_content, options.record_separator).len(),
+            records(&file_b_content, options.record_separator).len(),
+        );
+        if matches.get_flag("lenient_redaction") {
+            for problem in &problems {
+                info!("⚠️  Redaction range {}", problem);
+            }
+        } else if !problems.is_empty() {
+            anyhow::bail!(
+                "Invalid redaction ranges: {}; pass --lenient-redaction to prove anyway",
+                problems.join("; ")
+            );
+        }
+        let gaps = redaction_range_gaps(&redaction_ranges, &diffed_a, &diffed_b, &options);
+        for gap in &gaps {
+            info!("⚠️  Redaction range {}", gap);
+        }
+        if !gaps.is_empty() {
+            info!("   A range only hides diff lines of its own operation; any:START-END hides lines however they are shown, and --context or --include-unchanged shows unchanged lines as context");
+        }
+        let merge_base = match merge_base_path {
+            Some(path) => {
+                let bytes = read_input(path, max_bytes)?;
+                let content = decode_text(path, &bytes, false)?;
+                Some(MergeBase { hash: hash_bytes_with(options.hash, &bytes), content })
+            }
+            None => None,
+        };
+
+        let mut input = DiffInput {
+            file_a_hash: hash_bytes_with(options.hash, &file_a_bytes),
+            file_b_hash: hash_bytes_with(options.hash, &file_b_bytes),
+            file_a_content,
+            file_b_content,
+            file_a_bytes: Vec::new(),
+            file_b_bytes: Vec::new(),
+            redaction_ranges,
+            redaction_profile,
+            pattern_redactions,
+            hunk_anchors,
+            ignored_lines,
+            sync_anchors,
+            options,
+            files: Vec::new(),
+            script: None,
+            merge_base,
+        };
+        if input.options.binary {
+            send_as_bytes(&mut input, file_a_bytes, file_b_bytes);
+        }
+        input
+    };
+    check_limits(&input, *matches.get_one::<usize>("max_lines").unwrap(), max_bytes)?;
+    if let Some(&min_similarity) = matches.get_one::<f32>("min_similarity") {
+        check_similarity(&input, min_similarity)?;
+    }
+    if input.options.append_only {
+        check_append_only(&input)?;
+    }
+    if matches.get_flag("dry_run") {
+        return dry_run(&input, json_summary);
+    }
+    let output_path = &match matches.get_one::<String>("output_dir") {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            Path::new(dir).join(proof_file_name(matches, &input)).to_string_lossy().into_owned()
+        }
+        None => matches.get_one::<String>("output").unwrap().clone(),
+    };
+    let file_a_hash = input.file_a_hash;
+    let file_b_hash = input.file_b_hash;
+
+    info!("Generating zero-knowledge proof for diff...");
+    info!("File A hash: {}", hex::encode(file_a_hash));
+    info!("File B hash: {}", hex::encode(file_b_hash));
+
+    // executing first is cheap next to proving, and a rejected input fails here
+    // rather than after minutes of proving
+    info!("Executing guest...");
+    let env = ExecutorEnv::builder()
         .write(&input)?
         .build()?;
+    let session = default_executor().execute(env, METHOD_ELF)?;
+    info!("Executed {} cycles in {} segments", session.cycles(), session.segments.len());
+
+    if let Some(json_path) = matches.get_one::<String>("json_only") {
+        // no receipt is kept, so skip proving and just use the execution
+        let output: DiffOutput = session.journal.decode()?;
+
+        info!("✅ Diff computed without a proof");
+        info!("Diff contains {} lines", all_lines(&output).count());
+        print_summary(&output);
+
+        fs::write(json_path, serde_json::to_vec_pretty(&output)?)?;
+        let summary = summary_json(&output, json_path);
+        if json_summary {
+            out!("{}", serde_json::to_string_pretty(&summary)?);
+        } else {
+            out!("Diff output saved to: {}", json_path);
+        }
+        return Ok(summary);
+    }
+
+    if matches.get_flag("dev") {
+        let output: DiffOutput = session.journal.decode()?;
+
+        info!("✅ Diff computed without a proof (--dev)");
+        info!("Diff contains {} lines", all_lines(&output).count());
+        print_summary(&output);
+        info!("⚠️  Dev proof: no receipt, check it with `verify --skip-crypto`");
+
+        let proof_file = ProofFile {
+            verified: false,
+            output,
+            method_id: method_id_hex(METHOD_ID),
+            proof_generated: false,
+            zkdiff_version: Some(ZKDIFF_VERSION.to_string()),
+            risc0_version: Some(risc0_zkvm::VERSION.to_string()),
+            receipt: None,
+            metrics: None,
+        };
+        write_proof(output_path, &encode(&proof_file)?)?;
+        let summary = summary_json(&proof_file.output, output_path);
+        if json_summary {
+            out!("{}", serde_json::to_string_pretty(&summary)?);
+        } else if !proof_to_stdout {
+            out!("Proof saved to: {}", output_path);
+        }
+        return Ok(summary);
+    }
 
-    let prover = default_prover();
-    let prove_info = prover.prove(env, METHOD_ELF)?;
+    info!("Generating proof...");
+    let started = Instant::now();
+    let prove_info = prove(&input, backend, started, timeout)?;
+    let metrics = ProofMetrics {
+        prove_seconds: started.elapsed().as_secs_f64(),
+        total_cycles: prove_info.stats.total_cycles,
+        user_cycles: prove_info.stats.user_cycles,
+        segments: prove_info.stats.segments,
+    };
+    info!("{}", metrics_line(&metrics));
+    verbose!("User cycles: {}, segments: {}", metrics.user_cycles, metrics.segments);
+    verbose!("Receipt size: {} bytes", bincode::serialized_size(&prove_info.receipt)?);
 
     prove_info.receipt.verify(METHOD_ID)?;
 
     let output: DiffOutput = prove_info.receipt.journal.decode()?;
 
-    println!("✅ Proof generated and verified successfully!");
-    println!("Diff contains {} lines", output.diff_lines.len());
-    
-    let mut inserts = 0;
-    let mut deletes = 0;
-    let mut redacted = 0;
-    
-    for line in &output.diff_lines {
-        match line.operation {
-            DiffOperation::Insert => inserts += 1,
-            DiffOperation::Delete => deletes += 1,
-            DiffOperation::Replace => {
-                inserts += 1;
-                deletes += 1;
-            }
-        }
-        if line.content.is_none() {
-            redacted += 1;
-        }
+    info!("✅ Proof generated and verified successfully!");
+    info!("Diff contains {} lines", all_lines(&output).count());
+    print_summary(&output);
+    if let Some(receipt_path) = matches.get_one::<String>("receipt_out") {
+        fs::write(receipt_path, bincode::serialize(&prove_info.receipt)?)?;
+        info!("Receipt saved to: {}", receipt_path);
     }
-    
-    println!("Summary: +{} -{} lines, {} redacted", inserts, deletes, redacted);
 
-    let method_id_bytes = METHOD_ID.as_bytes();
     let proof_file = ProofFile {
         verified: true,
         output,
-        method_id: hex::encode(method_id_bytes),
+        method_id: method_id_hex(METHOD_ID),
         proof_generated: true,
-        receipt: Some(serde_json::to_value(&prove_info.receipt)?),
+        zkdiff_version: Some(ZKDIFF_VERSION.to_string()),
+        risc0_version: Some(risc0_zkvm::VERSION.to_string()),
+        receipt: Some(prove_info.receipt),
+        // timings differ on every run
+        metrics: (!canonical).then_some(metrics),
     };
     
-    let proof_data = serde_json::to_string_pretty(&proof_file)?;
-    fs::write(output_path, proof_data)?;
-    println!("Proof saved to: {}", output_path);
+    write_proof(output_path, &encode(&proof_file)?)?;
+    let summary = summary_json(&proof_file.output, output_path);
+    if json_summary {
+        out!("{}", serde_json::to_string_pretty(&summary)?);
+    } else if !proof_to_stdout {
+        out!("Proof saved to: {}", output_path);
+    }
+
+    Ok(summary)
+}
+
+/// Executes the guest without proving and prints what it committed, so redactions
+/// can be checked before spending minutes on a proof. The guest runs its whole
+/// pipeline and checks, so the lines are the ones a proof would hold and a rejected
+/// input fails here as it would under `generate`.
+fn dry_run(input: &DiffInput, json_summary: bool) -> Result<serde_json::Value> {
+    let env = ExecutorEnv::builder().write(input)?.build()?;
+    let session = default_executor().execute(env, METHOD_ELF)?;
+    let mut output: DiffOutput = session.journal.decode()?;
+    expand_line_dict(&mut output)?;
+
+    info!("✅ Diff computed without proving (--dry-run); nothing was proven or written");
+    info!("Diff contains {} lines", all_lines(&output).count());
+    print_summary(&output);
+    // stdout carries nothing but the summary with --summary-format json
+    if !json_summary {
+        info!();
+        let rendered = render::unified(&output, true, false, Colors::new(false));
+        out!("{}", rendered.trim_end_matches('\n'));
+    }
+
+    let leaks = visible_pattern_matches(&output)?;
+    for (line, pattern) in &leaks {
+        out!("❌ Visible line matches redaction pattern {}: {}", pattern, line);
+    }
+    if !leaks.is_empty() {
+        anyhow::bail!("{} visible lines match a redaction pattern", leaks.len());
+    }
+    let mut summary = summary_json(&output, "");
+    summary["output_file"] = serde_json::Value::Null;
+    if json_summary {
+        out!("{}", serde_json::to_string_pretty(&summary)?);
+    }
+    Ok(summary)
+}
 
+/// Writes an encoded proof to `path`, or to stdout for `-`.
+fn write_proof(path: &str, bytes: &[u8]) -> Result<()> {
+    if path == "-" {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(bytes)?;
+        stdout.flush()?;
+    } else {
+        fs::write(path, bytes)?;
+    }
     Ok(())
 }
 
-fn verify_proof(matches: &clap::ArgMatches) -> Result<()> {
-    let proof_file_path = matches.get_one::<String>("proof_file").unwrap();
+/// `--output-dir` name of the proof of `input`: the stems of both inputs, in the order
+/// they are diffed, and the first 4 bytes of a hash over the input hashes, so pairs
+/// with the same names don't collide. For example `foo__bar__a1b2c3d4.proof`.
+fn proof_file_name(matches: &clap::ArgMatches, input: &DiffInput) -> String {
+    let arg = |id: &str| matches.get_one::<String>(id).map(String::as_str).unwrap_or_default();
+    let mut names = if matches.contains_id("no_redact_content_check") {
+        vec![arg("no_redact_content_check")]
+    } else if matches.contains_id("base") {
+        vec![arg("ours"), arg("theirs")]
+    } else if matches.contains_id("dir_a") {
+        vec![arg("dir_a"), arg("dir_b")]
+    } else {
+        // text inputs are named after their option
+        let file = |id: &str, text| matches.get_one::<String>(id).map_or(text, String::as_str);
+        vec![file("file_a", "text-a"), file("file_b", "text-b")]
+    };
+    if matches.get_flag("swap") {
+        names.reverse();
+    }
+
+    let mut hashed = [input.file_a_hash, input.file_b_hash].concat();
+    if let Some(base) = &input.merge_base {
+        hashed.extend(base.hash);
+    }
+    let mut parts: Vec<String> = names.into_iter().map(file_stem).collect();
+    parts.push(hex::encode(&hash_bytes(&hashed)[..4]));
+    format!("{}.proof", parts.join("__"))
+}
+
+/// Stem of an input path as a safe file name part; stdin is `stdin` and a git spec
+/// is named after its path.
+fn file_stem(path: &str) -> String {
+    if path == "-" {
+        return "stdin".to_string();
+    }
+    let path = path.rsplit(':').next().unwrap_or(path);
+    let stem = Path::new(path).file_stem().map(|stem| stem.to_string_lossy().into_owned());
+    let stem: String = stem
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    if stem.is_empty() {
+        "input".to_string()
+    } else {
+        stem
+    }
+}
+
+/// Fails early on inputs that would take the guest too long or that it would
+/// reject anyway.
+fn check_limits(input: &DiffInput, max_lines: usize, max_bytes: usize) -> Result<()> {
+    // limits apply to the text the guest will diff
+    let mut input = input.clone();
+    input.decode_binary();
+    let mut sides = vec![
+        ("File A".to_string(), &input.file_a_content),
+        ("File B".to_string(), &input.file_b_content),
+    ];
+    if let Some(base) = &input.merge_base {
+        sides.push(("Merge base".to_string(), &base.content));
+    }
+    for file in &input.files {
+        sides.push((format!("{} (A)", file.path), &file.input.file_a_content));
+        sides.push((format!("{} (B)", file.path), &file.input.file_b_content));
+    }
+
+    for (name, content) in sides {
+        if content.len() > max_bytes {
+            anyhow::bail!("{} is {} bytes, over the --max-bytes limit of {}", name, content.len(), max_bytes);
+        }
+        let lines = records(content, input.options.record_separator).len();
+        if lines > max_lines {
+            anyhow::bail!("{} has {} lines, over the --max-lines limit of {}", name, lines, max_lines);
+        }
+    }
+
+    if let Some(script) = &input.script {
+        for (name, leaves) in [("File A", &script.leaves_a), ("File B", &script.leaves_b)] {
+            if leaves.len() > max_lines {
+                anyhow::bail!("{} has {} line hashes, over the --max-lines limit of {}", name, leaves.len(), max_lines);
+            }
+        }
+    }
+
+    let total = input.total_lines();
+    if total > MAX_TOTAL_LINES {
+        anyhow::bail!("Inputs have {} lines in total, over the guest limit of {}", total, MAX_TOTAL_LINES);
+    }
+    Ok(())
+}
+
+/// Fails before any guest run when a plain Myers diff on the host finds the inputs
+/// less similar than `min_similarity`, which usually means the wrong files.
+fn check_similarity(input: &DiffInput, min_similarity: f32) -> Result<()> {
+    if !(0.0..=1.0).contains(&min_similarity) {
+        anyhow::bail!("Invalid --min-similarity {}, expected a ratio from 0 to 1", min_similarity);
+    }
+    let mut input = input.clone();
+    input.decode_binary();
+    let (distance, lines_a, lines_b) = quick_diff_counts(&input);
+    let estimate = similarity(distance, lines_a, lines_b);
+    verbose!("Estimated similarity: {:.1}%", estimate * 100.0);
+    if estimate < min_similarity {
+        anyhow::bail!(
+            "Files are {:.1}% similar, under the --min-similarity of {:.1}%; no proof generated",
+            estimate * 100.0,
+            min_similarity * 100.0
+        );
+    }
+    Ok(())
+}
+
+/// Fails before any guest run when file B isn't file A with lines appended, naming
+/// the first line of file A it changes (and the file in directory mode), where the
+/// guest would only refuse the input.
+fn check_append_only(input: &DiffInput) -> Result<()> {
+    let mut input = input.clone();
+    input.decode_binary();
+    let mut pairs = vec![(None, &input)];
+    pairs.extend(input.files.iter().map(|file| (Some(&file.path), &file.input)));
+    for (path, input) in pairs {
+        let options = &input.options;
+        let keys = |content: &str| comparison_keys(&as_diffed(content, options), options);
+        let (mut keys_a, mut keys_b) = (keys(&input.file_a_content), keys(&input.file_b_content));
+        if let Some(ignored) = &input.ignored_lines {
+            ignore_matched_lines(&mut keys_a, &ignored.lines_a, options.record_separator);
+            ignore_matched_lines(&mut keys_b, &ignored.lines_b, options.record_separator);
+        }
+        let keys_a: Vec<&str> = keys_a.iter().map(String::as_str).collect();
+        let keys_b: Vec<&str> = keys_b.iter().map(String::as_str).collect();
+        if let Some(line) = append_mismatch(&keys_a, &keys_b) {
+            let file = path.map(|path| format!(" of {}", path)).unwrap_or_default();
+            anyhow::bail!(
+                "File B changes line {}{} of file A, so it isn't file A with lines appended; no proof generated",
+                line,
+                file
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Edit distance and line counts of `input` by `myers_diff` over its line ranges,
+/// summed over files in directory mode. Comparison options and the chosen algorithm
+/// aren't applied, so the proven similarity can differ a little.
+fn quick_diff_counts(input: &DiffInput) -> (usize, usize, usize) {
+    fn in_range(content: &str, separator: Option<u8>, range: Option<LineRange>) -> Vec<&str> {
+        let lines = records(content, separator);
+        match range.and_then(|range| lines.get(range.start.saturating_sub(1)..range.end)) {
+            Some(lines) => lines.to_vec(),
+            None => lines,
+        }
+    }
+
+    if let Some(script) = &input.script {
+        let distance = script.operations.iter().filter(|op| **op != EditOp::Keep).count();
+        return (distance, script.leaves_a.len(), script.leaves_b.len());
+    }
+    let separator = input.options.record_separator;
+    let a = in_range(&input.file_a_content, separator, input.options.range_a);
+    let b = in_range(&input.file_b_content, separator, input.options.range_b);
+    let mut counts = (edit_distance(&myers_diff(&a, &b)), a.len(), b.len());
+    for file in &input.files {
+        let (distance, lines_a, lines_b) = quick_diff_counts(&file.input);
+        counts = (counts.0 + distance, counts.1 + lines_a, counts.2 + lines_b);
+    }
+    counts
+}
+
+/// Relative paths (joined with '/') of all files under `root`, sorted.
+fn walk_dir(root: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    fn walk(dir: &Path, prefix: &str, files: &mut BTreeMap<String, PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+            if entry.file_type()?.is_dir() {
+                walk(&entry.path(), &relative, files)?;
+            } else {
+                files.insert(relative, entry.path());
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = BTreeMap::new();
+    walk(root, "", &mut files)?;
+    Ok(files)
+}
+
+/// Pairs files by relative path; a file present on one side only is diffed against
+/// empty content.
+fn dir_input(
+    dir_a: &Path,
+    dir_b: &Path,
+    patterns: &[String],
+    hunk_pattern: Option<&String>,
+    ignore_pattern: Option<&String>,
+    anchor_pattern: Option<&String>,
+    options: &DiffOptions,
+) -> Result<DiffInput> {
+    let files_a = walk_dir(dir_a)?;
+    let files_b = walk_dir(dir_b)?;
+    let mut paths: Vec<&String> = files_a.keys().chain(files_b.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut files = Vec::new();
+    let progress = Progress::files(paths.len());
+    for path in paths {
+        progress.start(path);
+        let read = |side: &BTreeMap<String, PathBuf>| -> Result<Vec<u8>> {
+            match side.get(path) {
+                Some(file) => Ok(fs::read(file)?),
+                None => Ok(Vec::new()),
+            }
+        };
+        let file_a_bytes = read(&files_a)?;
+        let file_b_bytes = read(&files_b)?;
+        let file_a_content = decode_text(&format!("{} (A)", path), &file_a_bytes, options.binary)?;
+        let file_b_content = decode_text(&format!("{} (B)", path), &file_b_bytes, options.binary)?;
+        let diffed_a = as_diffed(&file_a_content, options);
+        let diffed_b = as_diffed(&file_b_content, options);
+        let separator = options.record_separator;
+        let pattern_redactions =
+            match_redaction_patterns(patterns, &diffed_a, &diffed_b, separator)?;
+        let hunk_anchors =
+            hunk_pattern.map(|p| match_hunk_pattern(p, &diffed_a, separator)).transpose()?;
+        let ignored_lines = ignore_pattern
+            .map(|p| match_ignore_pattern(p, &diffed_a, &diffed_b, separator))
+            .transpose()?;
+        let sync_anchors = anchor_pattern
+            .map(|p| match_anchor_pattern(p, &diffed_a, &diffed_b, separator))
+            .transpose()?;
+        let mut input = DiffInput {
+            file_a_hash: hash_bytes_with(options.hash, &file_a_bytes),
+            file_b_hash: hash_bytes_with(options.hash, &file_b_bytes),
+            file_a_content,
+            file_b_content,
+            file_a_bytes: Vec::new(),
+            file_b_bytes: Vec::new(),
+            redaction_ranges: Vec::new(),
+            redaction_profile: None,
+            pattern_redactions,
+            hunk_anchors,
+            ignored_lines,
+            sync_anchors,
+            options: options.clone(),
+            files: Vec::new(),
+            script: None,
+            merge_base: None,
+        };
+        if options.binary {
+            send_as_bytes(&mut input, file_a_bytes, file_b_bytes);
+        }
+        files.push(FileInput {
+            path: path.clone(),
+            in_a: files_a.contains_key(path),
+            in_b: files_b.contains_key(path),
+            input,
+        });
+        progress.finish_one();
+    }
+    drop(progress);
+    // the guest matches the globs itself; this only catches a mistyped one
+    for glob in &options.redact_paths {
+        if !files.iter().any(|file| path_matches(glob, &file.path)) {
+            info!("⚠️  --redact-path {} matches no file, so it hides nothing", glob);
+        }
+    }
+
+    Ok(DiffInput {
+        file_a_hash: tree_hash(&files, false, options.hash),
+        file_b_hash: tree_hash(&files, true, options.hash),
+        file_a_content: String::new(),
+        file_b_content: String::new(),
+        file_a_bytes: Vec::new(),
+        file_b_bytes: Vec::new(),
+        redaction_ranges: Vec::new(),
+        redaction_profile: None,
+        pattern_redactions: Vec::new(),
+        hunk_anchors: None,
+        ignored_lines: None,
+        sync_anchors: None,
+        options: options.clone(),
+        files,
+        script: None,
+        merge_base: None,
+    })
+}
+
+/// Script mode: the line hashes and edit script of a JSON `ScriptInput`, with the
+/// Merkle roots of the hashes in place of the file hashes and no contents.
+fn script_input(path: &str, options: &DiffOptions) -> Result<DiffInput> {
+    let script: ScriptInput = match serde_json::from_slice(&fs::read(path)?) {
+        Ok(script) => script,
+        Err(e) => anyhow::bail!("Invalid script file {}: {}", path, e),
+    };
+    Ok(DiffInput {
+        file_a_hash: merkle_root_of_leaves(&script.leaves_a),
+        file_b_hash: merkle_root_of_leaves(&script.leaves_b),
+        file_a_content: String::new(),
+        file_b_content: String::new(),
+        file_a_bytes: Vec::new(),
+        file_b_bytes: Vec::new(),
+        redaction_ranges: Vec::new(),
+        redaction_profile: None,
+        pattern_redactions: Vec::new(),
+        hunk_anchors: None,
+        ignored_lines: None,
+        sync_anchors: None,
+        options: options.clone(),
+        files: Vec::new(),
+        script: Some(script),
+        merge_base: None,
+    })
+}
+
+/// Binary mode: the guest hashes the raw bytes and decodes them itself, so they
+/// replace the decoded contents in the input.
+fn send_as_bytes(input: &mut DiffInput, file_a_bytes: Vec<u8>, file_b_bytes: Vec<u8>) {
+    input.file_a_content = String::new();
+    input.file_b_content = String::new();
+    input.file_a_bytes = file_a_bytes;
+    input.file_b_bytes = file_b_bytes;
+}
+
+/// Verifies every proof file given. A single file behaves as it always has; with
+/// several, each is checked in turn, a file that can't be read or decoded counts as
+/// failed without stopping the others, and a PASS/FAIL table follows. The exit
+/// code is that of the first failure. Returns the exit code of every file.
+fn verify_proof(matches: &clap::ArgMatches) -> Result<Vec<(String, i32)>> {
+    check_expected_id(matches)?;
+    let paths: Vec<&String> = matches.get_many::<String>("proof_file").unwrap().collect();
+    if paths.iter().filter(|path| path.as_str() == "-").count() > 1 {
+        anyhow::bail!("Only one proof can be read from stdin");
+    }
+    if let [path] = paths[..] {
+        return Ok(vec![(path.clone(), verify_file(matches, path)?)]);
+    }
+    if matches.get_one::<String>("emit_patch").is_some() {
+        anyhow::bail!("--emit-patch takes a single proof file");
+    }
+    if matches.get_one::<String>("dump_receipt").is_some() {
+        anyhow::bail!("--dump-receipt takes a single proof file");
+    }
+
+    let mut results = Vec::new();
+    let progress = Progress::files(paths.len());
+    for (i, path) in paths.into_iter().enumerate() {
+        if i > 0 {
+            info!();
+        }
+        progress.start(path);
+        let code = verify_file(matches, path).unwrap_or_else(|e| {
+            out!("❌ Error: {:#}", e);
+            EXIT_ERROR
+        });
+        results.push((path.clone(), code));
+        progress.finish_one();
+    }
+    drop(progress);
+
+    info!("\nResults:");
+    for (path, code) in &results {
+        match *code {
+            EXIT_OK => info!("PASS  {}", path),
+            code => out!("FAIL  {}  ({}, exit {})", path, failure_reason(code), code),
+        }
+    }
+    let failed = results.iter().filter(|(_, code)| *code != EXIT_OK).count();
+    info!("{} proofs: {} passed, {} failed", results.len(), results.len() - failed, failed);
+
+    Ok(results)
+}
+
+/// Exit code of the first failed proof, or `EXIT_OK` when all passed.
+fn first_failure(results: &[(String, i32)]) -> i32 {
+    results.iter().map(|&(_, code)| code).find(|&code| code != EXIT_OK).unwrap_or(EXIT_OK)
+}
+
+/// Fails unless the guest built into this binary has the image ID `--expect-id`
+/// names, compared case-insensitively and with an optional `0x`.
+fn check_expected_id(matches: &clap::ArgMatches) -> Result<()> {
+    let Some(expected) = matches.get_one::<String>("expect_id") else {
+        return Ok(());
+    };
+    let expected = expected.strip_prefix("0x").unwrap_or(expected).to_ascii_lowercase();
+    if expected.len() != 64 || !expected.bytes().all(|b| b.is_ascii_hexdigit()) {
+        anyhow::bail!("--expect-id needs the 64 hex digits `zkdiff id` prints");
+    }
+    let built_in = method_id_hex(METHOD_ID);
+    if expected != built_in {
+        anyhow::bail!(
+            "Guest image ID mismatch: this build is {}, --expect-id wants {}",
+            built_in,
+            expected
+        );
+    }
+    Ok(())
+}
+
+/// `--status-fd`, rejected up front where it can't be honored.
+fn status_fd(matches: &clap::ArgMatches) -> Result<Option<i32>> {
+    let fd = matches.get_one::<i32>("status_fd").copied();
+    if fd.is_some() && !cfg!(unix) {
+        anyhow::bail!("--status-fd is only supported on Unix");
+    }
+    Ok(fd)
+}
+
+fn error_status(command: &str, error: &anyhow::Error) -> serde_json::Value {
+    serde_json::json!({ "ok": false, "command": command, "error": format!("{:#}", error) })
+}
+
+/// Writes `status` as a single line to the caller's file descriptor `fd`, which is
+/// left open.
+#[cfg(unix)]
+fn write_status(fd: i32, status: &serde_json::Value) -> Result<()> {
+    use std::mem::ManuallyDrop;
+    use std::os::unix::io::FromRawFd;
+
+    // SAFETY: the fd belongs to whoever started us and is only borrowed here;
+    // ManuallyDrop keeps it from being closed
+    let mut file = ManuallyDrop::new(unsafe { fs::File::from_raw_fd(fd) });
+    file.write_all(format!("{}\n", serde_json::to_string(status)?).as_bytes())
+        .map_err(|e| anyhow::anyhow!("Can't write to --status-fd {}: {}", fd, e))
+}
+
+#[cfg(not(unix))]
+fn write_status(_fd: i32, _status: &serde_json::Value) -> Result<()> {
+    unreachable!("--status-fd is rejected off Unix")
+}
+
+fn failure_reason(code: i32) -> &'static str {
+    match code {
+        EXIT_METHOD_ID_MISMATCH => "method ID mismatch",
+        EXIT_RECEIPT_INVALID => "receipt invalid",
+        EXIT_NO_RECEIPT => "no receipt",
+        EXIT_REDACTION_LEAK => "redaction leak",
+        EXIT_PROOF_HASH_MISMATCH => "proof hash mismatch",
+        EXIT_JOURNAL_MISMATCH => "journal mismatch",
+        _ => "error",
+    }
+}
+
+fn verify_file(matches: &clap::ArgMatches, proof_file_path: &str) -> Result<i32> {
+    info!("Verifying proof from: {}", proof_file_path);
     
-    println!("Verifying proof from: {}", proof_file_path);
+    // either encoding is read as is; ProofFile::decode tells them apart by the magic
+    let bytes = if proof_file_path == "-" { read_stdin()? } else { fs::read(proof_file_path)? };
+    if matches.get_flag("skip_crypto") {
+        // a bare DiffOutput from --json-only, or the output of a full proof file
+        let mut output = match serde_json::from_slice::<DiffOutput>(&bytes) {
+            Ok(output) => output,
+            Err(_) => ProofFile::decode(&bytes)?.output,
+        };
+        if let Some(mismatch) = proof_hash_mismatch(&output) {
+            out!("❌ Proof hash mismatch for {}!", mismatch);
+            out!("The diff in the file was changed after it was computed");
+            return Ok(EXIT_PROOF_HASH_MISMATCH);
+        }
+        expand_line_dict(&mut output)?;
+        info!("✅ Proof hash is consistent with the diff");
+        info!("⚠️  --skip-crypto checks integrity only: no receipt was verified, so nothing attests that the zkVM guest computed this diff from the hashed files");
+        return report_output(matches, &output);
+    }
+
+    let proof_file = ProofFile::decode(&bytes)?;
+    let report = verify_proof_file(&proof_file);
     
-    let proof_data = fs::read_to_string(proof_file_path)?;
-    let proof_file: ProofFile = serde_json::from_str(&proof_data)?;
+    if !report.method_id_matches {
+        out!("❌ Method ID mismatch!");
+        out!("Expected: {}", method_id_hex(METHOD_ID));
+        out!("Found: {}", report.method_id);
+        return Ok(EXIT_METHOD_ID_MISMATCH);
+    }
+
+    if let Some(mismatch) = &report.proof_hash_mismatch {
+        out!("❌ Proof hash mismatch for {}!", mismatch);
+        out!("The diff in the proof file was changed after it was proven");
+        return Ok(EXIT_PROOF_HASH_MISMATCH);
+    }
+
+    match &report.receipt {
+        ReceiptStatus::Valid => {}
+        ReceiptStatus::Missing => {
+            out!("⚠️  No receipt found in proof file - cannot verify cryptographically");
+            out!("Proof metadata indicates: {}", if proof_file.verified { "verified" } else { "not verified" });
+            if !proof_file.proof_generated {
+                out!("It was generated without proving (--dev); `verify --skip-crypto` checks its proof hash");
+            }
+            return Ok(EXIT_NO_RECEIPT);
+        }
+        ReceiptStatus::Invalid(e) => {
+            out!("❌ Proof verification failed: {}", e);
+            return Ok(EXIT_RECEIPT_INVALID);
+        }
+    }
+
+    // the receipt attests its journal, not the diff stored next to it
+    if let Some(mismatch) = &report.journal_mismatch {
+        out!("❌ The diff in the proof file doesn't match the receipt's journal: {}", mismatch);
+        out!("The receipt is valid, but for another diff than the one in the file");
+        return Ok(EXIT_JOURNAL_MISMATCH);
+    }
     
-    let expected_method_id = hex::encode(METHOD_ID.as_bytes());
-    if proof_file.method_id != expected_method_id {
-        println!("❌ Method ID mismatch!");
-        println!("Expected: {}", expected_method_id);
-        println!("Found: {}", proof_file.method_id);
-        return Ok(());
+    info!("✅ Proof verification successful!");
+    if let Some(metrics) = &proof_file.metrics {
+        info!("{}", metrics_line(metrics));
+    }
+    // only a receipt that verified is written out
+    if let (Some(receipt_path), Some(receipt)) =
+        (matches.get_one::<String>("dump_receipt"), &proof_file.receipt)
+    {
+        fs::write(receipt_path, bincode::serialize(receipt)?)?;
+        info!("Receipt saved to: {}", receipt_path);
     }
+    let mut output = proof_file.output;
+    expand_line_dict(&mut output)?;
+    report_output(matches, &output)
+}
 
-    if let Some(receipt_value) = proof_file.receipt {
-        let receipt: Receipt = serde_json::from_value(receipt_value)?;
-        
-        match receipt.verify(METHOD_ID) {
-            Ok(_) => {
-                println!("✅ Proof verification successful!");
-                
-                println!("\nProof Details:");
-                println!("File A hash: {}", hex::encode(proof_file.output.file_a_hash));
-                println!("File B hash: {}", hex::encode(proof_file.output.file_b_hash));
-                println!("Diff lines: {}", proof_file.output.diff_lines.len());
-                
-                let mut inserts = 0;
-                let mut deletes = 0;
-                let mut redacted = 0;
-                
-                for line in &proof_file.output.diff_lines {
-                    match line.operation {
-                        DiffOperation::Insert => inserts += 1,
-                        DiffOperation::Delete => deletes += 1,
-                        DiffOperation::Replace => {
-                            inserts += 1;
-                            deletes += 1;
-                        }
-                    }
-                    if line.content.is_none() {
-                        redacted += 1;
-                    }
-                }
-                
-                println!("Summary: +{} -{} lines, {} redacted", inserts, deletes, redacted);
+/// Prints the contents of a proof file. Nothing is checked, so this works on proofs
+/// without a receipt and costs no verification.
+fn proof_info(matches: &clap::ArgMatches) -> Result<()> {
+    let proof_file_path = matches.get_one::<String>("proof_file").unwrap();
+    let proof_file = ProofFile::read(proof_file_path)?;
+
+    out!("Proof file: {}", proof_file_path);
+    let build = if proof_file.method_id == method_id_hex(METHOD_ID) {
+        "matches this build"
+    } else {
+        "differs from this build"
+    };
+    out!("Method ID: {} ({})", proof_file.method_id, build);
+    out!("Receipt: {}", if proof_file.receipt.is_some() { "present" } else { "none" });
+    out!("Proof generated: {}", if proof_file.proof_generated { "yes" } else { "no" });
+    if let (Some(zkdiff), Some(risc0)) = (&proof_file.zkdiff_version, &proof_file.risc0_version) {
+        out!("Written by: zkdiff {}, risc0 {}", zkdiff, risc0);
+    }
+    if let Some(metrics) = &proof_file.metrics {
+        out!("{}", metrics_line(metrics));
+    }
+    let mut output = proof_file.output;
+    expand_line_dict(&mut output)?;
+    print_details(&output);
+    out!("\n⚠️  Unverified — run `verify` to check the receipt");
+    Ok(())
+}
+
+/// Rebuilds file B from file A and the diff in a proof, checked against the proof's
+/// hashes. The receipt isn't verified; `verify` does that.
+fn apply_proof(matches: &clap::ArgMatches) -> Result<()> {
+    let proof_file_path = matches.get_one::<String>("proof_file").unwrap();
+    let output_path = matches.get_one::<String>("output").unwrap();
+
+    let bytes = fs::read(proof_file_path)?;
+    let mut output = match serde_json::from_slice::<DiffOutput>(&bytes) {
+        Ok(output) => output,
+        Err(_) => ProofFile::decode(&bytes)?.output,
+    };
+    if let Some(mismatch) = proof_hash_mismatch(&output) {
+        return Err(ZkDiffError::ProofHashMismatch(mismatch.to_string()).into());
+    }
+    expand_line_dict(&mut output)?;
+    let file_b = match matches.get_one::<String>("file_a") {
+        Some(file_a_path) => {
+            let file_a_bytes = read_input(file_a_path, usize::MAX)?;
+            apply_diff(&output, &decode_text(file_a_path, &file_a_bytes, false)?)?
+        }
+        None => rebuild_file_b(&output)?,
+    };
+    fs::write(output_path, &file_b)?;
+    info!("✅ File B rebuilt and matches file_b_hash: {}", hex::encode(output.file_b_hash));
+    out!("File B saved to: {}", output_path);
+    info!("⚠️  The receipt was not checked; run `verify` to check the proof itself");
+    Ok(())
+}
+
+/// Prover pinned by `--prover`.
+#[derive(Debug, Clone)]
+enum Backend {
+    /// Proving on this machine's CPU, by the r0vm server at this path.
+    Local(PathBuf),
+    Bonsai,
+}
+
+/// Checks that the `--prover` backend named `name` can be used before anything is
+/// executed, so a missing server or API key isn't found out after the guest ran.
+fn backend(name: &str) -> Result<Backend> {
+    let chosen = std::env::var("RISC0_PROVER").unwrap_or_default();
+    if !chosen.is_empty() && chosen != name {
+        verbose!("--prover {} overrides RISC0_PROVER={}", name, chosen);
+    }
+    match name {
+        "local" => {
+            let r0vm = match std::env::var_os("RISC0_SERVER_PATH") {
+                Some(path) => Some(PathBuf::from(path)).filter(|path| path.is_file()),
+                None => std::env::var_os("PATH").and_then(|paths| {
+                    let dirs = std::env::split_paths(&paths);
+                    dirs.map(|dir| dir.join("r0vm")).find(|path| path.is_file())
+                }),
+            };
+            match r0vm {
+                Some(r0vm) => Ok(Backend::Local(r0vm)),
+                None => anyhow::bail!("--prover local needs r0vm, risc0's prover; install it with `rzup install` or point RISC0_SERVER_PATH at it"),
             }
-            Err(e) => {
-                println!("❌ Proof verification failed: {}", e);
+        }
+        "bonsai" => {
+            let missing: Vec<&str> = ["BONSAI_API_URL", "BONSAI_API_KEY"]
+                .into_iter()
+                .filter(|var| std::env::var(var).map_or(true, |value| value.is_empty()))
+                .collect();
+            if !missing.is_empty() {
+                anyhow::bail!("--prover bonsai needs {} set", missing.join(" and "));
             }
+            Ok(Backend::Bonsai)
         }
-    } else {
-        println!("⚠️  No receipt found in proof file - cannot verify cryptographically");
-        println!("Proof metadata indicates: {}", if proof_file.verified { "verified" } else { "not verified" });
+        _ => unreachable!(),
+    }
+}
+
+/// Proves `input` on a thread of its own, with the `backend` given or else the one
+/// risc0 picks from the environment, printing the time elapsed since `started`
+/// every `PROGRESS_INTERVAL` so a long proof doesn't look stuck. Fails once
+/// `timeout` has passed since `started`; the prover can't be interrupted, so it is
+/// left to end with the process, before anything was written for it.
+fn prove(
+    input: &DiffInput,
+    backend: Option<Backend>,
+    started: Instant,
+    timeout: Option<Duration>,
+) -> Result<ProveInfo> {
+    let (sender, proved) = mpsc::channel();
+    let input = input.clone();
+    thread::spawn(move || {
+        let prove = || -> Result<ProveInfo> {
+            let env = ExecutorEnv::builder().write(&input)?.build()?;
+            let prover: Rc<dyn Prover> = match backend {
+                Some(Backend::Local(r0vm)) => Rc::new(ExternalProver::new("local", r0vm)),
+                Some(Backend::Bonsai) => Rc::new(BonsaiProver::new("bonsai")),
+                None => default_prover(),
+            };
+            // risc0 names the r0vm server it proves with by default "ipc"
+            match prover.get_name().as_str() {
+                "ipc" => info!("Prover: local"),
+                name => info!("Prover: {}", name),
+            }
+            prover.prove(env, METHOD_ELF)
+        };
+        // nobody is waiting any more after a timeout
+        let _ = sender.send(prove());
+    });
+    loop {
+        let wait = match timeout {
+            Some(timeout) => PROGRESS_INTERVAL.min(timeout.saturating_sub(started.elapsed())),
+            None => PROGRESS_INTERVAL,
+        };
+        match proved.recv_timeout(wait) {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Disconnected) => anyhow::bail!("The prover stopped without a result"),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if let Some(timeout) = timeout.filter(|&timeout| started.elapsed() >= timeout) {
+            anyhow::bail!("Proving timed out after {}s; no proof was written", timeout.as_secs());
+        }
+        info!("  still proving, {}s elapsed", started.elapsed().as_secs());
+    }
+}
+
+/// Proving time and cycle count, like `Proved in 12.3s, 4.2M cycles`.
+fn metrics_line(metrics: &ProofMetrics) -> String {
+    let cycles = match metrics.total_cycles {
+        c if c >= 1_000_000 => format!("{:.1}M", c as f64 / 1_000_000.0),
+        c if c >= 1_000 => format!("{:.1}K", c as f64 / 1_000.0),
+        c => c.to_string(),
+    };
+    format!("Proved in {:.1}s, {} cycles", metrics.prove_seconds, cycles)
+}
+
+/// Prints the details and the requested rendering of a checked output, then checks
+/// visible lines against its redaction patterns.
+fn report_output(matches: &clap::ArgMatches, output: &DiffOutput) -> Result<i32> {
+    let format = matches.get_one::<String>("format").unwrap();
+    let width = *matches.get_one::<usize>("width").unwrap();
+    let line_numbers = matches.get_one::<String>("line_numbers").unwrap() == "on";
+    let expand_redactions = matches.get_flag("expand_redactions");
+
+    print_details(output);
+    
+    let leaks = visible_pattern_matches(output)?;
+    for (line, pattern) in &leaks {
+        out!("❌ Visible line matches redaction pattern {}: {}", pattern, line);
+    }
+    
+    // only the rendering is filtered and reversed; the details above describe the
+    // proof as is, and --only names operations as proven
+    let filtered;
+    let mut view = output;
+    if let Some(operations) = matches.get_many::<String>("only") {
+        let operations: Vec<DiffOperation> = operations.map(|op| parse_operation(op)).collect();
+        filtered = render::only(view, &operations);
+        view = &filtered;
+    }
+    let reversed;
+    if matches.get_flag("reverse_render") {
+        reversed = render::reversed(view);
+        view = &reversed;
+    }
+    let colors = Colors::new(use_color(matches));
+    let rendered = match format.as_str() {
+        "unified" => Some(render::unified(view, line_numbers, expand_redactions, colors)),
+        "side-by-side" => {
+            Some(render::side_by_side(view, width, line_numbers, expand_redactions, colors))
+        }
+        "merge" => {
+            if output.base_hash.is_none() {
+                anyhow::bail!("--format merge needs a proof generated with --base, --ours and --theirs");
+            }
+            Some(render::merge(view, colors))
+        }
+        "names-only" => Some(render::names_only(view)),
+        _ => None,
+    };
+    if let Some(rendered) = rendered {
+        info!();
+        // a pager drawn over by the progress bar would be unreadable
+        log::suspend(|| page(&rendered, matches.get_flag("no_pager")))?;
     }
     
+    if !leaks.is_empty() {
+        return Ok(EXIT_REDACTION_LEAK);
+    }
+    if let Some(patch_path) = matches.get_one::<String>("emit_patch") {
+        if output.stats.is_some() {
+            anyhow::bail!("A stats-only proof has no lines to write as a patch");
+        }
+        let file_path = matches.get_one::<String>("patch_path").unwrap();
+        fs::write(patch_path, render::patch(view, file_path))?;
+        out!("Patch saved to: {}", patch_path);
+    }
+    Ok(EXIT_OK)
+}
+
+/// Prints a rendered diff, through `$PAGER` (`less -R` when unset) when stdout is a
+/// terminal the diff doesn't fit on. An empty `PAGER`, `cat` or `--no-pager` prints
+/// it directly, and so does a pager that can't be started.
+fn page(rendered: &str, no_pager: bool) -> Result<()> {
+    let height = terminal_size::terminal_size().map(|(_, terminal_size::Height(rows))| rows);
+    let long = height.is_some_and(|rows| rendered.lines().count() >= rows as usize);
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut words = pager.split_whitespace();
+    let program = match words.next() {
+        Some(program) if !no_pager && long && program != "cat" && io::stdout().is_terminal() => {
+            program
+        }
+        _ => {
+            print!("{}", rendered);
+            return Ok(());
+        }
+    };
+    let mut child = match std::process::Command::new(program)
+        .args(words)
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            verbose!("Can't start pager `{}`: {}", pager, e);
+            print!("{}", rendered);
+            return Ok(());
+        }
+    };
+    io::stdout().flush()?;
+    // quitting the pager before the end closes the pipe, which isn't an error
+    let written = child.stdin.take().unwrap().write_all(rendered.as_bytes());
+    match written {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            anyhow::bail!("Can't write to pager `{}`: {}", pager, e);
+        }
+        _ => {}
+    }
+    child.wait()?;
     Ok(())
 }
 
-fn parse_redaction_ranges(redact_str: &str) -> Result<Vec<RedactionRange>> {
-    if redact_str.is_empty() {
-        return Ok(vec![]);
+/// `--color always` and `never` are taken as given; `auto` colors only a terminal,
+/// and only when `NO_COLOR` is unset or empty.
+fn use_color(matches: &clap::ArgMatches) -> bool {
+    match matches.get_one::<String>("color").unwrap().as_str() {
+        "always" => true,
+        "never" => false,
+        _ => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            !no_color && io::stdout().is_terminal()
+        }
     }
+}
 
-    let mut ranges = Vec::new();
-    for range_str in redact_str.split(',') {
-        let parts: Vec<&str> = range_str.split(':').collect();
-        if parts.len() != 2 {
-            continue;
+fn print_details(output: &DiffOutput) {
+    info!("\nProof Details:");
+    info!("File A hash: {}", hex::encode(output.file_a_hash));
+    info!("File B hash: {}", hex::encode(output.file_b_hash));
+    info!("File A: {} lines, File B: {} lines", output.lines_a, output.lines_b);
+    info!("Diff lines: {}", all_lines(output).count());
+    info!("Edit distance: {}", output.edit_distance);
+    info!("Similarity: {:.1}%", output.similarity * 100.0);
+    info!("Algorithm: {:?}", output.algorithm);
+    if !output.suppressed.is_empty() {
+        let names: Vec<String> = output.suppressed.iter().map(|op| format!("{:?}", op)).collect();
+        info!("Suppressed: {} lines are left out, the proof covers only the rest", names.join(", "));
+    }
+    if let Some(tokenizer) = output.tokenizer {
+        info!("Tokenizer: {:?} (lines diffed by their tokens)", tokenizer);
+    }
+    info!(
+        "Hash: {}",
+        match output.hash {
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Blake3 => "BLAKE3",
+        }
+    );
+    if output.salted_redaction {
+        info!("Redacted lengths: replaced by salted tags (equal tags mean equal hidden text)");
+    } else {
+        info!("Redacted lengths: {}", render::unit_name(output.length_unit));
+    }
+    info!("Proof hash: {}", hex::encode(output.proof_hash));
+    if let (Some(root_a), Some(root_b)) = (output.merkle_root_a, output.merkle_root_b) {
+        info!("Merkle root A: {}", hex::encode(root_a));
+        info!("Merkle root B: {}", hex::encode(root_b));
+    }
+    if let Some(range) = output.range_a {
+        info!("File A range: lines {}-{} only (partial diff)", range.start, range.end);
+    }
+    if let Some(range) = output.range_b {
+        info!("File B range: lines {}-{} only (partial diff)", range.start, range.end);
+    }
+    if output.precomputed_script {
+        info!("Precomputed script: checked against line hashes only; file hashes are Merkle roots and lines have no content");
+    }
+    if let Some(base_hash) = output.base_hash {
+        info!("Merge base hash: {}", hex::encode(base_hash));
+        info!("Merge: {}", merge_counts(output));
+    }
+    if output.binary {
+        info!("Binary: files hashed as raw bytes, content decoded lossily (invalid UTF-8 shown as U+FFFD)");
+    }
+    if output.stats.is_some() {
+        info!("Stats only: no line content or line numbers are committed");
+    }
+    if output.structure_only {
+        info!("Structure only: files hashed but not diffed, only their paths, status and hashes are committed");
+    }
+    if let Some(separator) = output.record_separator {
+        info!("Records: split on {:?} instead of newlines, and line numbers count records", char::from(separator));
+    }
+    if output.normalize_eol {
+        info!("Line endings: normalized (CRLF and LF compared as equal)");
+    }
+    if output.ignore_whitespace {
+        info!("Whitespace: ignored (lines compared trimmed, with inner runs collapsed)");
+    }
+    if output.ignore_case {
+        info!("Case: ignored (ASCII letters compared case-insensitively)");
+    }
+    if output.ignore_blank_lines {
+        info!("Blank lines: ignored (left out of the comparison, and shown only where paired)");
+    }
+    if let Some(pattern) = &output.ignore_pattern {
+        info!("Ignored lines: those matching {} compared as equal to each other", pattern);
+    }
+    if let Some(pattern) = &output.anchor_pattern {
+        info!("Anchors: equal lines matching {} aligned, and the segments between them diffed on their own", pattern);
+    }
+    if let Some(prefix) = output.prefix_lines {
+        info!("Append only: the first {} lines of file A are unchanged in file B, only lines after them are listed", prefix);
+    }
+    if let Some(width) = output.expand_tabs {
+        info!("Tabs: expanded to stops every {} columns, in the content shown too", width);
+    }
+    if output.include_unchanged {
+        info!("Full file: every line of file B is committed, and `apply` can rebuild it without file A");
+    }
+    if !output.line_dict.is_empty() {
+        info!("Deduplicated: {} distinct line contents committed once each and referenced by index", output.line_dict.len());
+    }
+    if let Some(gap) = output.merge_gap {
+        info!("Merged hunks: gaps of up to {} unchanged lines between changes shown as context", gap);
+    }
+    if let Some(max_len) = output.max_line_len {
+        let truncated = output.diff_lines.iter().filter(|line| line.truncated).count();
+        info!("Line length: content capped at {} bytes, {} lines truncated", max_len, truncated);
+    }
+    if output.no_newline_a {
+        info!("File A: no newline at end of file");
+    }
+    if output.no_newline_b {
+        info!("File B: no newline at end of file");
+    }
+    for pattern in &output.redaction_patterns {
+        info!("Redaction pattern: {}", pattern);
+    }
+    if !output.redaction_keyword_hashes.is_empty() {
+        let values = if output.redact_keyword_values { ", with their values" } else { "" };
+        let count = output.redaction_keyword_hashes.len();
+        info!("Redaction keywords: {} hidden{}, as hashed:", count, values);
+        for hash in &output.redaction_keyword_hashes {
+            info!("  {}", hex::encode(hash));
+        }
+    }
+    if output.redact_all {
+        info!("Redaction: every line's content is hidden (--redact-all)");
+    }
+    if !output.redact_paths.is_empty() {
+        info!("Redacted paths: every line of files matching {} is hidden", output.redact_paths.join(", "));
+    }
+    if output.redact_line_numbers {
+        info!("Line numbers: committed only for lines without redacted content");
+    }
+    if let Some(pattern) = &output.hunk_pattern {
+        info!("Hunk pattern: {}", pattern);
+    }
+    let hunks: usize = output.hunks.len()
+        + output.files.iter().map(|file| file.output.hunks.len()).sum::<usize>();
+    if hunks > 0 {
+        info!("Hunks: {} committed with their line spans", hunks);
+    }
+    if let Some(profile) = &output.redaction_profile {
+        info!("Redaction profile: {}", profile);
+    }
+    for (start_a, start_b, len) in moved_blocks(&output.diff_lines) {
+        info!("Moved: A:{}-{} -> B:{}-{}", start_a, start_a + len - 1, start_b, start_b + len - 1);
+    }
+    if !output.files.is_empty() {
+        info!("Files: {}", output.files.len());
+        for file in &output.files {
+            let status = match file.status {
+                FileStatus::Added => "added",
+                FileStatus::Removed => "removed",
+                FileStatus::Modified => "modified",
+                FileStatus::Unchanged => "unchanged",
+            };
+            // files hidden by --redact-path; with --redact-all every file is
+            let redacted = file.output.redact_all && !output.redact_all;
+            info!("  {:<9} {}{}", status, file.path, if redacted { " (redacted)" } else { "" });
+            if output.structure_only {
+                if file.status != FileStatus::Added {
+                    info!("            A: {}", hex::encode(file.output.file_a_hash));
+                }
+                if file.status != FileStatus::Removed {
+                    info!("            B: {}", hex::encode(file.output.file_b_hash));
+                }
+            }
+            for (start_a, start_b, len) in moved_blocks(&file.output.diff_lines) {
+                info!(
+                    "            moved A:{}-{} -> B:{}-{}",
+                    start_a,
+                    start_a + len - 1,
+                    start_b,
+                    start_b + len - 1
+                );
+            }
         }
+    }
+    print_summary(output);
+}
 
-        let operation = match parts[0] {
-            "insert" | "i" => DiffOperation::Insert,
-            "delete" | "d" => DiffOperation::Delete,
-            "replace" | "r" => DiffOperation::Replace,
-            _ => continue,
+/// Moved blocks as (first line in A, first line in B, length), from the `Move`
+/// lines at their old places.
+fn moved_blocks(diff_lines: &[DiffLine]) -> Vec<(usize, usize, usize)> {
+    let mut blocks: Vec<(usize, usize, usize)> = Vec::new();
+    for line in diff_lines {
+        if line.operation != DiffOperation::Move {
+            continue;
+        }
+        let (Some(a), Some(b)) = (line.line_number_a, line.moved_line) else {
+            continue;
         };
+        match blocks.last_mut() {
+            Some((start_a, start_b, len)) if *start_a + *len == a && *start_b + *len == b => {
+                *len += 1
+            }
+            _ => blocks.push((a, b, 1)),
+        }
+    }
+    blocks
+}
 
-        let range_parts: Vec<&str> = parts[1].split('-').collect();
-        if range_parts.len() != 2 {
-            continue;
+/// Diff lines of a single-file output, or of every file in directory mode.
+fn all_lines(output: &DiffOutput) -> impl Iterator<Item = &DiffLine> {
+    output
+        .diff_lines
+        .iter()
+        .chain(output.files.iter().flat_map(|file| file.output.diff_lines.iter()))
+}
+
+/// Machine-readable counterpart of `print_summary` for `--summary-format json`.
+fn summary_json(output: &DiffOutput, output_path: &str) -> serde_json::Value {
+    let stats = diff_stats(output);
+    serde_json::json!({
+        "file_a_hash": hex::encode(output.file_a_hash),
+        "file_b_hash": hex::encode(output.file_b_hash),
+        "inserted": stats.inserted,
+        "deleted": stats.deleted,
+        "replaced": stats.replaced,
+        "moved": stats.moved,
+        "redacted": stats.redacted,
+        "output_file": output_path,
+    })
+}
+
+fn print_summary(output: &DiffOutput) {
+    let stats = diff_stats(output);
+    info!(
+        "Summary: +{} -{} ~{} lines, {} moved, {} redacted",
+        stats.inserted, stats.deleted, stats.replaced, stats.moved, stats.redacted
+    );
+    if output.base_hash.is_some() {
+        info!("Merge: {}", merge_counts(output));
+    }
+}
+
+/// Changed merge regions by who changed them, conflicts last.
+fn merge_counts(output: &DiffOutput) -> String {
+    let count = |status| output.merge_chunks.iter().filter(|c| c.status == status).count();
+    format!(
+        "{} from ours, {} from theirs, {} changed on both sides alike, {} conflicts",
+        count(MergeStatus::Ours),
+        count(MergeStatus::Theirs),
+        count(MergeStatus::Both),
+        count(MergeStatus::Conflict)
+    )
+}
+
+/// `content` as the guest diffs it, which is what patterns must be matched against.
+fn as_diffed<'a>(content: &'a str, options: &DiffOptions) -> Cow<'a, str> {
+    match options.expand_tabs {
+        Some(width) => Cow::Owned(tab_expanded(content, width)),
+        None => Cow::Borrowed(content),
+    }
+}
+
+fn match_redaction_patterns(
+    patterns: &[String],
+    file_a_content: &str,
+    file_b_content: &str,
+    separator: Option<u8>,
+) -> Result<Vec<PatternRedaction>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let regex = Regex::new(pattern)?;
+            Ok(PatternRedaction {
+                pattern: pattern.clone(),
+                lines_a: matching_lines(&regex, file_a_content, separator),
+                lines_b: matching_lines(&regex, file_b_content, separator),
+            })
+        })
+        .collect()
+}
+
+/// File A lines matching the hunk header pattern, for the guest to pick from.
+fn match_hunk_pattern(
+    pattern: &str,
+    file_a_content: &str,
+    separator: Option<u8>,
+) -> Result<HunkAnchors> {
+    let regex = Regex::new(pattern)?;
+    Ok(HunkAnchors {
+        pattern: pattern.to_string(),
+        lines_a: matching_lines(&regex, file_a_content, separator),
+    })
+}
+
+/// Lines of both files matching the `--ignore-matching` pattern, which the guest
+/// compares as equal to each other.
+fn match_ignore_pattern(
+    pattern: &str,
+    file_a_content: &str,
+    file_b_content: &str,
+    separator: Option<u8>,
+) -> Result<IgnoredLines> {
+    let regex = Regex::new(pattern)?;
+    Ok(IgnoredLines {
+        pattern: pattern.to_string(),
+        lines_a: matching_lines(&regex, file_a_content, separator),
+        lines_b: matching_lines(&regex, file_b_content, separator),
+    })
+}
+
+/// Lines of both files matching the `--anchor` pattern, which the guest aligns
+/// with an equal one before diffing the segments between them.
+fn match_anchor_pattern(
+    pattern: &str,
+    file_a_content: &str,
+    file_b_content: &str,
+    separator: Option<u8>,
+) -> Result<SyncAnchors> {
+    let regex = Regex::new(pattern)?;
+    Ok(SyncAnchors {
+        pattern: pattern.to_string(),
+        lines_a: matching_lines(&regex, file_a_content, separator),
+        lines_b: matching_lines(&regex, file_b_content, separator),
+    })
+}
+
+/// 1-based numbers of the lines, or the records split on `separator`, of `content`
+/// that `regex` matches.
+fn matching_lines(regex: &Regex, content: &str, separator: Option<u8>) -> Vec<usize> {
+    records(content, separator)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// The guest can't evaluate regexes, so re-run the committed patterns over every
+/// line left visible and report any that should have been hidden.
+fn visible_pattern_matches(output: &DiffOutput) -> Result<Vec<(String, String)>> {
+    let mut found = Vec::new();
+    for file in &output.files {
+        for (label, pattern) in visible_pattern_matches(&file.output)? {
+            found.push((format!("{}:{}", file.path, label), pattern));
+        }
+    }
+    for pattern in &output.redaction_patterns {
+        let regex = Regex::new(pattern)?;
+        for line in &output.diff_lines {
+            // lines committed without numbers are still checked, with `?` for the number
+            let label = |side: &str, number: Option<usize>| match number {
+                Some(n) => format!("{}:{}", side, n),
+                None => format!("{}:?", side),
+            };
+            let (old_label, new_label) = match line.operation {
+                DiffOperation::Insert | DiffOperation::Move if line.line_number_a.is_none() => {
+                    (Some(label("B", line.line_number_b)), None)
+                }
+                _ => (
+                    Some(label("A", line.line_number_a)),
+                    Some(label("B", line.line_number_b)),
+                ),
+            };
+            for (label, content) in [(old_label, &line.content), (new_label, &line.new_content)] {
+                if let (Some(label), Some(content)) = (label, content) {
+                    if regex.is_match(content) {
+                        found.push((label, pattern.clone()));
+                    }
+                }
+            }
         }
+    }
+    Ok(found)
+}
 
-        let start_line = range_parts[0].parse::<usize>()?;
-        let end_line = range_parts[1].parse::<usize>()?;
+/// Operation named by a `--only` or `--suppress` value, which clap has checked.
+fn parse_operation(operation: &str) -> DiffOperation {
+    match operation {
+        "insert" => DiffOperation::Insert,
+        "delete" => DiffOperation::Delete,
+        "replace" => DiffOperation::Replace,
+        "move" => DiffOperation::Move,
+        _ => DiffOperation::Context,
+    }
+}
 
-        ranges.push(RedactionRange {
-            start_line,
-            end_line,
-            operation,
-        });
+/// `--timeout` value: a whole number of seconds, or of minutes or hours with an `m`
+/// or `h` suffix. Seconds may be suffixed with `s`.
+fn parse_timeout(timeout: &str) -> Result<Duration> {
+    let digits = timeout.find(|c: char| !c.is_ascii_digit()).unwrap_or(timeout.len());
+    let (number, unit) = timeout.split_at(digits);
+    let scale = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => 0,
+    };
+    match number.parse::<u64>().ok().and_then(|n| n.checked_mul(scale)) {
+        Some(seconds) if seconds > 0 => Ok(Duration::from_secs(seconds)),
+        _ => anyhow::bail!("Invalid --timeout {:?}, expected a duration like 300s, 5m or 1h", timeout),
     }
+}
+
+fn parse_salt(salt: &str) -> Result<Vec<u8>> {
+    match hex::decode(salt) {
+        Ok(bytes) if !bytes.is_empty() => Ok(bytes),
+        _ => anyhow::bail!("Invalid redaction salt {:?}, expected a non-empty hex string", salt),
+    }
+}
 
+fn parse_record_separator(separator: &str) -> Result<u8> {
+    match unescape(separator, "--record-separator")?.as_bytes() {
+        [byte] => Ok(*byte),
+        _ => anyhow::bail!("Invalid --record-separator {:?}, expected a single ASCII character or escape", separator),
+    }
+}
+
+fn parse_redaction_hash(hash: &str) -> Result<[u8; 32]> {
+    match hex::decode(hash).ok().and_then(|bytes| <[u8; 32]>::try_from(bytes).ok()) {
+        Some(hash) => Ok(hash),
+        None => anyhow::bail!("Invalid --fail-on-redaction-miss hash {:?}, expected 64 hex digits", hash),
+    }
+}
+
+fn parse_line_range(range_str: &str) -> Result<LineRange> {
+    let Some((start, end)) = range_str.split_once('-') else {
+        anyhow::bail!("Invalid line range {:?}, expected START-END", range_str);
+    };
+    let range = LineRange {
+        start: start.parse()?,
+        end: end.parse()?,
+    };
+    if range.start == 0 || range.start > range.end {
+        anyhow::bail!("Invalid line range {:?}: lines start at 1 and START must not exceed END", range_str);
+    }
+    Ok(range)
+}
+
+/// Reads redaction ranges from a JSON array of `RedactionRange` objects, rejecting
+/// any entry whose start comes after its end. Rules are numbered from 1 in errors.
+fn read_redaction_file(path: &str) -> Result<Vec<RedactionRange>> {
+    let ranges: Vec<RedactionRange> = match serde_json::from_slice(&fs::read(path)?) {
+        Ok(ranges) => ranges,
+        Err(e) => anyhow::bail!("Invalid redaction file {}: {}", path, e),
+    };
+    check_redaction_rules(&ranges, path)?;
     Ok(ranges)
-}
\ No newline at end of file
+}
+
+/// Ranges of `profile` in a JSON object of profile names to redaction ranges.
+fn read_redaction_profile(path: &str, profile: &str) -> Result<Vec<RedactionRange>> {
+    let mut profiles: BTreeMap<String, Vec<RedactionRange>> =
+        match serde_json::from_slice(&fs::read(path)?) {
+            Ok(profiles) => profiles,
+            Err(e) => anyhow::bail!("Invalid profile file {}: {}", path, e),
+        };
+    let Some(ranges) = profiles.remove(profile) else {
+        let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+        anyhow::bail!(
+            "No redaction profile {} in {}; it defines: {}",
+            profile,
+            path,
+            names.join(", ")
+        );
+    };
+    check_redaction_rules(&ranges, &format!("profile {} of {}", profile, path))?;
+    Ok(ranges)
+}
+
+/// Rejects rules whose start comes after their end, naming the rule by its 1-based
+/// position in `source`.
+fn check_redaction_rules(ranges: &[RedactionRange], source: &str) -> Result<()> {
+    for (i, range) in ranges.iter().enumerate() {
+        if range.start_line > range.end_line {
+            anyhow::bail!(
+                "Redaction rule {} in {}: start_line {} is after end_line {}",
+                i + 1,
+                source,
+                range.start_line,
+                range.end_line
+            );
+        }
+        if let (Some(start_col), Some(end_col)) = (range.start_col, range.end_col) {
+            if start_col > end_col {
+                anyhow::bail!(
+                    "Redaction rule {} in {}: start_col {} is after end_col {}",
+                    i + 1,
+                    source,
+                    start_col,
+                    end_col
+                );
+            }
+        }
+    }
+    Ok(())
+}
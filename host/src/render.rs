@@ -0,0 +1,887 @@
+use std::mem;
+
+use zkdiff::{
+    diff_hunks, group_hunks, retain_operations, DiffLine, DiffOperation, DiffOutput, FileDiff,
+    FileStatus, Hunk, LengthUnit, MergeStatus, RedactedSpan,
+};
+
+const NO_NEWLINE_MARKER: &str = "\\ No newline at end of file";
+/// Width of the line number before each side-by-side column.
+const LINE_NUMBER_WIDTH: usize = 5;
+const ESCAPE: char = '\x1b';
+
+/// ANSI styles for terminal output. Each one resets only its own attribute, so a
+/// dimmed placeholder can sit inside a colored line.
+#[derive(Debug, Clone, Copy)]
+pub struct Colors {
+    enabled: bool,
+}
+
+impl Colors {
+    pub fn new(enabled: bool) -> Self {
+        Colors { enabled }
+    }
+
+    fn paint(self, text: &str, start: &str, end: &str) -> String {
+        if self.enabled && !text.is_empty() {
+            format!("\x1b[{}m{}\x1b[{}m", start, text, end)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn deleted(self, text: &str) -> String {
+        self.paint(text, "31", "39")
+    }
+
+    fn inserted(self, text: &str) -> String {
+        self.paint(text, "32", "39")
+    }
+
+    fn redacted(self, text: &str) -> String {
+        self.paint(text, "2", "22")
+    }
+
+    fn file_header(self, text: &str) -> String {
+        self.paint(text, "1", "22")
+    }
+
+    fn hunk_header(self, text: &str) -> String {
+        self.paint(text, "36", "39")
+    }
+}
+
+/// Plural name of the unit redacted lengths are counted in.
+pub fn unit_name(unit: LengthUnit) -> &'static str {
+    match unit {
+        LengthUnit::Bytes => "bytes",
+        LengthUnit::Chars => "chars",
+        LengthUnit::Graphemes => "graphemes",
+    }
+}
+
+// a salted output holds tags rather than lengths in `redacted_length`
+fn unified_marker(output: &DiffOutput, colors: Colors) -> impl Fn(usize) -> String {
+    let (unit, salted) = (output.length_unit, output.salted_redaction);
+    move |length| {
+        colors.redacted(&if salted {
+            format!("[redacted #{:08x}]", length)
+        } else {
+            format!("[redacted {} {}]", length, unit_name(unit))
+        })
+    }
+}
+
+fn block_marker(output: &DiffOutput, colors: Colors) -> impl Fn(usize) -> String {
+    let (unit, salted) = (output.length_unit, output.salted_redaction);
+    move |length| {
+        colors.redacted(&if salted {
+            format!("███ (#{:08x})", length)
+        } else {
+            format!("███ ({} {})", length, unit_name(unit))
+        })
+    }
+}
+
+/// Placeholder for a collapsed run of `lines` wholly redacted lines, `total` being
+/// the sum of their committed lengths, which isn't meaningful when salted.
+fn unified_run_marker(output: &DiffOutput, colors: Colors) -> impl Fn(usize, usize) -> String {
+    let (unit, salted) = (output.length_unit, output.salted_redaction);
+    move |lines, total| {
+        colors.redacted(&if salted {
+            format!("[redacted {} lines]", lines)
+        } else {
+            format!("[redacted {} lines, {} {} total]", lines, total, unit_name(unit))
+        })
+    }
+}
+
+fn block_run_marker(output: &DiffOutput, colors: Colors) -> impl Fn(usize, usize) -> String {
+    let (unit, salted) = (output.length_unit, output.salted_redaction);
+    move |lines, total| {
+        colors.redacted(&if salted {
+            format!("███ ({} lines)", lines)
+        } else {
+            format!("███ ({} lines, {} {} total)", lines, total, unit_name(unit))
+        })
+    }
+}
+
+/// Length of the run of wholly redacted lines of one operation that `lines` starts
+/// with, both sides hidden for replaced lines. Runs of one line aren't collapsed, so
+/// this is 0 for those.
+fn redacted_run(lines: &[&DiffLine]) -> usize {
+    let hidden = |line: &DiffLine| {
+        let old = line.content.is_none() && line.redacted_length.is_some();
+        let new = line.new_content.is_none() && line.new_redacted_length.is_some();
+        match line.operation {
+            DiffOperation::Delete | DiffOperation::Insert | DiffOperation::Context => old,
+            DiffOperation::Replace => old && new,
+            DiffOperation::Move | DiffOperation::NoNewline | DiffOperation::Any => false,
+        }
+    };
+    let Some(first) = lines.first().filter(|line| hidden(line)) else {
+        return 0;
+    };
+    let run = lines
+        .iter()
+        .take_while(|line| line.operation == first.operation && hidden(line))
+        .count();
+    if run > 1 {
+        run
+    } else {
+        0
+    }
+}
+
+/// Summed committed lengths of the old and the new side of `run`.
+fn run_totals(run: &[&DiffLine]) -> (usize, usize) {
+    let old = run.iter().filter_map(|line| line.redacted_length).sum();
+    let new = run.iter().filter_map(|line| line.new_redacted_length).sum();
+    (old, new)
+}
+
+fn old_text(line: &DiffLine, marker: &dyn Fn(usize) -> String) -> String {
+    let text = line_text(&line.content, line.redacted_length, &line.redacted_spans, marker);
+    with_truncation(text, line.full_length)
+}
+
+fn new_text(line: &DiffLine, marker: &dyn Fn(usize) -> String) -> String {
+    let text =
+        line_text(&line.new_content, line.new_redacted_length, &line.new_redacted_spans, marker);
+    with_truncation(text, line.new_full_length)
+}
+
+/// Marks where the committed text of a truncated side stops.
+fn with_truncation(text: String, full_length: Option<usize>) -> String {
+    match full_length {
+        Some(length) => format!("{}… [truncated, {} bytes]", text, length),
+        None => text,
+    }
+}
+
+fn line_text(
+    content: &Option<String>,
+    redacted_length: Option<usize>,
+    redacted_spans: &Option<Vec<RedactedSpan>>,
+    marker: &dyn Fn(usize) -> String,
+) -> String {
+    match (content, redacted_length, redacted_spans) {
+        (Some(content), _, Some(spans)) => with_span_markers(content, spans, marker),
+        (Some(content), _, None) => content.clone(),
+        (None, Some(length), _) => marker(length),
+        (None, None, _) => String::new(),
+    }
+}
+
+/// Puts a placeholder back at each hidden span of a partially redacted line.
+fn with_span_markers(
+    content: &str,
+    spans: &[RedactedSpan],
+    marker: &dyn Fn(usize) -> String,
+) -> String {
+    let mut out = String::new();
+    let mut chars = content.chars();
+    let mut pos = 0;
+    for span in spans {
+        out.extend(chars.by_ref().take(span.start - pos));
+        out.push_str(&marker(span.redacted_length));
+        pos = span.end;
+    }
+    out.extend(chars);
+    out
+}
+
+fn flush_changes(out: &mut String, removed: &mut Vec<String>, added: &mut Vec<String>) {
+    for text in removed.drain(..).chain(added.drain(..)) {
+        out.push_str(&text);
+        out.push('\n');
+    }
+}
+
+/// Unified diff convention: an empty range is addressed by the line before it.
+fn hunk_range(start: usize, count: usize) -> String {
+    if count == 0 {
+        format!("{},0", start.saturating_sub(1))
+    } else {
+        format!("{},{}", start, count)
+    }
+}
+
+/// `output` as if file A and file B had been swapped when it was proven: insertions
+/// become deletions and the old and new text of replaced lines trade places. This is
+/// only a view for rendering, so `proof_hash` no longer matches it. Hunk headers
+/// name file A lines and are dropped; committed hunks are regrouped.
+pub fn reversed(output: &DiffOutput) -> DiffOutput {
+    let mut output = output.clone();
+    reverse(&mut output);
+    output
+}
+
+fn reverse(output: &mut DiffOutput) {
+    mem::swap(&mut output.file_a_hash, &mut output.file_b_hash);
+    mem::swap(&mut output.no_newline_a, &mut output.no_newline_b);
+    mem::swap(&mut output.range_a, &mut output.range_b);
+    mem::swap(&mut output.merkle_root_a, &mut output.merkle_root_b);
+    output.hunk_pattern = None;
+    output.hunk_headers.clear();
+    if let Some(stats) = &mut output.stats {
+        mem::swap(&mut stats.inserted, &mut stats.deleted);
+    }
+    for chunk in &mut output.merge_chunks {
+        mem::swap(&mut chunk.ours_start, &mut chunk.theirs_start);
+        mem::swap(&mut chunk.ours_count, &mut chunk.theirs_count);
+        mem::swap(&mut chunk.ours_lines, &mut chunk.theirs_lines);
+        chunk.status = match chunk.status {
+            MergeStatus::Ours => MergeStatus::Theirs,
+            MergeStatus::Theirs => MergeStatus::Ours,
+            status => status,
+        };
+    }
+
+    for line in &mut output.diff_lines {
+        mem::swap(&mut line.line_number_a, &mut line.line_number_b);
+        mem::swap(&mut line.merkle_path_a, &mut line.merkle_path_b);
+        line.operation = reversed_operation(&line.operation);
+        // one-sided lines keep their text in `content` either way
+        if line.operation == DiffOperation::Replace {
+            mem::swap(&mut line.content, &mut line.new_content);
+            mem::swap(&mut line.redacted_length, &mut line.new_redacted_length);
+            mem::swap(&mut line.eol, &mut line.new_eol);
+            mem::swap(&mut line.redacted_spans, &mut line.new_redacted_spans);
+            mem::swap(&mut line.full_length, &mut line.new_full_length);
+        }
+        for edit in line.word_edits.iter_mut().chain(line.char_edits.iter_mut()).flatten() {
+            mem::swap(&mut edit.old_start, &mut edit.new_start);
+            mem::swap(&mut edit.old_end, &mut edit.new_end);
+            edit.operation = reversed_operation(&edit.operation);
+        }
+    }
+    // deletions come before insertions within a change, as in a forward diff; runs
+    // are split at hunk boundaries so no line moves into another hunk
+    let hunk_sizes: Vec<usize> =
+        group_hunks(&output.diff_lines).iter().map(|hunk| hunk.lines.len()).collect();
+    let one_sided = |line: &DiffLine| {
+        matches!(line.operation, DiffOperation::Insert | DiffOperation::Delete)
+    };
+    let mut rest = output.diff_lines.as_mut_slice();
+    for size in hunk_sizes {
+        let (hunk, after) = rest.split_at_mut(size);
+        for run in hunk.chunk_by_mut(|x, y| one_sided(x) && one_sided(y)) {
+            run.sort_by_key(|line| line.operation == DiffOperation::Insert);
+        }
+        rest = after;
+    }
+    if !output.hunks.is_empty() {
+        output.hunks = diff_hunks(&output.diff_lines);
+    }
+
+    for file in &mut output.files {
+        file.status = match file.status {
+            FileStatus::Added => FileStatus::Removed,
+            FileStatus::Removed => FileStatus::Added,
+            status => status,
+        };
+        reverse(&mut file.output);
+    }
+}
+
+/// `output` with only the diff lines, in every file, whose operation is one of
+/// `operations`, and the missing newline markers after them. Like `reversed` this is
+/// only a view for rendering. Hunk headers belong to the hunks before filtering and
+/// are dropped; committed hunks are regrouped.
+pub fn only(output: &DiffOutput, operations: &[DiffOperation]) -> DiffOutput {
+    let mut output = output.clone();
+    keep_only(&mut output, operations);
+    output
+}
+
+fn keep_only(output: &mut DiffOutput, operations: &[DiffOperation]) {
+    retain_operations(&mut output.diff_lines, |operation| operations.contains(operation));
+    output.hunk_pattern = None;
+    output.hunk_headers.clear();
+    if !output.hunks.is_empty() {
+        output.hunks = diff_hunks(&output.diff_lines);
+    }
+    for file in &mut output.files {
+        keep_only(&mut file.output, operations);
+    }
+}
+
+fn reversed_operation(operation: &DiffOperation) -> DiffOperation {
+    match operation {
+        DiffOperation::Insert => DiffOperation::Delete,
+        DiffOperation::Delete => DiffOperation::Insert,
+        other => other.clone(),
+    }
+}
+
+/// Renders the diff in `diff -u` format. Redacted lines keep their slot with a
+/// placeholder so hunk offsets stay valid. In directory mode each changed file gets
+/// its own headers, with `/dev/null` standing in for a missing side. Without
+/// `line_numbers` the hunk headers are a bare `@@`. Unless `expand_redactions`,
+/// a run of wholly redacted lines of one operation is shown as a single line.
+pub fn unified(
+    output: &DiffOutput,
+    line_numbers: bool,
+    expand_redactions: bool,
+    colors: Colors,
+) -> String {
+    let view = View { line_numbers, expand_redactions, colors };
+    let mut out = String::new();
+    if output.files.is_empty() {
+        unified_file(&mut out, "a", "b", output, view);
+    }
+    for file in &output.files {
+        if file.status != FileStatus::Unchanged {
+            let (old_name, new_name) = file_names(file);
+            unified_file(&mut out, &old_name, &new_name, &file.output, view);
+        }
+    }
+    out
+}
+
+/// What `unified` was asked to show.
+#[derive(Clone, Copy)]
+struct View {
+    line_numbers: bool,
+    expand_redactions: bool,
+    colors: Colors,
+}
+
+/// Header names of both sides of a file in directory mode.
+fn file_names(file: &FileDiff) -> (String, String) {
+    let old_name = match file.status {
+        FileStatus::Added => "/dev/null".to_string(),
+        _ => format!("a/{}", file.path),
+    };
+    let new_name = match file.status {
+        FileStatus::Removed => "/dev/null".to_string(),
+        _ => format!("b/{}", file.path),
+    };
+    (old_name, new_name)
+}
+
+fn unified_file(
+    out: &mut String,
+    old_name: &str,
+    new_name: &str,
+    output: &DiffOutput,
+    view: View,
+) {
+    let colors = view.colors;
+    out.push_str(&format!("{}\n", colors.file_header(&format!("--- {}", old_name))));
+    out.push_str(&format!("{}\n", colors.file_header(&format!("+++ {}", new_name))));
+    let marker = unified_marker(output, colors);
+    let run_marker = unified_run_marker(output, colors);
+    let run_marker: Option<&dyn Fn(usize, usize) -> String> =
+        (!view.expand_redactions).then_some(&run_marker);
+
+    for hunk in group_hunks(&output.diff_lines) {
+        let context = hunk_context(output, &hunk);
+        let new_start = view.line_numbers.then_some(hunk.new_start);
+        unified_hunk(out, &hunk, new_start, context, &marker, run_marker, colors);
+    }
+}
+
+/// Renders the visible part of the diff as a patch `git apply` accepts. Hunks with
+/// any redacted line can't be reproduced, so they are left out and listed in a
+/// comment at the top, where `git apply` ignores text, and the file B ranges of the
+/// hunks after them are shifted to match. Single-file proofs don't record a file
+/// name, so `path` is used for both sides.
+pub fn patch(output: &DiffOutput, path: &str) -> String {
+    let mut omitted = Vec::new();
+    let mut out = String::new();
+    if output.files.is_empty() {
+        patch_file(&mut out, &mut omitted, &format!("a/{}", path), &format!("b/{}", path), output);
+    }
+    for file in &output.files {
+        if file.status != FileStatus::Unchanged {
+            let (old_name, new_name) = file_names(file);
+            patch_file(&mut out, &mut omitted, &old_name, &new_name, &file.output);
+        }
+    }
+
+    let mut header = String::new();
+    for (name, old_range, new_range) in &omitted {
+        header.push_str(&format!(
+            "# zkdiff: omitted hunk @@ -{} +{} @@ of {}, it has redacted or truncated lines\n",
+            old_range, new_range, name
+        ));
+    }
+    if !omitted.is_empty() {
+        header.push('\n');
+    }
+    header + &out
+}
+
+fn patch_file(
+    out: &mut String,
+    omitted: &mut Vec<(String, String, String)>,
+    old_name: &str,
+    new_name: &str,
+    output: &DiffOutput,
+) {
+    // a patch is written to a file, never colored
+    let colors = Colors::new(false);
+    let marker = unified_marker(output, colors);
+    let mut body = String::new();
+    // lines file B gained in omitted hunks, which the kept ones no longer account for
+    let mut shift: isize = 0;
+    for hunk in group_hunks(&output.diff_lines) {
+        if hunk.lines.iter().any(|line| is_redacted(line) || line.truncated) {
+            omitted.push((
+                new_name.to_string(),
+                hunk_range(hunk.old_start, hunk.old_count),
+                hunk_range(hunk.new_start, hunk.new_count),
+            ));
+            shift += hunk.new_count as isize - hunk.old_count as isize;
+            continue;
+        }
+        let new_start = (hunk.new_start as isize - shift) as usize;
+        let context = hunk_context(output, &hunk);
+        unified_hunk(&mut body, &hunk, Some(new_start), context, &marker, None, colors);
+    }
+
+    if !body.is_empty() {
+        out.push_str(&format!("--- {}\n", old_name));
+        out.push_str(&format!("+++ {}\n", new_name));
+        out.push_str(&body);
+    }
+}
+
+fn is_redacted(line: &DiffLine) -> bool {
+    line.redacted_length.is_some()
+        || line.new_redacted_length.is_some()
+        || line.redacted_spans.is_some()
+        || line.new_redacted_spans.is_some()
+}
+
+/// Enclosing line committed for `hunk`, when the proof has hunk headers.
+fn hunk_context<'a>(output: &'a DiffOutput, hunk: &Hunk) -> Option<&'a str> {
+    output
+        .hunk_headers
+        .iter()
+        .find(|header| header.old_start == hunk.old_start && header.new_start == hunk.new_start)
+        .and_then(|header| header.hunk_context.as_deref())
+}
+
+/// Writes one hunk, header included, with its file B range starting at `new_start`
+/// and `context` after the ranges like git's function names. Without `new_start`
+/// the header has no ranges. Runs of redacted lines are collapsed when there is a
+/// `run_marker`.
+fn unified_hunk(
+    out: &mut String,
+    hunk: &Hunk,
+    new_start: Option<usize>,
+    context: Option<&str>,
+    marker: &dyn Fn(usize) -> String,
+    run_marker: Option<&dyn Fn(usize, usize) -> String>,
+    colors: Colors,
+) {
+    let ranges = match new_start {
+        Some(new_start) => format!(
+            "@@ -{} +{} @@",
+            hunk_range(hunk.old_start, hunk.old_count),
+            hunk_range(new_start, hunk.new_count),
+        ),
+        None => "@@".to_string(),
+    };
+    out.push_str(&format!(
+        "{}{}\n",
+        colors.hunk_header(&ranges),
+        context.map(|context| format!(" {}", context)).unwrap_or_default(),
+    ));
+
+    // changes are printed as all removed lines then all added lines, between runs of
+    // context
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let mut after_context = false;
+    let mut skip = 0;
+    for (i, line) in hunk.lines.iter().enumerate() {
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+        if let Some(run_marker) = run_marker {
+            let run = redacted_run(&hunk.lines[i..]);
+            if run > 0 {
+                let (total_old, total_new) = run_totals(&hunk.lines[i..i + run]);
+                let (old, new) = (run_marker(run, total_old), run_marker(run, total_new));
+                match line.operation {
+                    DiffOperation::Context => {
+                        flush_changes(out, &mut removed, &mut added);
+                        out.push_str(&format!(" {}\n", old));
+                    }
+                    DiffOperation::Delete => removed.push(colors.deleted(&format!("-{}", old))),
+                    DiffOperation::Insert => added.push(colors.inserted(&format!("+{}", old))),
+                    _ => {
+                        removed.push(colors.deleted(&format!("-{}", old)));
+                        added.push(colors.inserted(&format!("+{}", new)));
+                    }
+                }
+                after_context = line.operation == DiffOperation::Context;
+                skip = run - 1;
+                continue;
+            }
+        }
+        match line.operation {
+            DiffOperation::Context => {
+                flush_changes(out, &mut removed, &mut added);
+                out.push_str(&format!(" {}\n", old_text(line, marker)));
+                after_context = true;
+                continue;
+            }
+            // an unchanged last line takes a single marker even when it is missing
+            // from both files
+            DiffOperation::NoNewline if after_context => {
+                if !out.ends_with(&format!("{}\n", NO_NEWLINE_MARKER)) {
+                    out.push_str(&format!("{}\n", NO_NEWLINE_MARKER));
+                }
+                continue;
+            }
+            // a move is a plain removal and addition in a patch
+            DiffOperation::Delete => {
+                removed.push(colors.deleted(&format!("-{}", old_text(line, marker))))
+            }
+            DiffOperation::Move if line.line_number_a.is_some() => {
+                removed.push(colors.deleted(&format!("-{}", old_text(line, marker))))
+            }
+            DiffOperation::Insert | DiffOperation::Move => {
+                added.push(colors.inserted(&format!("+{}", old_text(line, marker))))
+            }
+            DiffOperation::Replace => {
+                removed.push(colors.deleted(&format!("-{}", old_text(line, marker))));
+                added.push(colors.inserted(&format!("+{}", new_text(line, marker))));
+            }
+            // the marker follows the last line of its file, which is always the last
+            // line of that side in the hunk
+            DiffOperation::NoNewline if marks_file_a(&hunk.lines, i) => {
+                removed.push(NO_NEWLINE_MARKER.to_string());
+            }
+            DiffOperation::NoNewline => added.push(NO_NEWLINE_MARKER.to_string()),
+            DiffOperation::Any => {}
+        }
+        after_context = false;
+    }
+    flush_changes(out, &mut removed, &mut added);
+}
+
+/// Whether the missing newline marker at `lines[i]` is file A's. One committed
+/// without numbers follows a hidden line: after a deletion it is file A's, after an
+/// insertion file B's, and after a replaced line the first of two is file A's and a
+/// lone one is taken as file B's.
+fn marks_file_a(lines: &[&DiffLine], i: usize) -> bool {
+    let line = lines[i];
+    if line.line_number_a.is_some() || line.line_number_b.is_some() {
+        return line.line_number_a.is_some();
+    }
+    let before = lines[..i].iter().rposition(|line| line.operation != DiffOperation::NoNewline);
+    match before.map(|j| (&lines[j].operation, i - j)) {
+        Some((DiffOperation::Delete, _)) => true,
+        Some((DiffOperation::Replace, 1)) => {
+            lines.get(i + 1).is_some_and(|next| next.operation == DiffOperation::NoNewline)
+        }
+        _ => false,
+    }
+}
+
+/// Renders a merge-mode proof the way git leaves a merged file. Unchanged regions
+/// aren't committed with their lines, so each is only a note; every other region is
+/// headed by its base lines and shows the lines the merge takes, or for a conflict
+/// both sides between `<<<<<<< ours`, `=======` and `>>>>>>> theirs`.
+pub fn merge(output: &DiffOutput, colors: Colors) -> String {
+    let mut out = String::new();
+    for chunk in &output.merge_chunks {
+        let taken = match chunk.status {
+            MergeStatus::Unchanged => "unchanged",
+            MergeStatus::Ours => "from ours",
+            MergeStatus::Theirs => "from theirs",
+            MergeStatus::Both => "changed alike on both sides",
+            MergeStatus::Conflict => "conflict",
+        };
+        let span = line_span(chunk.base_start, chunk.base_count);
+        let note = format!("@@ base {}: {} @@", span, taken);
+        out.push_str(&format!("{}\n", colors.hunk_header(&note)));
+        let text = |lines: &[String]| -> String {
+            lines.iter().map(|line| format!("{}\n", line)).collect()
+        };
+        match chunk.status {
+            MergeStatus::Unchanged => {}
+            MergeStatus::Ours | MergeStatus::Both => out.push_str(&text(&chunk.ours_lines)),
+            MergeStatus::Theirs => out.push_str(&text(&chunk.theirs_lines)),
+            MergeStatus::Conflict => {
+                out.push_str(&format!("{}\n", colors.file_header("<<<<<<< ours")));
+                out.push_str(&text(&chunk.ours_lines));
+                out.push_str(&format!("{}\n", colors.file_header("=======")));
+                out.push_str(&text(&chunk.theirs_lines));
+                out.push_str(&format!("{}\n", colors.file_header(">>>>>>> theirs")));
+            }
+        }
+    }
+    out
+}
+
+/// Lists the changed lines without their content, like `-12 +13 ~40-45`: `-` for
+/// lines of file A deleted, `+` for lines of file B inserted and `~` for lines of
+/// file A replaced, with consecutive lines of one kind joined into a range. Moved
+/// lines count as deleted and inserted, and a line committed without its number
+/// is `?`. In directory mode each changed file gets its own line, led by its path.
+pub fn names_only(output: &DiffOutput) -> String {
+    if output.files.is_empty() {
+        let names = changed_line_names(&output.diff_lines);
+        return if names.is_empty() { String::new() } else { format!("{}\n", names) };
+    }
+    let mut out = String::new();
+    for file in &output.files {
+        let names = changed_line_names(&file.output.diff_lines);
+        if !names.is_empty() {
+            out.push_str(&format!("{}: {}\n", file.path, names));
+        }
+    }
+    out
+}
+
+fn changed_line_names(lines: &[DiffLine]) -> String {
+    // runs of one sign over consecutive line numbers, or a single unnumbered line
+    let mut runs: Vec<(char, Option<(usize, usize)>)> = Vec::new();
+    for line in lines {
+        let (sign, number) = match line.operation {
+            DiffOperation::Delete => ('-', line.line_number_a),
+            DiffOperation::Insert => ('+', line.line_number_b),
+            DiffOperation::Replace => ('~', line.line_number_a),
+            DiffOperation::Move if line.line_number_a.is_some() => ('-', line.line_number_a),
+            DiffOperation::Move => ('+', line.line_number_b),
+            DiffOperation::Context | DiffOperation::NoNewline | DiffOperation::Any => continue,
+        };
+        match (runs.last_mut(), number) {
+            (Some((last, Some((_, end)))), Some(n)) if *last == sign && *end + 1 == n => *end = n,
+            _ => runs.push((sign, number.map(|n| (n, n)))),
+        }
+    }
+    let names: Vec<String> = runs
+        .iter()
+        .map(|(sign, span)| match span {
+            Some((start, end)) if start == end => format!("{}{}", sign, start),
+            Some((start, end)) => format!("{}{}-{}", sign, start, end),
+            None => format!("{}?", sign),
+        })
+        .collect();
+    names.join(" ")
+}
+
+/// `count` lines from `start` in words; an empty span is named by the line after it.
+fn line_span(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("before line {}", start),
+        1 => format!("line {}", start),
+        _ => format!("lines {}-{}", start, start + count - 1),
+    }
+}
+
+/// Renders the diff as two columns, file A on the left and file B on the right,
+/// each `width` characters wide. The gutter between them marks the change like
+/// `diff -y`: `|` replaced, `<` deleted, `>` inserted. Hunks are separated by a
+/// rule, and in directory mode each changed file gets its own header row. Without
+/// `line_numbers` the columns aren't numbered, and unless `expand_redactions` a run
+/// of wholly redacted lines of one operation takes a single row.
+pub fn side_by_side(
+    output: &DiffOutput,
+    width: usize,
+    line_numbers: bool,
+    expand_redactions: bool,
+    colors: Colors,
+) -> String {
+    let mut out = String::new();
+    let columns = Columns { width, line_numbers, expand_redactions, colors };
+    if output.files.is_empty() {
+        side_by_side_file(&mut out, "a", "b", output, columns);
+    }
+    for file in &output.files {
+        if file.status != FileStatus::Unchanged {
+            let (old_name, new_name) = file_names(file);
+            side_by_side_file(&mut out, &old_name, &new_name, &file.output, columns);
+        }
+    }
+    out
+}
+
+/// Width, numbering, redaction runs and colors of the side-by-side columns.
+#[derive(Clone, Copy)]
+struct Columns {
+    width: usize,
+    line_numbers: bool,
+    expand_redactions: bool,
+    colors: Colors,
+}
+
+fn side_by_side_file(
+    out: &mut String,
+    old_name: &str,
+    new_name: &str,
+    output: &DiffOutput,
+    columns: Columns,
+) {
+    push_row(out, None, old_name, ' ', None, new_name, columns);
+    let marker = block_marker(output, columns.colors);
+    let run_marker = block_run_marker(output, columns.colors);
+
+    for (i, hunk) in group_hunks(&output.diff_lines).iter().enumerate() {
+        if i > 0 {
+            // spans both line numbers, both columns and the gutter
+            let number_width = if columns.line_numbers { LINE_NUMBER_WIDTH + 1 } else { 0 };
+            let rule_width = 2 * (number_width + columns.width) + 4;
+            out.push_str(&format!("{}\n", "-".repeat(rule_width)));
+        }
+        let mut skip = 0;
+        for (i, line) in hunk.lines.iter().enumerate() {
+            let (a, b) = (line.line_number_a, line.line_number_b);
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let run = if columns.expand_redactions { 0 } else { redacted_run(&hunk.lines[i..]) };
+            if run > 0 {
+                // one row for the run, numbered by its first line
+                let (total_old, total_new) = run_totals(&hunk.lines[i..i + run]);
+                let (old, new) = (run_marker(run, total_old), run_marker(run, total_new));
+                match line.operation {
+                    DiffOperation::Context => push_row(out, a, &old, ' ', b, &old, columns),
+                    DiffOperation::Delete => push_row(out, a, &old, '<', None, "", columns),
+                    DiffOperation::Insert => push_row(out, None, "", '>', b, &old, columns),
+                    _ => push_row(out, a, &old, '|', b, &new, columns),
+                }
+                skip = run - 1;
+                continue;
+            }
+            match line.operation {
+                DiffOperation::Context => {
+                    let text = old_text(line, &marker);
+                    push_row(out, a, &text, ' ', b, &text, columns);
+                }
+                DiffOperation::Delete => {
+                    push_row(out, a, &old_text(line, &marker), '<', None, "", columns)
+                }
+                DiffOperation::Insert => {
+                    push_row(out, None, "", '>', b, &old_text(line, &marker), columns)
+                }
+                // the other column points at where the line went or came from
+                DiffOperation::Move if a.is_some() => {
+                    let note = moved_note("to", line.moved_line, columns);
+                    push_row(out, a, &old_text(line, &marker), '<', None, &note, columns)
+                }
+                DiffOperation::Move => {
+                    let note = moved_note("from", line.moved_line, columns);
+                    push_row(out, None, &note, '>', b, &old_text(line, &marker), columns)
+                }
+                DiffOperation::Replace => push_row(
+                    out,
+                    a,
+                    &old_text(line, &marker),
+                    '|',
+                    b,
+                    &new_text(line, &marker),
+                    columns,
+                ),
+                DiffOperation::NoNewline if marks_file_a(&hunk.lines, i) => {
+                    push_row(out, None, NO_NEWLINE_MARKER, ' ', None, "", columns)
+                }
+                DiffOperation::NoNewline => {
+                    push_row(out, None, "", ' ', None, NO_NEWLINE_MARKER, columns)
+                }
+                DiffOperation::Any => {}
+            }
+        }
+    }
+}
+
+/// `(moved to 12)`, or just `(moved)` when the columns aren't numbered.
+fn moved_note(direction: &str, moved_line: Option<usize>, columns: Columns) -> String {
+    if columns.line_numbers {
+        format!("(moved {} {})", direction, moved_line.unwrap_or_default())
+    } else {
+        "(moved)".to_string()
+    }
+}
+
+/// Writes one row. The gutter decides the colors: the file A column of a `<` or `|`
+/// row is a deletion, the file B column of a `>` or `|` row an insertion. Columns
+/// are colored after fitting so escape codes never count towards the width.
+fn push_row(
+    out: &mut String,
+    number_a: Option<usize>,
+    text_a: &str,
+    gutter: char,
+    number_b: Option<usize>,
+    text_b: &str,
+    columns: Columns,
+) {
+    let Columns { width, line_numbers, colors, .. } = columns;
+    let fitted_a = fit(text_a, width);
+    let fitted_b = fit(text_b, width);
+    let (column_a, column_b) = match gutter {
+        '<' => (colors.deleted(&fitted_a), fitted_b),
+        '>' => (fitted_a, colors.inserted(&fitted_b)),
+        '|' => (colors.deleted(&fitted_a), colors.inserted(&fitted_b)),
+        _ => (fitted_a, fitted_b),
+    };
+    let padding = " ".repeat(width.saturating_sub(visible_width(&column_a)));
+    let number = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_default();
+    let row = if line_numbers {
+        format!(
+            "{:>nw$} {}{} {} {:>nw$} {}",
+            number(number_a),
+            column_a,
+            padding,
+            gutter,
+            number(number_b),
+            column_b,
+            nw = LINE_NUMBER_WIDTH,
+        )
+    } else {
+        format!("{}{} {} {}", column_a, padding, gutter, column_b)
+    };
+    out.push_str(row.trim_end());
+    out.push('\n');
+}
+
+/// Characters of `text` that take up a column, leaving out ANSI escape codes.
+fn visible_width(text: &str) -> usize {
+    let mut in_code = false;
+    text.chars()
+        .filter(|&c| {
+            in_code |= c == ESCAPE;
+            let visible = !in_code;
+            in_code &= c != 'm';
+            visible
+        })
+        .count()
+}
+
+/// Cuts `text` to at most `width` visible characters, ending in an ellipsis when
+/// shortened. Escape codes past the cut are kept, so a dimmed placeholder that gets
+/// cut still resets its style.
+fn fit(text: &str, width: usize) -> String {
+    if visible_width(text) <= width {
+        return text.to_string();
+    }
+    let limit = width.saturating_sub(1);
+    let mut fitted = String::new();
+    let mut shown = 0;
+    let mut in_code = false;
+    for c in text.chars() {
+        in_code |= c == ESCAPE;
+        if in_code {
+            fitted.push(c);
+            in_code = c != 'm';
+        } else if shown < limit {
+            fitted.push(c);
+            shown += 1;
+        } else if shown == limit {
+            fitted.push('…');
+            shown += 1;
+        }
+    }
+    fitted
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::text_input;
+use zkdiff::{
+    apply_diff, compute_diff, proof_hash_mismatch, rebuild_file_b, DiffInput, DiffOperation,
+    DiffOptions, DiffOutput, RedactionRange,
+};
+
+fn output(
+    a: &str,
+    b: &str,
+    options: DiffOptions,
+    redaction_ranges: Vec<RedactionRange>,
+) -> DiffOutput {
+    compute_diff(&DiffInput { redaction_ranges, ..text_input(a, b, options) })
+}
+
+fn redact(operation: DiffOperation, line: usize) -> RedactionRange {
+    RedactionRange { start_line: line, end_line: line, start_col: None, end_col: None, operation }
+}
+
+#[test]
+fn file_b_is_rebuilt_from_file_a_and_the_diff() {
+    let a = "one\ntwo\nthree\nfour\nfive\nsix\n";
+    let pairs = [
+        "one\nTWO\nthree\nfour\nfive\nsix\n",
+        "zero\none\nthree\nfour\nfive\nsix\nseven",
+        "",
+        "one\ntwo\nthree\nfour\nfive\nsix\nseven\n",
+        "four\nfive\nsix\none\ntwo\nthree\n",
+    ];
+    for b in pairs {
+        for context in [0, 2] {
+            let options = DiffOptions { context, detect_moves: true, ..Default::default() };
+            assert_eq!(apply_diff(&output(a, b, options, Vec::new()), a).unwrap(), b);
+        }
+    }
+    let (a, b) = ("x\r\ny\r\n", "x\r\nY\r\nz\r\n");
+    assert_eq!(apply_diff(&output(a, b, DiffOptions::default(), Vec::new()), a).unwrap(), b);
+}
+
+#[test]
+fn only_redacted_lines_file_b_needs_stop_the_rebuild() {
+    let (a, b) = ("one\ntwo\nthree\n", "one\nthree\nfour\n");
+    let deleted = output(a, b, DiffOptions::default(), vec![redact(DiffOperation::Delete, 2)]);
+    assert_eq!(apply_diff(&deleted, a).unwrap(), b);
+
+    let inserted = output(a, b, DiffOptions::default(), vec![redact(DiffOperation::Insert, 3)]);
+    let error = apply_diff(&inserted, a).unwrap_err().to_string();
+    assert!(error.contains("line 3 of file B is redacted"), "{}", error);
+}
+
+#[test]
+fn proofs_that_cant_be_replayed_are_rejected() {
+    let proof = output("one\n", "two\n", DiffOptions::default(), Vec::new());
+    let error = apply_diff(&proof, "three\n").unwrap_err().to_string();
+    assert!(error.contains("file_a_hash"), "{}", error);
+
+    let stats_only = DiffOptions { stats_only: true, ..Default::default() };
+    let proof = output("one\n", "two\n", stats_only, Vec::new());
+    assert!(apply_diff(&proof, "one\n").unwrap_err().to_string().contains("stats-only"));
+}
+
+#[test]
+fn include_unchanged_proofs_rebuild_file_b_without_file_a() {
+    let a = "one\ntwo\nthree\nfour\nfive\nsix\n";
+    let moved = DiffOptions { include_unchanged: true, detect_moves: true, ..Default::default() };
+    for b in ["one\nTWO\nthree\nfour\nfive\nsix", "four\nfive\nsix\none\ntwo\nthree\n", a, ""] {
+        assert_eq!(rebuild_file_b(&output(a, b, moved.clone(), Vec::new())).unwrap(), b);
+    }
+    let (a, b) = ("x\r\ny\n", "x\r\nY\r\ny\n");
+    let full = DiffOptions { include_unchanged: true, ..Default::default() };
+    assert_eq!(rebuild_file_b(&output(a, b, full.clone(), Vec::new())).unwrap(), b);
+
+    // only lines of file A may be hidden
+    let deleted = output(a, b, full, vec![redact(DiffOperation::Delete, 1)]);
+    assert_eq!(rebuild_file_b(&deleted).unwrap(), b);
+    let error = rebuild_file_b(&output(a, b, DiffOptions::default(), Vec::new())).unwrap_err();
+    assert!(error.to_string().contains("--include-unchanged"), "{}", error);
+}
+
+#[test]
+fn a_stored_guest_output_is_applied_as_apply_does() {
+    // hash checked as stored, as `apply` checks it before rebuilding anything
+    let output: DiffOutput = serde_json::from_str(include_str!("fixtures/json_only.json")).unwrap();
+    assert_eq!(proof_hash_mismatch(&output), None);
+    let file_b = apply_diff(&output, "one\ntwo\nthree\n").unwrap();
+    assert_eq!(file_b, "one\n2\nthree\nfour\n");
+}
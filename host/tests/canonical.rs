@@ -0,0 +1,65 @@
+mod common;
+
+use common::text_input;
+use zkdiff::{compute_diff, DiffOptions, ProofFile, ProofFormat, ProofMetrics, ZkDiffError};
+
+fn proof_file() -> ProofFile {
+    let (a, b) = ("one\ntwo\n", "one\nthree\n");
+    ProofFile {
+        verified: false,
+        output: compute_diff(&text_input(a, b, DiffOptions { context: 1, ..Default::default() })),
+        method_id: "00".repeat(32),
+        proof_generated: false,
+        zkdiff_version: None,
+        risc0_version: None,
+        receipt: None,
+        metrics: Some(ProofMetrics {
+            prove_seconds: 1.5,
+            total_cycles: 2,
+            user_cycles: 1,
+            segments: 1,
+        }),
+    }
+}
+
+/// Position of every `"key":` in `json`, in the order they are given.
+fn positions(json: &str, keys: &[&str]) -> Vec<usize> {
+    keys.iter().map(|key| json.find(&format!("\"{}\":", key)).unwrap()).collect()
+}
+
+#[test]
+fn canonical_json_sorts_keys_at_every_level() {
+    let bytes = proof_file().encode_canonical(ProofFormat::Json).unwrap();
+    let json = String::from_utf8(bytes).unwrap();
+    let top = positions(&json, &["method_id", "metrics", "output", "proof_generated", "verified"]);
+    assert!(top.windows(2).all(|pair| pair[0] < pair[1]), "{}", json);
+    let nested = positions(&json, &["prove_seconds", "segments", "total_cycles", "user_cycles"]);
+    assert!(nested.windows(2).all(|pair| pair[0] < pair[1]), "{}", json);
+}
+
+#[test]
+fn canonical_encoding_is_reproducible_and_decodes() {
+    for format in [ProofFormat::Json, ProofFormat::Bincode] {
+        let bytes = proof_file().encode_canonical(format).unwrap();
+        assert_eq!(bytes, proof_file().encode_canonical(format).unwrap());
+        let decoded = ProofFile::decode(&bytes).unwrap();
+        assert_eq!(decoded.output.proof_hash, proof_file().output.proof_hash);
+        assert_eq!(decoded.metrics, proof_file().metrics);
+    }
+}
+
+#[test]
+fn a_proof_from_another_risc0_version_names_it() {
+    let mut json = serde_json::to_value(proof_file()).unwrap();
+    json["risc0_version"] = "0.19.1".into();
+    json["receipt"] = serde_json::json!({ "inner": { "Unknown": [] } });
+    match ProofFile::decode(&serde_json::to_vec(&json).unwrap()) {
+        Err(ZkDiffError::Risc0Version { found, .. }) => assert_eq!(found, "0.19.1"),
+        other => panic!("{:?}", other),
+    }
+
+    // without a version to blame, the decoding error is reported as is
+    json.as_object_mut().unwrap().remove("risc0_version");
+    let error = ProofFile::decode(&serde_json::to_vec(&json).unwrap()).unwrap_err();
+    assert!(matches!(error, ZkDiffError::Encoding(_)), "{:?}", error);
+}
@@ -0,0 +1,26 @@
+// each test binary uses its own subset of these helpers
+#![allow(dead_code)]
+
+use zkdiff::{hash_content, DiffInput, DiffOptions};
+
+/// Input diffing text `a` against text `b` with `options`, and nothing else set.
+pub fn text_input(a: &str, b: &str, options: DiffOptions) -> DiffInput {
+    DiffInput {
+        file_a_hash: hash_content(a),
+        file_b_hash: hash_content(b),
+        file_a_content: a.to_string(),
+        file_b_content: b.to_string(),
+        file_a_bytes: Vec::new(),
+        file_b_bytes: Vec::new(),
+        redaction_ranges: Vec::new(),
+        redaction_profile: None,
+        pattern_redactions: Vec::new(),
+        hunk_anchors: None,
+        ignored_lines: None,
+        sync_anchors: None,
+        options,
+        files: Vec::new(),
+        script: None,
+        merge_base: None,
+    }
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::text_input;
+use methods::METHOD_ELF;
+use risc0_zkvm::{default_executor, ExecutorEnv};
+use zkdiff::{DiffInput, DiffOperation, DiffOptions, DiffOutput, RedactionRange};
+
+/// Runs the guest on `input` without proving, as `generate --dry-run` does.
+fn execute(input: &DiffInput) -> anyhow::Result<DiffOutput> {
+    let env = ExecutorEnv::builder().write(input)?.build()?;
+    let session = default_executor().execute(env, METHOD_ELF)?;
+    Ok(session.journal.decode()?)
+}
+
+#[test]
+fn a_redaction_miss_fails_execution_rather_than_panicking() {
+    let options = DiffOptions { expected_redaction_hash: Some([0; 32]), ..Default::default() };
+    let input = DiffInput {
+        redaction_ranges: vec![RedactionRange {
+            start_line: 2,
+            end_line: 2,
+            start_col: None,
+            end_col: None,
+            operation: DiffOperation::Insert,
+        }],
+        ..text_input("one\n", "one\nsecret\n", options)
+    };
+    let error = execute(&input).unwrap_err();
+    assert!(format!("{:#}", error).contains("Redacted text doesn't have the expected hash"));
+}
+
+#[test]
+fn the_guest_expands_tabs_before_committing() {
+    let options = DiffOptions { expand_tabs: Some(4), ..Default::default() };
+    let output = execute(&text_input("one\n", "one\n\tindented\n", options)).unwrap();
+    assert_eq!(output.diff_lines[0].content.as_deref(), Some("    indented"));
+}
@@ -0,0 +1,17 @@
+use methods::METHOD_ID;
+use risc0_zkvm::sha::Digest;
+use zkdiff::method_id_hex;
+
+#[test]
+fn method_id_matches_risc0_formatting() {
+    assert_eq!(method_id_hex(METHOD_ID), Digest::from(METHOD_ID).to_string());
+}
+
+#[test]
+fn method_id_hex_is_little_endian_per_word() {
+    let id = [0x0403_0201, 0, 0, 0, 0, 0, 0, 0x0807_0605];
+    let hex = method_id_hex(id);
+    assert_eq!(hex.len(), 64);
+    assert!(hex.starts_with("01020304"));
+    assert!(hex.ends_with("05060708"));
+}
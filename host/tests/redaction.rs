@@ -0,0 +1,126 @@
+mod common;
+
+use common::text_input;
+use zkdiff::{
+    compute_diff, parse_redaction_ranges, redaction_range_gaps, redaction_range_problems, DiffInput,
+    DiffOperation, DiffOptions, ZkDiffError,
+};
+
+fn error(redact: &str) -> String {
+    parse_redaction_ranges(redact).unwrap_err().to_string()
+}
+
+#[test]
+fn valid_ranges_parse() {
+    let ranges = parse_redaction_ranges("d:1-2,insert:3-3:4-9").unwrap();
+    let fields: Vec<_> = ranges
+        .iter()
+        .map(|r| (r.operation.clone(), r.start_line, r.end_line, r.start_col, r.end_col))
+        .collect();
+    assert_eq!(
+        fields,
+        [(DiffOperation::Delete, 1, 2, None, None), (DiffOperation::Insert, 3, 3, Some(4), Some(9))]
+    );
+    assert!(parse_redaction_ranges("").unwrap().is_empty());
+}
+
+#[test]
+fn negative_numbers_are_rejected() {
+    assert!(error("d:-1-4").contains("\"d:-1-4\": line numbers can't be negative"));
+    assert!(error("d:1--4").contains("\"d:1--4\": line numbers can't be negative"));
+    assert!(error("r:1-1:-2-3").contains("column numbers can't be negative"));
+}
+
+#[test]
+fn reversed_line_ranges_parse_and_are_reported_as_problems() {
+    let ranges = parse_redaction_ranges("d:5-2").unwrap();
+    let problems = redaction_range_problems(&ranges, 10, 10);
+    assert_eq!(problems, ["Delete:5-2: start line is after end line"]);
+    assert!(error("r:1-1:9-3").contains("\"r:1-1:9-3\": start column 9 is after end column 3"));
+}
+
+#[test]
+fn extra_colons_are_rejected() {
+    assert!(error("r:1-2:3-4:5").contains("\"r:1-2:3-4:5\": expected operation:start-end"));
+    assert!(error("d:3").contains("\"d:3\": line range \"3\" should be START-END"));
+}
+
+#[test]
+fn empty_fields_are_rejected() {
+    assert!(error("d:1-2,,i:3-4").contains("\"\": empty range"));
+    assert!(error(":1-2").contains("missing operation"));
+    assert!(error("d:-").contains("missing start line"));
+    assert!(error("d:1-").contains("missing end line"));
+    assert!(error("r:1-1:0-4").contains("columns start at 1"));
+    assert!(error("x:1-2").contains("unknown operation \"x\""));
+    assert!(error("d:a-2").contains("start line \"a\" isn't a whole number"));
+}
+
+#[test]
+fn every_malformed_range_is_reported() {
+    let message = error("d:1-2,x:1-2,d:1-b,i:4-5");
+    assert!(message.starts_with("Invalid --redact ranges: "), "{}", message);
+    assert!(message.contains("\"x:1-2\": unknown operation"), "{}", message);
+    assert!(message.contains("\"d:1-b\": end line \"b\" isn't a whole number"), "{}", message);
+    assert!(!message.contains("d:1-2\":") && !message.contains("i:4-5"), "{}", message);
+}
+
+#[test]
+fn malformed_ranges_are_a_bad_redaction_spec() {
+    match parse_redaction_ranges("d:1-2,x:1-2,d:1-b").unwrap_err() {
+        ZkDiffError::BadRedactionSpec(problems) => assert_eq!(problems.len(), 2, "{:?}", problems),
+        error => panic!("{:?}", error),
+    }
+}
+
+#[test]
+fn any_ranges_hide_lines_however_they_are_shown() {
+    let ranges = parse_redaction_ranges("any:2-4:1-3,a:1-1").unwrap();
+    let operations: Vec<_> = ranges.iter().map(|r| (r.operation.clone(), r.start_col)).collect();
+    assert_eq!(operations, [(DiffOperation::Any, Some(1)), (DiffOperation::Any, None)]);
+
+    // line 2 of file A is deleted, line 3 of file B inserted
+    let (a, b) = ("one\ntwo\nthree\n", "one\nthree\nfour\n");
+    let input = DiffInput {
+        redaction_ranges: parse_redaction_ranges("any:2-3").unwrap(),
+        ..text_input(a, b, DiffOptions { context: 1, ..Default::default() })
+    };
+    let output = compute_diff(&input);
+    let hidden: Vec<_> = output
+        .diff_lines
+        .iter()
+        .map(|line| (line.operation.clone(), line.redacted_length.is_some()))
+        .collect();
+    assert_eq!(
+        hidden,
+        [
+            (DiffOperation::Context, false),
+            (DiffOperation::Delete, true),
+            (DiffOperation::Context, true),
+            (DiffOperation::Insert, true),
+        ]
+    );
+    assert!(redaction_range_problems(&input.redaction_ranges, 3, 3).is_empty());
+    let outside = redaction_range_problems(&parse_redaction_ranges("a:2-5").unwrap(), 3, 4);
+    assert_eq!(outside, ["Any:2-5: outside both files, which have 3 and 4 lines"]);
+}
+
+#[test]
+fn ranges_missing_the_lines_the_diff_shows_are_reported() {
+    let (a, b) = ("one\ntwo\nthree\nfour\n", "one\nTWO\nthree\nfour\n");
+    let context = DiffOptions { context: 1, ..Default::default() };
+    let gaps = |redact, options: &DiffOptions| {
+        redaction_range_gaps(&parse_redaction_ranges(redact).unwrap(), a, b, options)
+    };
+    assert_eq!(
+        gaps("d:1-4", &context),
+        [
+            "Delete:1-4: lines 1, 3 of file A are shown in full as Context, which the range doesn't hide",
+            "Delete:1-4: line 4 of file A is unchanged and not in the diff, so there is nothing to hide",
+        ]
+    );
+    assert!(gaps("any:1-3", &context).is_empty());
+    let replaced = &gaps("c:1-2", &DiffOptions::default())[0];
+    assert!(replaced.contains("line 2 of file A is shown in full as Replace"), "{}", replaced);
+    assert!(gaps("i:2-4", &DiffOptions::default()).iter().all(|gap| gap.contains("unchanged")));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use std::collections::BTreeSet;
+
+use common::text_input;
+use serde_json::Value;
+use zkdiff::{
+    compute_diff, hash_content, proof_file_schema, DiffInput, DiffOperation, DiffOptions,
+    Granularity, MergeBase, ProofFile, ProofMetrics, RedactionRange,
+};
+
+const FILE_A: &str = "one\r\ntwo\r\nthree\r\nfour\r\nfive\r\nsix\r\n";
+const FILE_B: &str = "one\r\ntwo words\r\nthree\r\nfour\r\nsecret column\r\nsix\r\nseven";
+
+fn input(options: DiffOptions) -> DiffInput {
+    DiffInput {
+        redaction_ranges: vec![RedactionRange {
+            start_line: 5,
+            end_line: 5,
+            start_col: Some(1),
+            end_col: Some(6),
+            operation: DiffOperation::Replace,
+        }],
+        redaction_profile: Some("default".to_string()),
+        ..text_input(FILE_A, FILE_B, options)
+    }
+}
+
+/// Proof files that between them set every optional part of the output.
+fn proof_files() -> Vec<Value> {
+    let rich = DiffOptions {
+        granularity: Granularity::Word,
+        normalize_eol: true,
+        context: 1,
+        merkle: true,
+        expand_tabs: Some(4),
+        hunks: true,
+        ..Default::default()
+    };
+    let mut merge = input(DiffOptions::default());
+    merge.redaction_ranges.clear();
+    merge.merge_base = Some(MergeBase { hash: hash_content("one\r\n"), content: "one\r\n".into() });
+    let stats_only = DiffOptions { stats_only: true, ..Default::default() };
+    let inputs = [input(rich), input(stats_only), merge];
+    inputs
+        .iter()
+        .map(compute_diff)
+        .map(|output| {
+            let proof_file = ProofFile {
+                verified: false,
+                output,
+                method_id: "00".repeat(32),
+                proof_generated: false,
+                zkdiff_version: None,
+                risc0_version: None,
+                receipt: None,
+                metrics: Some(ProofMetrics {
+                    prove_seconds: 1.5,
+                    total_cycles: 2,
+                    user_cycles: 1,
+                    segments: 1,
+                }),
+            };
+            serde_json::to_value(proof_file).unwrap()
+        })
+        .collect()
+}
+
+fn schema() -> Value {
+    serde_json::to_value(proof_file_schema()).unwrap()
+}
+
+fn keys(object: &Value) -> BTreeSet<String> {
+    object.as_object().unwrap().keys().cloned().collect()
+}
+
+#[test]
+fn proof_files_validate_against_the_schema() {
+    let schema = schema();
+    for proof_file in proof_files() {
+        assert!(jsonschema::is_valid(&schema, &proof_file), "{}", proof_file);
+    }
+}
+
+#[test]
+fn schema_lists_exactly_the_serialized_fields() {
+    let schema = schema();
+    let proof_file = &proof_files()[0];
+    let definition = |name: &str| keys(&schema["definitions"][name]["properties"]);
+    assert_eq!(keys(&schema["properties"]), keys(proof_file));
+    assert_eq!(definition("DiffOutput"), keys(&proof_file["output"]));
+    assert_eq!(definition("DiffLine"), keys(&proof_file["output"]["diff_lines"][0]));
+}
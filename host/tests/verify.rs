@@ -0,0 +1,97 @@
+mod common;
+
+use common::text_input;
+use methods::METHOD_ID;
+use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+use zkdiff::{
+    compute_diff, create_proof_hash, method_id_hex, proof_hash_mismatch, verify_proof_file,
+    DiffOptions, DiffOutput, ProofFile, ReceiptStatus, ZkDiffError,
+};
+
+fn proof_file(method_id: String) -> ProofFile {
+    let (a, b) = ("one\ntwo\nthree\n", "one\n2\nthree\nfour\n");
+    ProofFile {
+        verified: false,
+        output: compute_diff(&text_input(a, b, DiffOptions::default())),
+        method_id,
+        proof_generated: false,
+        zkdiff_version: None,
+        risc0_version: None,
+        receipt: None,
+        metrics: None,
+    }
+}
+
+/// Receipt whose journal commits `output`. It is fake and never verifies, but its
+/// journal reads like a real one.
+fn fake_receipt(output: &DiffOutput) -> Receipt {
+    let words = risc0_zkvm::serde::to_vec(output).unwrap();
+    let journal: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    let claim = ReceiptClaim::ok(METHOD_ID, journal.clone());
+    Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal)
+}
+
+#[test]
+fn a_dev_proof_passes_every_check_but_the_receipt() {
+    let report = verify_proof_file(&proof_file(method_id_hex(METHOD_ID)));
+    assert!(report.method_id_matches);
+    assert_eq!(report.receipt, ReceiptStatus::Missing);
+    assert_eq!(report.proof_hash_mismatch, None);
+    assert_eq!((report.stats.replaced, report.stats.inserted, report.diff_lines), (1, 1, 2));
+    assert!(!report.passed());
+}
+
+#[test]
+fn every_failed_check_is_reported() {
+    let mut proof_file = proof_file("00".repeat(32));
+    proof_file.output.edit_distance += 1;
+    let report = verify_proof_file(&proof_file);
+    assert!(!report.method_id_matches);
+    assert_eq!(report.method_id, "00".repeat(32));
+    assert_eq!(report.proof_hash_mismatch.as_deref(), Some("the diff"));
+    assert_eq!(report.receipt, ReceiptStatus::Missing);
+}
+
+#[test]
+fn check_fails_with_the_first_failed_check() {
+    let mut proof_file = proof_file(method_id_hex(METHOD_ID));
+    let error = verify_proof_file(&proof_file).check().unwrap_err();
+    assert!(matches!(error, ZkDiffError::Verify(_)), "{:?}", error);
+
+    proof_file.output.edit_distance += 1;
+    let error = verify_proof_file(&proof_file).check().unwrap_err();
+    assert!(matches!(&error, ZkDiffError::ProofHashMismatch(what) if what == "the diff"));
+
+    proof_file.method_id = "00".repeat(32);
+    match verify_proof_file(&proof_file).check().unwrap_err() {
+        ZkDiffError::MethodIdMismatch { expected, found } => {
+            assert_eq!((expected, found), (method_id_hex(METHOD_ID), "00".repeat(32)));
+        }
+        error => panic!("{:?}", error),
+    }
+}
+
+#[test]
+fn a_diff_other_than_the_journal_is_reported() {
+    let mut proof_file = proof_file(method_id_hex(METHOD_ID));
+    proof_file.receipt = Some(fake_receipt(&proof_file.output));
+    assert_eq!(verify_proof_file(&proof_file).journal_mismatch, None);
+
+    // a consistent proof hash doesn't help when the receipt commits another diff
+    proof_file.output.edit_distance += 1;
+    proof_file.output.proof_hash = create_proof_hash(&proof_file.output);
+    let report = verify_proof_file(&proof_file);
+    assert_eq!(report.proof_hash_mismatch, None);
+    assert_eq!(report.journal_mismatch.as_deref(), Some("field `edit_distance` differs"));
+    assert!(!report.passed());
+}
+
+#[test]
+fn a_stored_guest_output_passes_the_proof_hash_check() {
+    // a --json-only output as written, whose hash wasn't computed by this test, so a
+    // change in how the host hashes can't go unnoticed
+    let output: DiffOutput = serde_json::from_str(include_str!("fixtures/json_only.json")).unwrap();
+    let hash = "16dd28a861a9152c9ebe72503ca6443e0e748d9b3fcd1689c47919e15c122018";
+    assert_eq!(hex::encode(output.proof_hash), hash);
+    assert_eq!(proof_hash_mismatch(&output), None);
+}
@@ -1,293 +1,116 @@
 use risc0_zkvm::guest::env;
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct DiffInput {
-    file_a_hash: [u8; 32],
-    file_b_hash: [u8; 32],
-    file_a_content: String,
-    file_b_content: String,
-    redaction_ranges: Vec<RedactionRange>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct RedactionRange {
-    start_line: usize,
-    end_line: usize,
-    operation: DiffOperation,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-enum DiffOperation {
-    Insert,
-    Delete,
-    Replace,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct DiffOutput {
-    file_a_hash: [u8; 32],
-    file_b_hash: [u8; 32],
-    diff_lines: Vec<DiffLine>,
-    proof_hash: [u8; 32],
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct DiffLine {
-    line_number_a: Option<usize>,
-    line_number_b: Option<usize>,
-    operation: DiffOperation,
-    content: Option<String>,
-    redacted_length: Option<usize>,
-}
-
-#[derive(Debug, Clone)]
-struct Edit {
-    operation: EditOp,
-    old_index: usize,
-    new_index: usize,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-enum EditOp {
-    Insert,
-    Delete,
-    Keep,
-}
+use zkdiff_core::{
+    compute_diff, hash_bytes_with, hash_content_with, merkle_root_of_leaves, tree_hash, DiffInput,
+    MAX_TOTAL_LINES,
+};
 
 fn main() {
-    let input: DiffInput = env::read();
-
-    let computed_hash_a = hash_content(&input.file_a_content);
-    let computed_hash_b = hash_content(&input.file_b_content);
-    
-    assert_eq!(input.file_a_hash, computed_hash_a, "File A hash mismatch");
-    assert_eq!(input.file_b_hash, computed_hash_b, "File B hash mismatch");
-
-    let lines_a: Vec<&str> = input.file_a_content.lines().collect();
-    let lines_b: Vec<&str> = input.file_b_content.lines().collect();
+    let mut input: DiffInput = env::read();
 
-    let edits = myers_diff(&lines_a, &lines_b);
+    check_input(&input);
+    input.decode_binary();
+    input.expand_tabs();
 
-    let diff_lines = create_diff_lines(&edits, &lines_a, &lines_b, &input.redaction_ranges);
+    assert!(
+        input.total_lines() <= MAX_TOTAL_LINES,
+        "Input has more than {} lines",
+        MAX_TOTAL_LINES
+    );
 
-    let proof_hash = create_proof_hash(&input, &diff_lines);
-
-    let output = DiffOutput {
-        file_a_hash: input.file_a_hash,
-        file_b_hash: input.file_b_hash,
-        diff_lines,
-        proof_hash,
-    };
+    let output = compute_diff(&input);
 
     env::commit(&output);
 }
 
-fn hash_content(content: &str) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(content.as_bytes());
-    hasher.finalize().into()
-}
-
-fn myers_diff(a: &[&str], b: &[&str]) -> Vec<Edit> {
-    let n = a.len();
-    let m = b.len();
-    let max_d = n + m;
-    
-    let mut v = vec![0i32; 2 * max_d + 1];
-    let offset = max_d as i32;
-    
-    let mut trace = Vec::new();
-    
-    for d in 0..=max_d {
-        trace.push(v.clone());
-        
-        let start_k = -(d as i32);
-        let end_k = d as i32;
-        
-        for k in (start_k..=end_k).step_by(2) {
-            let idx = (k + offset) as usize;
-            
-            let x = if k == start_k || (k != end_k && v[idx - 1] < v[idx + 1]) {
-                v[idx + 1]
-            } else {
-                v[idx - 1] + 1
-            };
-            
-            let mut x_cur = x;
-            let mut y_cur = x - k;
-            
-            while (x_cur as usize) < n && (y_cur as usize) < m && 
-                  a[x_cur as usize] == b[y_cur as usize] {
-                x_cur += 1;
-                y_cur += 1;
-            }
-            
-            v[idx] = x_cur;
-            
-            if (x_cur as usize) >= n && (y_cur as usize) >= m {
-                return backtrack(&trace, a, b, d);
-            }
-        }
-    }
-    
-    Vec::new()
-}
-
-fn backtrack(trace: &[Vec<i32>], a: &[&str], b: &[&str], d: usize) -> Vec<Edit> {
-    let mut edits = Vec::new();
-    let mut x = a.len() as i32;
-    let mut y = b.len() as i32;
-    let max_d = a.len() + b.len();
-    let offset = max_d as i32;
-    
-    for trace_d in (0..=d).rev() {
-        let v = &trace[trace_d];
-        let k = x - y;
-        let idx = (k + offset) as usize;
-        
-        let prev_k = if k == -(trace_d as i32) || 
-                        (k != trace_d as i32 && v[idx - 1] < v[idx + 1]) {
-            k + 1
-        } else {
-            k - 1
-        };
-        
-        let prev_idx = (prev_k + offset) as usize;
-        let prev_x = if trace_d > 0 { v[prev_idx] } else { 0 };
-        let prev_y = prev_x - prev_k;
-        
-        while x > prev_x && y > prev_y {
-            x -= 1;
-            y -= 1;
-            edits.push(Edit {
-                operation: EditOp::Keep,
-                old_index: x as usize,
-                new_index: y as usize,
-            });
-        }
-        
-        if trace_d > 0 {
-            if x > prev_x {
-                x -= 1;
-                edits.push(Edit {
-                    operation: EditOp::Delete,
-                    old_index: x as usize,
-                    new_index: y as usize,
-                });
-            } else if y > prev_y {
-                y -= 1;
-                edits.push(Edit {
-                    operation: EditOp::Insert,
-                    old_index: x as usize,
-                    new_index: y as usize,
-                });
-            }
-        }
+fn check_input(input: &DiffInput) {
+    if let Some(base) = &input.merge_base {
+        // merged as text against a single pair of files only
+        assert!(
+            input.script.is_none() && input.files.is_empty() && !input.options.binary,
+            "Merge base outside single-file text mode"
+        );
+        let hash = hash_content_with(input.options.hash, &base.content);
+        assert_eq!(base.hash, hash, "Merge base hash mismatch");
     }
-    
-    edits.reverse();
-    edits
-}
-
-fn create_diff_lines(
-    edits: &[Edit],
-    lines_a: &[&str],
-    lines_b: &[&str],
-    redaction_ranges: &[RedactionRange],
-) -> Vec<DiffLine> {
-    let mut diff_lines = Vec::new();
-    
-    for edit in edits {
-        match edit.operation {
-            EditOp::Keep => {
-                continue;
-            }
-            EditOp::Delete => {
-                let line_content = lines_a[edit.old_index];
-                let should_redact = should_redact_line(
-                    edit.old_index,
-                    &DiffOperation::Delete,
-                    redaction_ranges,
-                );
-                
-                diff_lines.push(DiffLine {
-                    line_number_a: Some(edit.old_index + 1),
-                    line_number_b: None,
-                    operation: DiffOperation::Delete,
-                    content: if should_redact { None } else { Some(line_content.to_string()) },
-                    redacted_length: if should_redact { Some(line_content.len()) } else { None },
-                });
-            }
-            EditOp::Insert => {
-                let line_content = lines_b[edit.new_index];
-                let should_redact = should_redact_line(
-                    edit.new_index,
-                    &DiffOperation::Insert,
-                    redaction_ranges,
-                );
-                
-                diff_lines.push(DiffLine {
-                    line_number_a: None,
-                    line_number_b: Some(edit.new_index + 1),
-                    operation: DiffOperation::Insert,
-                    content: if should_redact { None } else { Some(line_content.to_string()) },
-                    redacted_length: if should_redact { Some(line_content.len()) } else { None },
-                });
-            }
+    if let Some(script) = &input.script {
+        // the whole point is proving without the contents, so none may be sent
+        assert!(
+            input.files.is_empty()
+                && input.file_a_content.is_empty()
+                && input.file_b_content.is_empty()
+                && input.file_a_bytes.is_empty()
+                && input.file_b_bytes.is_empty(),
+            "Script input with content"
+        );
+        let root_a = merkle_root_of_leaves(&script.leaves_a);
+        let root_b = merkle_root_of_leaves(&script.leaves_b);
+        assert_eq!(input.file_a_hash, root_a, "Script A root mismatch");
+        assert_eq!(input.file_b_hash, root_b, "Script B root mismatch");
+    } else if input.files.is_empty() {
+        check_hashes(input, "File");
+    } else {
+        assert!(
+            input.file_a_content.is_empty()
+                && input.file_b_content.is_empty()
+                && input.file_a_bytes.is_empty()
+                && input.file_b_bytes.is_empty(),
+            "Directory input with top-level content"
+        );
+        // the output reports the tree's options and the first file's patterns
+        // for every file, so each file must have been diffed with them
+        let first = &input.files[0].input;
+        for file in &input.files {
+            assert!(file.input.files.is_empty(), "Nested directory input");
+            assert!(file.input.options == input.options, "{} options mismatch", file.path);
+            assert!(patterns(&file.input) == patterns(first), "{} patterns mismatch", file.path);
+            assert!(file.in_a || file.in_b, "{} missing from both sides", file.path);
+            // an absent file diffs as empty, so it must not carry content
+            let empty_a = file.input.file_a_content.is_empty() && file.input.file_a_bytes.is_empty();
+            let empty_b = file.input.file_b_content.is_empty() && file.input.file_b_bytes.is_empty();
+            assert!(file.in_a || empty_a, "{} A should be absent", file.path);
+            assert!(file.in_b || empty_b, "{} B should be absent", file.path);
+            check_hashes(&file.input, &file.path);
         }
+        let hash = input.options.hash;
+        assert_eq!(input.file_a_hash, tree_hash(&input.files, false, hash), "Tree A hash mismatch");
+        assert_eq!(input.file_b_hash, tree_hash(&input.files, true, hash), "Tree B hash mismatch");
     }
-    
-    diff_lines
 }
 
-fn should_redact_line(
-    line_number: usize,
-    operation: &DiffOperation,
-    redaction_ranges: &[RedactionRange],
-) -> bool {
-    for range in redaction_ranges {
-        if range.operation == *operation &&
-           line_number >= range.start_line.saturating_sub(1) &&
-           line_number < range.end_line {
-            return true;
-        }
-    }
-    false
+/// The redaction, hunk header, ignore and anchor patterns the host matched the
+/// lines of this input against.
+fn patterns(input: &DiffInput) -> (Vec<&String>, [Option<&String>; 3]) {
+    let redactions = input.pattern_redactions.iter().map(|r| &r.pattern).collect();
+    let hunk = input.hunk_anchors.as_ref().map(|anchors| &anchors.pattern);
+    let ignore = input.ignored_lines.as_ref().map(|ignored| &ignored.pattern);
+    let anchor = input.sync_anchors.as_ref().map(|anchors| &anchors.pattern);
+    (redactions, [hunk, ignore, anchor])
 }
 
-fn create_proof_hash(input: &DiffInput, diff_lines: &[DiffLine]) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(&input.file_a_hash);
-    hasher.update(&input.file_b_hash);
-    
-    for line in diff_lines {
-        hasher.update([match line.operation {
-            DiffOperation::Insert => 1u8,
-            DiffOperation::Delete => 2u8,
-            DiffOperation::Replace => 3u8,
-        }]);
-        
-        if let Some(line_a) = line.line_number_a {
-            hasher.update(&line_a.to_le_bytes());
-        }
-        if let Some(line_b) = line.line_number_b {
-            hasher.update(&line_b.to_le_bytes());
-        }
-        
-        match (&line.content, line.redacted_length) {
-            (Some(content), _) => {
-                hasher.update(b"content:");
-                hasher.update(content.as_bytes());
-            }
-            (None, Some(length)) => {
-                hasher.update(b"redacted:");
-                hasher.update(&length.to_le_bytes());
-            }
-            _ => {}
-        }
-    }
+/// Checks the file hashes against whichever form the content was sent in: the raw
+/// bytes in binary mode, the text otherwise, with the chosen hash function. The
+/// other form must be empty.
+fn check_hashes(input: &DiffInput, name: &str) {
+    let hash = input.options.hash;
+    let (computed_hash_a, computed_hash_b) = if input.options.binary {
+        assert!(
+            input.file_a_content.is_empty() && input.file_b_content.is_empty(),
+            "{} content sent as text in binary mode",
+            name
+        );
+        (hash_bytes_with(hash, &input.file_a_bytes), hash_bytes_with(hash, &input.file_b_bytes))
+    } else {
+        assert!(
+            input.file_a_bytes.is_empty() && input.file_b_bytes.is_empty(),
+            "{} content sent as bytes outside binary mode",
+            name
+        );
+        (
+            hash_content_with(hash, &input.file_a_content),
+            hash_content_with(hash, &input.file_b_content),
+        )
+    };
     
-    hasher.finalize().into()
-}
\ No newline at end of file
+    assert_eq!(input.file_a_hash, computed_hash_a, "{} A hash mismatch", name);
+    assert_eq!(input.file_b_hash, computed_hash_b, "{} B hash mismatch", name);
+}